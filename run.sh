@@ -41,7 +41,7 @@ key="$1"
 #     round_size: Option<u128>,
 
 #     /// number of threads to use
-#     /// default is 16
+#     /// default is the number of available cores
 #     #[arg(long)]
 #     num_threads: Option<usize>,
 # }
@@ -0,0 +1,357 @@
+use ethers_core::types::{Address, U256};
+use rayon::prelude::*;
+
+use crate::derive::{AddressDeriver, Scheme};
+use crate::score::{ScoreMode, TieBreak};
+use crate::search::AddressSalt;
+use crate::utils::{bytes32, compute_batch, ADDRESS_NIBBLES};
+
+/// Salts computed at a time, bounds the memory used by large samples
+const ANALYZE_CHUNK_SIZE: u64 = 1 << 16;
+
+/// Salts per parallel task within a chunk
+const ANALYZE_TASK_SIZE: usize = 1024;
+
+/// How often each nibble value, 0 through f, appeared at each of the 40 positions
+pub type NibbleFrequencies = [[u64; 16]; ADDRESS_NIBBLES];
+
+/// Count the nibble values at each position of the addresses of consecutive salts,
+/// to check the addresses are as uniform as keccak256 output should be
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address
+/// * `init_code_hash` - The init code hash as a number
+/// * `from_salt_n` - The first salt of the sample
+/// * `sample_size` - The number of salts to scan
+/// * `salt_step` - The distance between consecutive salts
+///
+/// # Returns
+/// * The count of each nibble value at each position, every position sums to `sample_size`
+pub fn nibble_frequencies(
+    factory: Address,
+    init_code_hash: U256,
+    from_salt_n: U256,
+    sample_size: u64,
+    salt_step: u64,
+) -> NibbleFrequencies {
+    let step = U256::from(salt_step);
+    let mut frequencies = [[0; 16]; ADDRESS_NIBBLES];
+    let mut scanned = 0;
+    while scanned < sample_size {
+        let chunk_len = (sample_size - scanned).min(ANALYZE_CHUNK_SIZE);
+        let salts: Vec<U256> = (scanned..scanned + chunk_len)
+            .map(|i| from_salt_n + U256::from(i) * step)
+            .collect();
+        let chunk = salts
+            .par_chunks(ANALYZE_TASK_SIZE)
+            .map(|salts| {
+                let mut counts = [[0; 16]; ADDRESS_NIBBLES];
+                for address in compute_batch(factory, init_code_hash, salts) {
+                    for (i, byte) in address.as_bytes().iter().enumerate() {
+                        counts[2 * i][(byte >> 4) as usize] += 1;
+                        counts[2 * i + 1][(byte & 0x0f) as usize] += 1;
+                    }
+                }
+                counts
+            })
+            .reduce(|| [[0; 16]; ADDRESS_NIBBLES], add_frequencies);
+        frequencies = add_frequencies(frequencies, chunk);
+        scanned += chunk_len;
+    }
+    return frequencies;
+}
+
+fn add_frequencies(mut a: NibbleFrequencies, b: NibbleFrequencies) -> NibbleFrequencies {
+    for (row_a, row_b) in a.iter_mut().zip(b.iter()) {
+        for (count_a, count_b) in row_a.iter_mut().zip(row_b.iter()) {
+            *count_a += count_b;
+        }
+    }
+    a
+}
+
+/// The distance between the salts of a sparse sample, spreading them evenly over a window
+///
+/// # Arguments
+/// * `from_salt_n` - The first salt of the window
+/// * `window` - The number of salts in the window, `None` for every salt from the first on
+/// * `sample_size` - The number of salts to sample
+///
+/// # Returns
+/// * The stride, the last salt sampled is at most a stride from the end of the window,
+///   or `None` if the window has fewer salts than the sample
+pub fn sparse_stride(from_salt_n: U256, window: Option<U256>, sample_size: u64) -> Option<U256> {
+    // U256::MAX - from + 1 would overflow for the whole space, one salt less doesn't matter
+    let window = window.unwrap_or(U256::MAX - from_salt_n);
+    let stride = window / U256::from(sample_size);
+    if stride.is_zero() {
+        return None;
+    }
+    Some(stride)
+}
+
+/// The result of a sparse sample
+pub struct SparseSample {
+    /// The best address sampled
+    pub best: AddressSalt,
+    /// The number of addresses sampled with each score, indexed by score
+    pub scores: Vec<u64>,
+}
+
+/// Compute the addresses of salts a fixed stride apart, to estimate how rare a score is
+/// under a factory without scanning the whole window
+///
+/// Unlike `--sample`, which keeps a random subset of the salts a search scans, the salts
+/// are fixed by the stride so two runs sample the same ones.
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address
+/// * `init_code_hash` - The init code hash as a number
+/// * `from_salt_n` - The first salt of the sample
+/// * `stride` - The distance between consecutive salts, see `sparse_stride`
+/// * `sample_size` - The number of salts to compute, at least 1
+/// * `score_mode` - What makes an address better than another
+/// * `tie_break` - How two addresses with the same score are ordered
+///
+/// # Returns
+/// * The best address and the count of each score, which sum to `sample_size`
+pub fn sparse_sample(
+    factory: Address,
+    init_code_hash: U256,
+    from_salt_n: U256,
+    stride: U256,
+    sample_size: u64,
+    score_mode: ScoreMode,
+    tie_break: TieBreak,
+) -> SparseSample {
+    let mut best: Option<AddressSalt> = None;
+    let mut scores = Vec::new();
+    let mut sampled = 0;
+    while sampled < sample_size {
+        let chunk_len = (sample_size - sampled).min(ANALYZE_CHUNK_SIZE);
+        let salts: Vec<U256> = (sampled..sampled + chunk_len)
+            .map(|i| from_salt_n + U256::from(i) * stride)
+            .collect();
+        let chunk = salts
+            .par_chunks(ANALYZE_TASK_SIZE)
+            .map(|salts| {
+                let mut best: Option<AddressSalt> = None;
+                let mut scores = Vec::new();
+                for (address, salt_n) in compute_batch(factory, init_code_hash, salts)
+                    .into_iter()
+                    .zip(salts.iter())
+                {
+                    let found = AddressSalt {
+                        address,
+                        salt_n: *salt_n,
+                    };
+                    best = better_of(best, Some(found), score_mode, tie_break);
+                    count_score(&mut scores, score_mode.score(address) as usize, 1);
+                }
+                (best, scores)
+            })
+            .reduce(
+                || (None, Vec::new()),
+                |(best_a, mut scores_a), (best_b, scores_b)| {
+                    for (score, count) in scores_b.into_iter().enumerate() {
+                        count_score(&mut scores_a, score, count);
+                    }
+                    (better_of(best_a, best_b, score_mode, tie_break), scores_a)
+                },
+            );
+        best = better_of(best, chunk.0, score_mode, tie_break);
+        for (score, count) in chunk.1.into_iter().enumerate() {
+            count_score(&mut scores, score, count);
+        }
+        sampled += chunk_len;
+    }
+    SparseSample {
+        // a sample has at least one salt
+        best: best.unwrap(),
+        scores,
+    }
+}
+
+/// Compute the address of each salt under several salt derivation schemes at once, keeping
+/// the best of each, to find out which scheme a factory uses from the addresses it gives
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address
+/// * `init_code_hash` - The init code hash as a number
+/// * `from_salt_n` - The first salt to scan
+/// * `sample_size` - The number of salts to scan, at least 1
+/// * `salt_step` - The distance between consecutive salts
+/// * `schemes` - The schemes to compute each address under
+/// * `score_mode` - What makes an address better than another
+/// * `tie_break` - How two addresses with the same score are ordered
+///
+/// # Returns
+/// * The best address of each scheme, in the order of `schemes`
+#[allow(clippy::too_many_arguments)]
+pub fn try_schemes(
+    factory: Address,
+    init_code_hash: U256,
+    from_salt_n: U256,
+    sample_size: u64,
+    salt_step: u64,
+    schemes: &[Scheme],
+    score_mode: ScoreMode,
+    tie_break: TieBreak,
+) -> Vec<AddressSalt> {
+    let step = U256::from(salt_step);
+    let init_code_hash = bytes32(init_code_hash);
+    let merge = |a: Vec<Option<AddressSalt>>, b: Vec<Option<AddressSalt>>| {
+        a.into_iter()
+            .zip(b)
+            .map(|(a, b)| better_of(a, b, score_mode, tie_break))
+            .collect::<Vec<_>>()
+    };
+    let mut best = vec![None; schemes.len()];
+    let mut scanned = 0;
+    while scanned < sample_size {
+        let chunk_len = (sample_size - scanned).min(ANALYZE_CHUNK_SIZE);
+        let salts: Vec<U256> = (scanned..scanned + chunk_len)
+            .map(|i| from_salt_n + U256::from(i) * step)
+            .collect();
+        let chunk = salts
+            .par_chunks(ANALYZE_TASK_SIZE)
+            .map(|salts| {
+                let mut best = vec![None; schemes.len()];
+                for salt_n in salts {
+                    let salt = bytes32(*salt_n);
+                    for (scheme, best) in schemes.iter().zip(best.iter_mut()) {
+                        let found = AddressSalt {
+                            address: scheme.derive(factory, &salt, &init_code_hash),
+                            salt_n: *salt_n,
+                        };
+                        *best = better_of(*best, Some(found), score_mode, tie_break);
+                    }
+                }
+                best
+            })
+            .reduce(|| vec![None; schemes.len()], merge);
+        best = merge(best, chunk);
+        scanned += chunk_len;
+    }
+    // a sample has at least one salt
+    best.into_iter().map(Option::unwrap).collect()
+}
+
+fn better_of(
+    a: Option<AddressSalt>,
+    b: Option<AddressSalt>,
+    score_mode: ScoreMode,
+    tie_break: TieBreak,
+) -> Option<AddressSalt> {
+    match (a, b) {
+        (Some(a), Some(b)) if score_mode.is_better(&b, &a, tie_break) => Some(b),
+        (a, b) => a.or(b),
+    }
+}
+
+fn count_score(scores: &mut Vec<u64>, score: usize, count: u64) {
+    if score >= scores.len() {
+        scores.resize(score + 1, 0);
+    }
+    scores[score] += count;
+}
+
+/// The largest relative deviation of any count from the uniform expectation
+///
+/// # Arguments
+/// * `frequencies` - The counts from `nibble_frequencies`
+///
+/// # Returns
+/// * The deviation as a fraction of the expected count, 0.0 for a perfectly uniform sample
+pub fn max_deviation(frequencies: &NibbleFrequencies) -> f64 {
+    let total: u64 = frequencies[0].iter().sum();
+    let expected = total as f64 / 16.0;
+    if expected == 0.0 {
+        return 0.0;
+    }
+    frequencies
+        .iter()
+        .flatten()
+        .map(|count| (*count as f64 - expected).abs() / expected)
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::create2_address;
+
+    #[test]
+    fn sparse_sample_spreads_n_salts_over_the_window() {
+        let from = U256::from(5000);
+        let window = U256::from(1000);
+        let stride = sparse_stride(from, Some(window), 7).unwrap();
+        assert_eq!(stride, U256::from(142));
+        // the last salt sampled is inside the window and within a stride of its end
+        let last = from + stride * 6;
+        assert!(last < from + window && last + stride >= from + window);
+        assert_eq!(sparse_stride(from, Some(U256::from(6)), 7), None);
+        // the whole space from the first salt on
+        assert_eq!(
+            sparse_stride(U256::zero(), None, 1 << 16),
+            Some(U256::MAX >> 16usize)
+        );
+
+        let factory = Address::repeat_byte(0x11);
+        let init_code_hash = U256::from(0x1234);
+        let sample = sparse_sample(
+            factory,
+            init_code_hash,
+            from,
+            stride,
+            7,
+            ScoreMode::LeadingZeros,
+            TieBreak::default(),
+        );
+        assert_eq!(sample.scores.iter().sum::<u64>(), 7);
+        let expected = (0..7u64)
+            .map(|i| {
+                let salt_n = from + stride * i;
+                AddressSalt {
+                    address: create2_address(factory, salt_n, init_code_hash),
+                    salt_n,
+                }
+            })
+            .min_by_key(|found| found.address)
+            .unwrap();
+        assert_eq!(sample.best, expected);
+    }
+
+    #[test]
+    fn nibbles_are_near_uniform_at_every_position() {
+        let sample_size = 1 << 16;
+        let frequencies = nibble_frequencies(
+            Address::repeat_byte(0x11),
+            U256::from(0x1234),
+            U256::from(7),
+            sample_size,
+            3,
+        );
+        // 4096 expected per value, with a standard deviation of about 62
+        let expected = sample_size / 16;
+        for (position, counts) in frequencies.iter().enumerate() {
+            assert_eq!(
+                counts.iter().sum::<u64>(),
+                sample_size,
+                "position {}",
+                position
+            );
+            for (nibble, count) in counts.iter().enumerate() {
+                assert!(
+                    count.abs_diff(expected) < expected / 10,
+                    "position {} nibble {:x}: {} of {}",
+                    position,
+                    nibble,
+                    count,
+                    sample_size
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,1137 @@
+use std::{fmt, fs, path::Path};
+
+use ethers_core::{
+    types::{Address, U256, U512},
+    utils::keccak256,
+};
+use num_format::{Locale, ToFormattedString};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::SearchError;
+use crate::event::EventFormat;
+use crate::score::{parse_byte, parse_leading_nibble, parse_score, CasedWord, ScoreMode, TieBreak};
+use crate::search::{ScoreConfig, SearchParams, SearchStrategy, Target, ThresholdReturn};
+use crate::utils::{bytes32, fmt_salt, known_factory, parse_address, parse_hex, ADDRESS_NIBBLES};
+
+/// The fully resolved parameters of a run
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The CREATE2 factory, addresses are derived from it
+    pub factory: Address,
+    /// The deployer fixed as the salt prefix, `None` when searching the full salt
+    pub deployer: Option<Address>,
+    /// The number of leading salt bytes fixed to the deployer
+    pub salt_guard_bytes: usize,
+    /// The caller a factory hashes each salt with before CREATE2, see `Create2Deriver`
+    pub sender_salting: Option<Address>,
+    /// The init code hashes to search, the rounds are split evenly between them
+    pub init_code_hashes: Vec<U256>,
+    /// The first salt of the search
+    pub initial_salt_n: U256,
+    /// The score aimed for, the number of leading zeros by default
+    pub zeros: u8,
+    /// What makes an address better than another
+    pub score_mode: ScoreMode,
+    /// Stop as soon as an address with `zeros` leading zeros is found
+    pub stop_at_target: bool,
+    /// Scores reported when the best first reaches them, the search stops at the highest
+    pub milestones: Vec<u32>,
+    /// The score the best must reach for the search to succeed, see `band_outcome`
+    pub min_zeros: Option<u8>,
+    /// Stop as soon as the best reaches this score, there's no use refining it further
+    pub max_zeros: Option<u8>,
+    /// Which address is returned when the search stops at its target
+    pub threshold_return: ThresholdReturn,
+    /// How two addresses with the same score are ordered
+    pub tie_break: TieBreak,
+    pub round_size: u128,
+    pub num_rounds: u128,
+    /// Scan exactly this many addresses in total, `num_rounds` is derived from it when set
+    pub max_attempts: Option<u128>,
+    pub num_threads: usize,
+    pub salt_step: u64,
+    pub histogram: bool,
+    pub profile: bool,
+    /// Keep a uniform random sample of this many scanned addresses
+    pub sample_size: Option<usize>,
+    pub event_format: EventFormat,
+    /// Derive the first salt of each run from this seed rather than at random,
+    /// see `seeded_initial_salt`
+    pub seed: Option<u64>,
+    /// The index of this worker among those sharing the seed
+    pub seed_index: u32,
+    /// Keep salts out of the logs, see `Event::masked`
+    pub mask_salts: bool,
+}
+
+impl Config {
+    /// The number of rounds each init code hash gets out of the shared budget
+    pub fn rounds_per_hash(&self) -> u128 {
+        match self.attempts_per_hash() {
+            // the last round is a partial one
+            Some(attempts) => attempts.div_ceil(self.round_size),
+            None => (self.num_rounds / self.init_code_hashes.len() as u128).max(1),
+        }
+    }
+
+    /// The number of attempts each init code hash gets out of `max_attempts`, if set
+    pub fn attempts_per_hash(&self) -> Option<u128> {
+        self.max_attempts
+            .map(|max_attempts| (max_attempts / self.init_code_hashes.len() as u128).max(1))
+    }
+
+    /// The total number of addresses the run will compute
+    pub fn total_attempts(&self) -> u128 {
+        let attempts_per_hash = self
+            .attempts_per_hash()
+            .unwrap_or(self.round_size * self.rounds_per_hash());
+        attempts_per_hash * self.init_code_hashes.len() as u128
+    }
+
+    /// The search parameters for one of the init code hashes
+    ///
+    /// # Arguments
+    /// * `init_code_hash` - The init code hash to search
+    ///
+    /// # Returns
+    /// * The parameters to pass to `Searcher::search`
+    pub fn search_params(&self, init_code_hash: U256) -> SearchParams {
+        let target = Target {
+            factory: self.factory,
+            init_code_hash,
+        };
+        let score = ScoreConfig {
+            score_mode: self.score_mode,
+            // the search stops at whichever comes first of the target and the last milestone
+            target_score: self
+                .stop_at_target
+                .then_some(self.zeros as u32)
+                .into_iter()
+                .chain(self.milestones.iter().max().copied())
+                .chain(self.max_zeros.map(u32::from))
+                .min(),
+            threshold_return: self.threshold_return,
+            tie_break: self.tie_break,
+        };
+        let strategy = SearchStrategy {
+            initial_salt_n: self.initial_salt_n,
+            round_size: self.round_size,
+            num_rounds: self.rounds_per_hash(),
+            max_attempts: self.attempts_per_hash(),
+            salt_step: self.salt_step,
+            histogram: self.histogram,
+            profile: self.profile,
+            sample_size: self.sample_size,
+            event_format: self.event_format,
+        };
+        SearchParams::new(target, score, strategy)
+    }
+
+    /// Where a score falls in the acceptance band of `min_zeros` and `max_zeros`
+    ///
+    /// # Arguments
+    /// * `score` - The score of the best address found
+    ///
+    /// # Returns
+    /// * The outcome, `None` if neither end of the band is set
+    pub fn band_outcome(&self, score: u32) -> Option<BandOutcome> {
+        if self.min_zeros.is_none() && self.max_zeros.is_none() {
+            return None;
+        }
+        if score < self.min_zeros.unwrap_or(0) as u32 {
+            return Some(BandOutcome::BelowFloor);
+        }
+        match self.max_zeros {
+            Some(max_zeros) if score >= max_zeros as u32 => Some(BandOutcome::ReachedCap),
+            _ => Some(BandOutcome::WithinBand),
+        }
+    }
+
+    /// Whether a search succeeded: its best is in the band if one is set, and meets the
+    /// target otherwise
+    ///
+    /// # Arguments
+    /// * `score` - The score of the best address found
+    pub fn succeeded(&self, score: u32) -> bool {
+        match self.band_outcome(score) {
+            Some(outcome) => outcome != BandOutcome::BelowFloor,
+            None => score >= self.zeros as u32,
+        }
+    }
+
+    /// The first salt of a run of a search restarted away from the last one, seeded
+    /// with `seed` if set and random otherwise
+    ///
+    /// # Arguments
+    /// * `span` - The distance the run covers from its first salt, see `SearchParams::span`
+    /// * `run` - The index of the run, 0 for the first
+    ///
+    /// # Returns
+    /// * The initial salt as a number
+    pub fn run_initial_salt(&self, span: U256, run: u32) -> U256 {
+        match self.seed {
+            Some(seed) => seeded_initial_salt(
+                self.deployer,
+                self.salt_guard_bytes,
+                span,
+                seed,
+                seed_run_index(self.seed_index, run),
+            ),
+            None => random_initial_salt(
+                self.deployer,
+                self.salt_guard_bytes,
+                span,
+                &mut rand::thread_rng(),
+            ),
+        }
+    }
+}
+
+impl Config {
+    /// The parameters which decide the salts searched and how addresses are scored,
+    /// one `key=value` line each in a fixed order
+    ///
+    /// Threads, logging and reporting options are left out as they don't change the result.
+    ///
+    /// # Returns
+    /// * The canonical text, equal for two configs running the same search
+    pub fn canonical(&self) -> String {
+        let deployer = match self.deployer {
+            Some(deployer) => format!("{:?}", deployer),
+            None => "none".to_string(),
+        };
+        let init_code_hashes: Vec<String> = self
+            .init_code_hashes
+            .iter()
+            .map(|hash| format!("0x{}", hex::encode(bytes32(*hash))))
+            .collect();
+        let score = match self.score_mode {
+            ScoreMode::LeadingZeros => "leading-zeros".to_string(),
+            ScoreMode::LeadingNibble(Some(nibble)) => format!("leading-nibble-{:x}", nibble),
+            ScoreMode::LeadingNibble(None) => "leading-nibble-any".to_string(),
+            ScoreMode::TotalZeroBytes => "total-zero-bytes".to_string(),
+            ScoreMode::ByteCount(byte) => format!("byte-count-{:02x}", byte),
+            ScoreMode::ClosestTo(target) => format!("closest-to-{:?}", target),
+            ScoreMode::TrailingThenLeading => "trailing-then-leading".to_string(),
+            ScoreMode::ChecksumUppercase => "checksum-uppercase".to_string(),
+            ScoreMode::ChecksumWord(word) => format!("checksum-word-{}", word),
+        };
+        let max_attempts = match self.max_attempts {
+            Some(max_attempts) => max_attempts.to_string(),
+            None => "none".to_string(),
+        };
+
+        [
+            format!("factory={:?}", self.factory),
+            format!("deployer={}", deployer),
+            format!("salt_guard_bytes={}", self.salt_guard_bytes),
+            format!("init_code_hashes={}", init_code_hashes.join(",")),
+            format!(
+                "initial_salt=0x{}",
+                hex::encode(bytes32(self.initial_salt_n))
+            ),
+            format!("score={}", score),
+            format!("zeros={}", self.zeros),
+            format!("stop_at_target={}", self.stop_at_target),
+            format!("milestones={:?}", self.milestones),
+            format!("round_size={}", self.round_size),
+            format!("num_rounds={}", self.num_rounds),
+            format!("max_attempts={}", max_attempts),
+            format!("salt_step={}", self.salt_step),
+        ]
+        .into_iter()
+        // only when set, so the fingerprints of runs without it are unchanged
+        .chain(
+            self.sender_salting
+                .map(|sender| format!("sender_salting={:?}", sender)),
+        )
+        .chain(
+            self.max_zeros
+                .map(|max_zeros| format!("max_zeros={}", max_zeros)),
+        )
+        // the defaults are left out for the same reason
+        .chain(
+            (self.tie_break != TieBreak::default())
+                .then(|| format!("tie_break={}", self.tie_break)),
+        )
+        .chain(
+            (self.threshold_return != ThresholdReturn::default())
+                .then(|| format!("threshold_return={}", self.threshold_return)),
+        )
+        // the seed picks the first salt of every restarted run, not only the first
+        .chain(
+            self.seed
+                .map(|seed| format!("seed={}\nseed_index={}", seed, self.seed_index)),
+        )
+        .collect::<Vec<String>>()
+        .join("\n")
+    }
+
+    /// The keccak256 of `canonical`, so a run can be matched to its parameters
+    pub fn fingerprint(&self) -> [u8; 32] {
+        keccak256(self.canonical())
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Configuration:")?;
+        writeln!(f, "  factory:        {:?}", self.factory)?;
+        match self.deployer {
+            Some(deployer) if self.salt_guard_bytes == DEFAULT_SALT_GUARD_BYTES => {
+                writeln!(f, "  salt mode:      deployer prefix {:?}", deployer)?
+            }
+            Some(deployer) => writeln!(
+                f,
+                "  salt mode:      deployer prefix {:?}, {} guard bytes",
+                deployer, self.salt_guard_bytes
+            )?,
+            None => writeln!(f, "  salt mode:      full salt")?,
+        }
+        if let Some(sender) = self.sender_salting {
+            writeln!(f, "  sender salting: {:?}", sender)?;
+        }
+        for init_code_hash in &self.init_code_hashes {
+            writeln!(
+                f,
+                "  init code hash: 0x{}",
+                hex::encode(bytes32(*init_code_hash))
+            )?;
+        }
+        writeln!(
+            f,
+            "  initial salt:   {}",
+            fmt_salt(self.initial_salt_n, self.mask_salts)
+        )?;
+        if let Some(seed) = self.seed {
+            writeln!(f, "  seed:           {}, index {}", seed, self.seed_index)?;
+        }
+        match self.score_mode {
+            ScoreMode::LeadingZeros => writeln!(f, "  score:          leading zeros")?,
+            ScoreMode::LeadingNibble(Some(nibble)) => {
+                writeln!(f, "  score:          leading {:x}s", nibble)?
+            }
+            ScoreMode::LeadingNibble(None) => {
+                writeln!(f, "  score:          leading run of any nibble")?
+            }
+            ScoreMode::TotalZeroBytes => writeln!(f, "  score:          total zero bytes")?,
+            ScoreMode::ByteCount(byte) => {
+                writeln!(f, "  score:          total 0x{:02x} bytes", byte)?
+            }
+            ScoreMode::ClosestTo(target) => {
+                writeln!(f, "  score:          closest to {:?}", target)?
+            }
+            ScoreMode::TrailingThenLeading => {
+                writeln!(f, "  score:          trailing zeros, then leading zeros")?
+            }
+            ScoreMode::ChecksumUppercase => {
+                writeln!(f, "  score:          upper case letters of the checksum")?
+            }
+            ScoreMode::ChecksumWord(word) => {
+                writeln!(f, "  score:          checksummed prefix {}", word)?
+            }
+        }
+        if self.stop_at_target && self.threshold_return == ThresholdReturn::First {
+            writeln!(
+                f,
+                "  target:         {}, stop at the first address reaching it",
+                self.zeros
+            )?;
+        } else if self.stop_at_target {
+            writeln!(f, "  target:         {}, stop when reached", self.zeros)?;
+        } else {
+            writeln!(f, "  target:         {}", self.zeros)?;
+        }
+        if !self.milestones.is_empty() {
+            let milestones: Vec<String> = self.milestones.iter().map(u32::to_string).collect();
+            writeln!(f, "  milestones:     {}", milestones.join(", "))?;
+        }
+        match (self.min_zeros, self.max_zeros) {
+            (Some(min_zeros), Some(max_zeros)) => writeln!(
+                f,
+                "  band:           {} to {}, stop at {}",
+                min_zeros, max_zeros, max_zeros
+            )?,
+            (Some(min_zeros), None) => writeln!(f, "  band:           at least {}", min_zeros)?,
+            (None, Some(max_zeros)) => writeln!(f, "  band:           stop at {}", max_zeros)?,
+            (None, None) => {}
+        }
+        writeln!(
+            f,
+            "  round size:     {}",
+            self.round_size.to_formatted_string(&Locale::en)
+        )?;
+        writeln!(
+            f,
+            "  rounds:         {}",
+            self.num_rounds.to_formatted_string(&Locale::en)
+        )?;
+        writeln!(f, "  salt step:      {}", self.salt_step)?;
+        writeln!(f, "  threads:        {}", self.num_threads)?;
+        write!(
+            f,
+            "  coverage:       {} attempts",
+            self.total_attempts().to_formatted_string(&Locale::en)
+        )
+    }
+}
+
+/// Number of leading salt bytes fixed to the deployer by default, the whole address
+pub const DEFAULT_SALT_GUARD_BYTES: usize = 20;
+
+/// The first salt for a deployer, or zero when searching the full salt
+///
+/// # Arguments
+/// * `deployer` - The deployer to prefix the salt with, if any
+/// * `guard_bytes` - The number of leading salt bytes fixed to the deployer, 0 through 32,
+///   zero padded past the 20 bytes of the address
+///
+/// # Returns
+/// * The initial salt as a number
+pub fn initial_salt(deployer: Option<Address>, guard_bytes: usize) -> U256 {
+    match deployer {
+        // the whole 32 bytes are free, start from zero
+        None => U256::zero(),
+        Some(deployer) => {
+            // the initial salt should start with the guard bytes of the deployer address
+            // 1 byte is 2 characters
+            let guard_chars = 2 * guard_bytes.min(20);
+            let deployer_chars = format!("{:x}", deployer)[..guard_chars].to_string();
+            let initial_salt_hex = format!("{:0<64}", deployer_chars);
+            // leaves a search space of 32 - guard_bytes bytes, 12 bytes or 96 bits by default
+            U256::from_str_radix(&initial_salt_hex, 16).unwrap()
+        }
+    }
+}
+
+/// The number of salts from the initial salt before the deployer prefix would change
+///
+/// # Arguments
+/// * `deployer` - The deployer the salt is prefixed with, if any
+/// * `guard_bytes` - The number of leading salt bytes fixed to the deployer
+///
+/// # Returns
+/// * 2^(8 * (32 - guard_bytes)), or `None` when all 32 bytes are free
+pub fn salt_space(deployer: Option<Address>, guard_bytes: usize) -> Option<U256> {
+    match deployer {
+        Some(_) if guard_bytes > 0 => Some(U256::one() << (8 * (32 - guard_bytes))),
+        _ => None,
+    }
+}
+
+/// The number of attempts which tile a salt window exactly, one per `salt_step`
+///
+/// # Arguments
+/// * `space` - The number of salts in the window, see `salt_space`
+/// * `salt_step` - The distance between consecutive salts searched
+///
+/// # Returns
+/// * The attempts to reach the end of the window, or `None` if they don't fit in a u128
+pub fn window_attempts(space: U256, salt_step: u64) -> Option<u128> {
+    let step = U256::from(salt_step);
+    // the last salt may sit less than a step from the end of the window
+    let attempts = space / step + if (space % step).is_zero() { 0 } else { 1 };
+    if attempts.bits() > 128 {
+        return None;
+    }
+    Some(attempts.as_u128())
+}
+
+/// A random first salt, for restarting a search in a fresh region of the salt space
+///
+/// # Arguments
+/// * `deployer` - The deployer to prefix the salt with, if any
+/// * `guard_bytes` - The number of leading salt bytes fixed to the deployer
+/// * `span` - The distance the search will cover from the first salt, it is kept
+///   inside the salt space so a deployer prefix is never overrun
+/// * `rng` - The source of randomness
+///
+/// # Returns
+/// * The initial salt as a number
+pub fn random_initial_salt(
+    deployer: Option<Address>,
+    guard_bytes: usize,
+    span: U256,
+    rng: &mut impl Rng,
+) -> U256 {
+    let mut bytes = [0u8; 32];
+    rng.fill(&mut bytes);
+    let random = U256::from_big_endian(&bytes);
+    let space = salt_space(deployer, guard_bytes).unwrap_or(U256::MAX);
+    if span >= space {
+        return initial_salt(deployer, guard_bytes);
+    }
+    initial_salt(deployer, guard_bytes) + random % (space - span)
+}
+
+/// Where the best of a search falls against the `--min-zeros` floor and `--max-zeros` cap
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BandOutcome {
+    /// Short of the floor, the search failed
+    BelowFloor,
+    /// At or above the floor but short of the cap, the budget ran out first
+    WithinBand,
+    /// At or above the cap, the search stopped there
+    ReachedCap,
+}
+
+/// The index of a run among all the runs of the workers sharing a seed
+///
+/// # Arguments
+/// * `worker` - The index of the worker, see `--seed-index`
+/// * `run` - The index of the run on that worker
+///
+/// # Returns
+/// * The worker in the high bits and the run in the low 32, so no two coincide
+pub fn seed_run_index(worker: u32, run: u32) -> u128 {
+    ((worker as u128) << 32) | run as u128
+}
+
+/// The first salt of a run of a seeded search, the same for the same seed and index
+///
+/// The salts after the fixed prefix are cut into slots of `span` salts, as many as a run
+/// searches. Run `index` takes slot `(offset + index * step) % slots`, with `offset` and
+/// `step` read from the keccak256 of the seed and `step` made coprime to `slots`, so the
+/// first `slots` indices each take a different slot: the runs never overlap, wherever
+/// their workers are, and still spread over the whole space in an order which looks random.
+///
+/// # Arguments
+/// * `deployer` - The deployer to prefix the salt with, if any
+/// * `guard_bytes` - The number of leading salt bytes fixed to the deployer
+/// * `span` - The distance a run covers from its first salt
+/// * `seed` - The seed shared by the runs
+/// * `index` - The index of the run, see `seed_run_index`
+///
+/// # Returns
+/// * The initial salt as a number, the first salt if the space has room for one run only
+pub fn seeded_initial_salt(
+    deployer: Option<Address>,
+    guard_bytes: usize,
+    span: U256,
+    seed: u64,
+    index: u128,
+) -> U256 {
+    let space = salt_space(deployer, guard_bytes).unwrap_or(U256::MAX);
+    let slots = space / span.max(U256::one());
+    if slots <= U256::one() {
+        return initial_salt(deployer, guard_bytes);
+    }
+    let hash = keccak256(seed.to_be_bytes());
+    let offset = U256::from_big_endian(&hash[..16]) % slots;
+    let mut step = (U256::from_big_endian(&hash[16..]) % slots).max(U256::one());
+    while gcd(step, slots) != U256::one() {
+        step = step % (slots - 1) + 1;
+    }
+    // index * step can overflow 256 bits when there are more than 2^128 slots
+    let stepped = (U512::from(index) * U512::from(step)) % U512::from(slots);
+    let stepped = U256::try_from(stepped).expect("reduced below a 256-bit modulus");
+    // offset + stepped < 2 * slots <= space, neither of which overflows
+    let slot = (offset + stepped) % slots;
+    initial_salt(deployer, guard_bytes) + slot * span
+}
+
+/// The greatest common divisor of two numbers, by Euclid's algorithm
+fn gcd(mut a: U256, mut b: U256) -> U256 {
+    while !b.is_zero() {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// The parameters of a run as stored in a TOML config file
+///
+/// Every field is optional so a file can set only some parameters and leave the
+/// rest to the command line. Addresses and hashes are kept as hex strings, the
+/// same as on the command line.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct ConfigFile {
+    pub factory: Option<String>,
+    pub deployer: Option<String>,
+    pub full_salt: Option<bool>,
+    /// See `--salt-guard-bytes`
+    pub salt_guard_bytes: Option<usize>,
+    /// An address, see `--sender-salting`
+    pub sender_salting: Option<String>,
+    pub init_code_hashes: Option<Vec<String>>,
+    pub zeros: Option<u8>,
+    /// A hex digit or "any", see `--leading-nibble`
+    pub leading_nibble: Option<String>,
+    /// See `--score`
+    pub score: Option<String>,
+    /// Hex characters, case included, see `--checksum-word`
+    pub checksum_word: Option<String>,
+    /// Two hex digits, see `--maximize-byte`
+    pub maximize_byte: Option<String>,
+    /// An address, see `--closest-to`
+    pub closest_to: Option<String>,
+    pub stop_at_target: Option<bool>,
+    /// See `--milestones`
+    pub milestones: Option<Vec<u32>>,
+    /// "first" or "round-best", see `--threshold-return`
+    pub threshold_return: Option<String>,
+    /// "lowest-address" or "lowest-salt", see `--tie-break`
+    pub tie_break: Option<String>,
+    /// See `--min-zeros`
+    pub min_zeros: Option<u8>,
+    /// See `--max-zeros`
+    pub max_zeros: Option<u8>,
+    // TOML integers are 64-bit
+    pub round_size: Option<u64>,
+    pub num_rounds: Option<u64>,
+    /// Takes precedence over `num_rounds`, see `--max-attempts`
+    pub max_attempts: Option<u64>,
+    pub num_threads: Option<usize>,
+    pub salt_step: Option<u64>,
+    /// See `--seed`
+    pub seed: Option<u64>,
+    /// See `--seed-index`, needs `seed`
+    pub seed_index: Option<u32>,
+    pub histogram: Option<bool>,
+    pub profile: Option<bool>,
+    /// See `--sample`
+    pub sample: Option<usize>,
+    /// "human" or "json", see `--log-format`
+    pub log_format: Option<String>,
+    /// See `--mask-salts`
+    pub mask_salts: Option<bool>,
+}
+
+impl ConfigFile {
+    /// The keys a config file may set, one per field
+    pub const KEYS: [&'static str; 30] = [
+        "factory",
+        "deployer",
+        "full_salt",
+        "salt_guard_bytes",
+        "sender_salting",
+        "init_code_hashes",
+        "zeros",
+        "leading_nibble",
+        "score",
+        "checksum_word",
+        "maximize_byte",
+        "closest_to",
+        "stop_at_target",
+        "milestones",
+        "threshold_return",
+        "tie_break",
+        "min_zeros",
+        "max_zeros",
+        "round_size",
+        "num_rounds",
+        "max_attempts",
+        "num_threads",
+        "salt_step",
+        "seed",
+        "seed_index",
+        "histogram",
+        "profile",
+        "sample",
+        "log_format",
+        "mask_salts",
+    ];
+
+    /// Read and validate a config file
+    ///
+    /// # Arguments
+    /// * `path` - The path of the TOML file
+    ///
+    /// # Returns
+    /// * The parameters set in the file, or every problem found in it
+    pub fn load(path: &Path) -> Result<Self, SearchError> {
+        let contents = fs::read_to_string(path).map_err(SearchError::Io)?;
+        Self::parse(&contents).map_err(|errors| {
+            SearchError::ConfigFile(format!("{}: {}", path.display(), errors.join("; ")))
+        })
+    }
+
+    /// Parse and validate the contents of a config file
+    ///
+    /// # Arguments
+    /// * `contents` - The TOML text
+    ///
+    /// # Returns
+    /// * The parameters set, or every problem found rather than only the first
+    pub fn parse(contents: &str) -> Result<Self, Vec<String>> {
+        let table: toml::Table = toml::from_str(contents).map_err(|e| vec![e.to_string()])?;
+        // serde ignores unknown keys, which hides typos, so they are checked by hand
+        let mut errors: Vec<String> = table
+            .keys()
+            .filter(|key| !Self::KEYS.contains(&key.as_str()))
+            .map(|key| format!("unknown key {}", key))
+            .collect();
+        let file: Self = match table.try_into() {
+            Ok(file) => file,
+            Err(e) => {
+                errors.push(e.to_string());
+                return Err(errors);
+            }
+        };
+        errors.extend(file.validate());
+        if errors.is_empty() {
+            Ok(file)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check the values the types alone don't constrain
+    ///
+    /// # Returns
+    /// * A description of each invalid value, empty if there are none
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for (key, address) in [
+            ("factory", &self.factory),
+            ("deployer", &self.deployer),
+            ("sender_salting", &self.sender_salting),
+        ] {
+            if let Some(address) = address {
+                if key == "factory" && known_factory(address).is_some() {
+                    continue;
+                }
+                if let Err(e) = parse_address(key, address) {
+                    errors.push(e.to_string());
+                }
+            }
+        }
+        if let Some(guard_bytes) = self.salt_guard_bytes {
+            if guard_bytes > 32 {
+                errors.push(format!(
+                    "salt_guard_bytes {} is more than the 32 bytes of a salt",
+                    guard_bytes
+                ));
+            }
+        }
+        if self.full_salt == Some(true) && self.deployer.is_some() {
+            errors.push("deployer and full_salt = true are mutually exclusive".to_string());
+        }
+        for hash in self.init_code_hashes.iter().flatten() {
+            match parse_hex("init_code_hashes", hash) {
+                Ok(bytes) if bytes.len() != 32 => errors.push(format!(
+                    "init_code_hashes {}: expected 32 bytes, got {}",
+                    hash,
+                    bytes.len()
+                )),
+                Ok(_) => {}
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        if let Some(zeros) = self.zeros {
+            if zeros as usize > ADDRESS_NIBBLES {
+                errors.push(format!(
+                    "zeros {} is more than the {} nibbles of an address",
+                    zeros, ADDRESS_NIBBLES
+                ));
+            }
+        }
+        if let (Some(min_zeros), Some(max_zeros)) = (self.min_zeros, self.max_zeros) {
+            if min_zeros > max_zeros {
+                errors.push(format!(
+                    "min_zeros {} is above max_zeros {}",
+                    min_zeros, max_zeros
+                ));
+            }
+        }
+        if let Some(threshold_return) = &self.threshold_return {
+            if let Err(e) = threshold_return.parse::<ThresholdReturn>() {
+                errors.push(format!("threshold_return: {}", e));
+            }
+        }
+        if let Some(tie_break) = &self.tie_break {
+            if let Err(e) = tie_break.parse::<TieBreak>() {
+                errors.push(format!("tie_break: {}", e));
+            }
+        }
+        if self.seed_index.is_some() && self.seed.is_none() {
+            errors.push("seed_index needs a seed".to_string());
+        }
+        if let Some(nibble) = &self.leading_nibble {
+            if let Err(e) = parse_leading_nibble(nibble) {
+                errors.push(format!("leading_nibble: {}", e));
+            }
+        }
+        if let Some(score) = &self.score {
+            if let Err(e) = parse_score(score) {
+                errors.push(format!("score: {}", e));
+            }
+        }
+        if let Some(word) = &self.checksum_word {
+            if let Err(e) = word.parse::<CasedWord>() {
+                errors.push(format!("checksum_word: {}", e));
+            }
+        }
+        if let Some(byte) = &self.maximize_byte {
+            if let Err(e) = parse_byte(byte) {
+                errors.push(format!("maximize_byte: {}", e));
+            }
+        }
+        if let Some(target) = &self.closest_to {
+            if let Err(e) = parse_address("closest_to", target) {
+                errors.push(format!("closest_to: {}", e));
+            }
+        }
+        // each selects the score mode
+        let score_keys: Vec<&str> = [
+            ("leading_nibble", self.leading_nibble.is_some()),
+            ("score", self.score.is_some()),
+            ("checksum_word", self.checksum_word.is_some()),
+            ("maximize_byte", self.maximize_byte.is_some()),
+            ("closest_to", self.closest_to.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(key, set)| set.then_some(key))
+        .collect();
+        if score_keys.len() > 1 {
+            errors.push(format!(
+                "{} are mutually exclusive",
+                score_keys.join(" and ")
+            ));
+        }
+        let counts = [
+            ("round_size", self.round_size),
+            ("num_rounds", self.num_rounds),
+            ("max_attempts", self.max_attempts),
+            ("num_threads", self.num_threads.map(|n| n as u64)),
+            ("salt_step", self.salt_step),
+            ("sample", self.sample.map(|n| n as u64)),
+        ];
+        for (key, count) in counts {
+            if count == Some(0) {
+                errors.push(format!("{} must be at least 1", key));
+            }
+        }
+        if let Some(format) = &self.log_format {
+            if let Err(e) = format.parse::<EventFormat>() {
+                errors.push(format!("log_format: {}", e));
+            }
+        }
+        return errors;
+    }
+
+    /// Write the config file, replacing it if it exists
+    ///
+    /// # Arguments
+    /// * `path` - The path of the TOML file
+    pub fn save(&self, path: &Path) -> Result<(), SearchError> {
+        let contents = toml::to_string(self)
+            .map_err(|e| SearchError::ConfigFile(format!("{}: {}", path.display(), e)))?;
+        fs::write(path, contents).map_err(SearchError::Io)
+    }
+}
+
+impl TryFrom<&Config> for ConfigFile {
+    type Error = SearchError;
+
+    /// Capture every resolved parameter so loading the file reproduces the same run
+    fn try_from(config: &Config) -> Result<Self, Self::Error> {
+        let to_u64 = |name: &str, n: u128| {
+            u64::try_from(n).map_err(|_| {
+                SearchError::ConfigFile(format!("{} {} does not fit in a TOML integer", name, n))
+            })
+        };
+
+        Ok(Self {
+            factory: Some(format!("{:?}", config.factory)),
+            deployer: config.deployer.map(|deployer| format!("{:?}", deployer)),
+            full_salt: Some(config.deployer.is_none()),
+            salt_guard_bytes: Some(config.salt_guard_bytes),
+            sender_salting: config.sender_salting.map(|sender| format!("{:?}", sender)),
+            init_code_hashes: Some(
+                config
+                    .init_code_hashes
+                    .iter()
+                    .map(|hash| format!("0x{}", hex::encode(bytes32(*hash))))
+                    .collect(),
+            ),
+            zeros: Some(config.zeros),
+            leading_nibble: match config.score_mode {
+                ScoreMode::LeadingNibble(Some(nibble)) => Some(format!("{:x}", nibble)),
+                ScoreMode::LeadingNibble(None) => Some("any".to_string()),
+                _ => None,
+            },
+            score: match config.score_mode {
+                ScoreMode::TotalZeroBytes => Some("total-zero-bytes".to_string()),
+                ScoreMode::TrailingThenLeading => Some("trailing-then-leading".to_string()),
+                ScoreMode::ChecksumUppercase => Some("checksum-uppercase".to_string()),
+                _ => None,
+            },
+            checksum_word: match config.score_mode {
+                ScoreMode::ChecksumWord(word) => Some(word.to_string()),
+                _ => None,
+            },
+            maximize_byte: match config.score_mode {
+                ScoreMode::ByteCount(byte) => Some(format!("{:02x}", byte)),
+                _ => None,
+            },
+            closest_to: match config.score_mode {
+                ScoreMode::ClosestTo(target) => Some(format!("{:?}", target)),
+                _ => None,
+            },
+            stop_at_target: Some(config.stop_at_target),
+            milestones: (!config.milestones.is_empty()).then(|| config.milestones.clone()),
+            threshold_return: Some(config.threshold_return.to_string()),
+            tie_break: Some(config.tie_break.to_string()),
+            min_zeros: config.min_zeros,
+            max_zeros: config.max_zeros,
+            round_size: Some(to_u64("round_size", config.round_size)?),
+            num_rounds: Some(to_u64("num_rounds", config.num_rounds)?),
+            max_attempts: config
+                .max_attempts
+                .map(|n| to_u64("max_attempts", n))
+                .transpose()?,
+            num_threads: Some(config.num_threads),
+            salt_step: Some(config.salt_step),
+            seed: config.seed,
+            seed_index: config.seed.map(|_| config.seed_index),
+            histogram: Some(config.histogram),
+            profile: Some(config.profile),
+            sample: config.sample_size,
+            log_format: Some(config.event_format.to_string()),
+            mask_salts: Some(config.mask_salts),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::search::Searcher;
+
+    /// The single error a config file is rejected with
+    fn rejection(contents: &str) -> String {
+        let errors = ConfigFile::parse(contents).unwrap_err();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        errors[0].clone()
+    }
+
+    #[test]
+    fn a_valid_file_parses() {
+        let file =
+            ConfigFile::parse("zeros = 8\nsalt_guard_bytes = 32\nfull_salt = true\n").unwrap();
+        assert_eq!(file.zeros, Some(8));
+        assert_eq!(file.salt_guard_bytes, Some(32));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert_eq!(rejection("zeroes = 8\n"), "unknown key zeroes");
+    }
+
+    #[test]
+    fn zeros_past_the_address_are_rejected() {
+        assert!(ConfigFile::parse("zeros = 40\n").is_ok());
+        assert!(rejection("zeros = 41\n").starts_with("zeros 41 is more than"));
+    }
+
+    #[test]
+    fn guard_bytes_past_the_salt_are_rejected() {
+        assert!(rejection("salt_guard_bytes = 33\n").starts_with("salt_guard_bytes 33"));
+    }
+
+    #[test]
+    fn full_salt_and_deployer_conflict() {
+        let deployer = format!("deployer = \"{:?}\"\n", Address::repeat_byte(0x11));
+        assert!(ConfigFile::parse(&deployer).is_ok());
+        assert_eq!(
+            rejection(&format!("{}full_salt = true\n", deployer)),
+            "deployer and full_salt = true are mutually exclusive"
+        );
+        assert!(ConfigFile::parse(&format!("{}full_salt = false\n", deployer)).is_ok());
+    }
+
+    /// A config searching the full salt for 6 leading zeros
+    fn config() -> Config {
+        Config {
+            factory: Address::repeat_byte(0xfa),
+            deployer: None,
+            salt_guard_bytes: DEFAULT_SALT_GUARD_BYTES,
+            sender_salting: None,
+            init_code_hashes: vec![U256::from(0x1234)],
+            initial_salt_n: U256::zero(),
+            zeros: 6,
+            score_mode: ScoreMode::LeadingZeros,
+            stop_at_target: true,
+            milestones: vec![],
+            min_zeros: None,
+            max_zeros: None,
+            threshold_return: ThresholdReturn::default(),
+            tie_break: TieBreak::default(),
+            round_size: 1 << 20,
+            num_rounds: 16,
+            max_attempts: None,
+            num_threads: 4,
+            salt_step: 1,
+            histogram: false,
+            profile: false,
+            sample_size: None,
+            event_format: EventFormat::default(),
+            seed: None,
+            seed_index: 0,
+            mask_salts: false,
+        }
+    }
+
+    #[test]
+    fn fingerprint_covers_the_seed() {
+        // reporting options don't change the search
+        let reporting = Config {
+            num_threads: 1,
+            histogram: true,
+            mask_salts: true,
+            ..config()
+        };
+        assert_eq!(config().fingerprint(), reporting.fingerprint());
+
+        let seeded = Config {
+            seed: Some(7),
+            ..config()
+        };
+        assert_eq!(
+            seeded.fingerprint(),
+            Config {
+                seed: Some(7),
+                ..config()
+            }
+            .fingerprint()
+        );
+        assert_ne!(seeded.fingerprint(), config().fingerprint());
+        assert_ne!(
+            seeded.fingerprint(),
+            Config {
+                seed: Some(8),
+                ..config()
+            }
+            .fingerprint()
+        );
+        assert_ne!(
+            seeded.fingerprint(),
+            Config {
+                seed_index: 1,
+                ..seeded.clone()
+            }
+            .fingerprint()
+        );
+    }
+
+    #[test]
+    fn fingerprint_covers_the_tie_break_policy() {
+        let default_policy = Config {
+            tie_break: TieBreak::LowestAddress,
+            threshold_return: ThresholdReturn::RoundBest,
+            ..config()
+        };
+        // the defaults leave the canonical text of older runs unchanged
+        assert_eq!(default_policy.canonical(), config().canonical());
+        assert!(!config().canonical().contains("tie_break"));
+
+        let lowest_salt = Config {
+            tie_break: TieBreak::LowestSalt,
+            ..config()
+        };
+        assert!(lowest_salt.canonical().ends_with("\ntie_break=lowest-salt"));
+        assert_ne!(lowest_salt.fingerprint(), config().fingerprint());
+
+        let first = Config {
+            threshold_return: ThresholdReturn::First,
+            ..config()
+        };
+        assert!(first.canonical().ends_with("\nthreshold_return=first"));
+        assert_ne!(first.fingerprint(), config().fingerprint());
+        assert_ne!(first.fingerprint(), lowest_salt.fingerprint());
+    }
+
+    #[test]
+    fn guard_bytes_leave_a_window_of_the_rest() {
+        let deployer = Address::repeat_byte(0xde);
+        assert_eq!(salt_space(Some(deployer), 0), None);
+        assert_eq!(salt_space(None, DEFAULT_SALT_GUARD_BYTES), None);
+        for guard_bytes in 1..=32 {
+            let free_bytes = 32 - guard_bytes;
+            assert_eq!(
+                salt_space(Some(deployer), guard_bytes),
+                Some(U256::one() << (8 * free_bytes)),
+                "{} guard bytes",
+                guard_bytes
+            );
+            // the deployer up to its 20 bytes, zero padded, then the free window
+            let salt = bytes32(initial_salt(Some(deployer), guard_bytes));
+            let prefix = guard_bytes.min(20);
+            assert!(salt[..prefix].iter().all(|byte| *byte == 0xde));
+            assert!(salt[prefix..].iter().all(|byte| *byte == 0));
+        }
+    }
+
+    #[test]
+    fn seeded_starts_are_distinct_and_reproducible() {
+        use std::collections::HashSet;
+
+        let span = U256::one() << 40usize;
+        let start = |seed: u64, worker: u32, run: u32| {
+            seeded_initial_salt(None, 0, span, seed, seed_run_index(worker, run))
+        };
+        let starts: Vec<U256> = (0..4)
+            .flat_map(|worker| (0..8).map(move |run| start(192, worker, run)))
+            .collect();
+        // whole slots apart, so no two runs overlap
+        assert!(starts.iter().all(|salt| (*salt % span).is_zero()));
+        assert_eq!(starts.iter().collect::<HashSet<_>>().len(), starts.len());
+        assert_eq!(start(192, 3, 7), starts[31]);
+        assert_ne!(start(193, 0, 0), starts[0]);
+
+        // a window of 256 slots, the first 256 runs take each once
+        let deployer = Some(Address::repeat_byte(0xde));
+        let first = initial_salt(deployer, 30);
+        let slots: HashSet<U256> = (0..256)
+            .map(|index| {
+                (seeded_initial_salt(deployer, 30, U256::from(256), 192, index) - first) >> 8usize
+            })
+            .collect();
+        assert_eq!(slots, (0..256u64).map(U256::from).collect());
+    }
+
+    #[test]
+    fn band_has_three_outcomes() {
+        let band = Config {
+            min_zeros: Some(4),
+            max_zeros: Some(8),
+            ..config()
+        };
+        for (score, outcome, succeeded) in [
+            (3, BandOutcome::BelowFloor, false),
+            (4, BandOutcome::WithinBand, true),
+            (7, BandOutcome::WithinBand, true),
+            (8, BandOutcome::ReachedCap, true),
+            (9, BandOutcome::ReachedCap, true),
+        ] {
+            assert_eq!(band.band_outcome(score), Some(outcome), "{}", score);
+            assert_eq!(band.succeeded(score), succeeded, "{}", score);
+        }
+        assert_eq!(config().band_outcome(3), None);
+
+        // each outcome of a search of 4096 addresses, a zero is all but certain in them
+        let search = |min_zeros: u8, max_zeros: u8| {
+            let config = Config {
+                min_zeros: Some(min_zeros),
+                max_zeros: Some(max_zeros),
+                stop_at_target: false,
+                round_size: 256,
+                ..config()
+            };
+            let searcher = Searcher::new(2, false).unwrap();
+            let params = config.search_params(config.init_code_hashes[0]);
+            let found = searcher.search(params).unwrap();
+            let outcome = config.band_outcome(params.score_mode.score(found.address));
+            (outcome.unwrap(), searcher.total_attempts())
+        };
+        assert_eq!(search(30, 40), (BandOutcome::BelowFloor, 4096));
+        assert_eq!(search(1, 40), (BandOutcome::WithinBand, 4096));
+        // the cap stops the search early
+        let (outcome, attempts) = search(1, 1);
+        assert_eq!(outcome, BandOutcome::ReachedCap);
+        assert!(attempts < 4096);
+    }
+}
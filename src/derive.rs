@@ -0,0 +1,224 @@
+use std::fmt;
+
+use ethers_core::{
+    types::Address,
+    utils::{get_create2_address_from_hash, keccak256},
+};
+
+/// How the search turns a salt into an address, so the search logic can be run
+/// without the cost of keccak256, e.g. to check rounds, merging and targets
+pub trait AddressDeriver: Sync {
+    /// Derive the address a factory deploys to
+    ///
+    /// # Arguments
+    /// * `factory` - The CREATE2 factory address
+    /// * `salt` - The 32-byte salt
+    /// * `init_code_hash` - The 32-byte init code hash
+    ///
+    /// # Returns
+    /// * The derived address
+    fn derive(&self, factory: Address, salt: &[u8], init_code_hash: &[u8]) -> Address;
+}
+
+/// The CREATE2 address, keccak256(0xff ++ factory ++ salt ++ init code hash)[12..]
+///
+/// For factories which bind the salt to the caller, the salt searched can be the one
+/// passed to the factory, which hashes it with the sender into the salt given to CREATE2.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Create2Deriver {
+    /// The caller the factory hashes each salt with, `None` when it uses the salt as is
+    sender: Option<Address>,
+}
+
+impl Create2Deriver {
+    /// Derive addresses as a factory computing the CREATE2 salt as
+    /// `keccak256(abi.encodePacked(msg.sender, salt))` does
+    ///
+    /// # Arguments
+    /// * `sender` - The address calling the factory
+    pub fn sender_salted(sender: Address) -> Self {
+        Self {
+            sender: Some(sender),
+        }
+    }
+}
+
+impl AddressDeriver for Create2Deriver {
+    fn derive(&self, factory: Address, salt: &[u8], init_code_hash: &[u8]) -> Address {
+        match self.sender {
+            Some(sender) => {
+                get_create2_address_from_hash(factory, sender_salt(sender, salt), init_code_hash)
+            }
+            None => get_create2_address_from_hash(factory, salt, init_code_hash),
+        }
+    }
+}
+
+/// The salt a factory binding salts to the caller passes to CREATE2
+///
+/// # Arguments
+/// * `sender` - The address calling the factory
+/// * `salt` - The 32-byte salt passed to the factory
+///
+/// # Returns
+/// * keccak256(abi.encodePacked(sender, salt)), of the 20 address bytes and the 32 salt bytes
+pub fn sender_salt(sender: Address, salt: &[u8]) -> [u8; 32] {
+    let mut packed = [0u8; 52];
+    packed[..20].copy_from_slice(sender.as_bytes());
+    packed[20..].copy_from_slice(salt);
+    keccak256(packed)
+}
+
+/// The init code hash of the proxy a CREATE3 factory deploys with CREATE2,
+/// keccak256(0x67363d3d37363d34f03d5260086018f3), the same for Solady and 0xSequence
+pub const CREATE3_PROXY_INIT_CODE_HASH: [u8; 32] = [
+    0x21, 0xc3, 0x5d, 0xbe, 0x1b, 0x34, 0x4a, 0x24, 0x88, 0xcf, 0x33, 0x21, 0xd6, 0xce, 0x54, 0x2f,
+    0x8e, 0x9f, 0x30, 0x55, 0x44, 0xff, 0x09, 0xe4, 0x99, 0x3a, 0x62, 0x31, 0x9a, 0x49, 0x7c, 0x1f,
+];
+
+/// The CREATE3 address: the factory deploys a fixed proxy with CREATE2, which deploys the
+/// contract with CREATE as its first transaction, so the init code hash plays no part
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Create3Deriver;
+
+impl AddressDeriver for Create3Deriver {
+    fn derive(&self, factory: Address, salt: &[u8], _init_code_hash: &[u8]) -> Address {
+        let proxy = get_create2_address_from_hash(factory, salt, CREATE3_PROXY_INIT_CODE_HASH);
+        // the CREATE address of the proxy's nonce 1, keccak256(rlp([proxy, 1]))[12..]
+        let mut rlp = [0u8; 23];
+        rlp[0] = 0xd6;
+        rlp[1] = 0x94;
+        rlp[2..22].copy_from_slice(proxy.as_bytes());
+        rlp[22] = 0x01;
+        Address::from_slice(&keccak256(rlp)[12..])
+    }
+}
+
+/// A way a factory may turn the salt it's given into an address, for `--try-schemes`
+/// when it isn't known which one a factory uses
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    /// CREATE2 with the salt as is
+    Raw,
+    /// CREATE2 with the salt hashed with the caller, see `sender_salt`
+    SenderSalted(Address),
+    /// CREATE3 through a proxy, see `Create3Deriver`
+    Create3,
+}
+
+impl AddressDeriver for Scheme {
+    fn derive(&self, factory: Address, salt: &[u8], init_code_hash: &[u8]) -> Address {
+        match self {
+            Scheme::Raw => Create2Deriver::default().derive(factory, salt, init_code_hash),
+            Scheme::SenderSalted(sender) => {
+                Create2Deriver::sender_salted(*sender).derive(factory, salt, init_code_hash)
+            }
+            Scheme::Create3 => Create3Deriver.derive(factory, salt, init_code_hash),
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scheme::Raw => write!(f, "raw salt"),
+            Scheme::SenderSalted(sender) => write!(f, "keccak256(sender {:?}, salt)", sender),
+            Scheme::Create3 => write!(f, "CREATE3"),
+        }
+    }
+}
+
+/// A deterministic stand-in taking the last 20 bytes of the salt as the address,
+/// so the address of each salt is known without hashing
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SaltDeriver;
+
+impl AddressDeriver for SaltDeriver {
+    fn derive(&self, _factory: Address, salt: &[u8], _init_code_hash: &[u8]) -> Address {
+        Address::from_slice(&salt[salt.len() - 20..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The factory, salt and init code hash of example 5 of EIP-1014
+    fn example() -> (Address, [u8; 32], [u8; 32]) {
+        let factory = "0x00000000000000000000000000000000deadbeef"
+            .parse()
+            .unwrap();
+        let mut salt = [0u8; 32];
+        salt[28..].copy_from_slice(&[0xca, 0xfe, 0xba, 0xbe]);
+        (factory, salt, keccak256([0xde, 0xad, 0xbe, 0xef]))
+    }
+
+    #[test]
+    fn schemes_derive_the_reference_addresses() {
+        let (factory, salt, init_code_hash) = example();
+        let sender = Address::repeat_byte(0x11);
+        let cases = [
+            // the address EIP-1014 gives
+            (Scheme::Raw, "0x60f3f640a8508fC6a86d45DF051962668E1e8AC7"),
+            (
+                Scheme::SenderSalted(sender),
+                "0xdd0439CfDC800E4621C02246DB39cbF46104ed7A",
+            ),
+            (
+                Scheme::Create3,
+                "0xd24D57281f0dbc7EA64A2DeAF977e65Ee97D4367",
+            ),
+        ];
+        for (scheme, expected) in cases {
+            let address = scheme.derive(factory, &salt, &init_code_hash);
+            assert_eq!(address, expected.parse().unwrap(), "{}", scheme);
+        }
+    }
+
+    #[test]
+    fn sender_salting_matches_the_reference_factory_pattern() {
+        let (factory, salt, init_code_hash) = example();
+        let sender = Address::repeat_byte(0x11);
+        // keccak256(abi.encodePacked(sender, salt)), the sender unpadded before the salt
+        let salted = sender_salt(sender, &salt);
+        assert_eq!(
+            hex::encode(salted),
+            "bd3565866c7cb9ca9713784c8d0524a8a110701e442e00cab854013115105adf"
+        );
+        let mut padded = [0u8; 64];
+        padded[12..32].copy_from_slice(sender.as_bytes());
+        padded[32..].copy_from_slice(&salt);
+        assert_ne!(salted, keccak256(padded));
+
+        let reference: Address = "0xdd0439CfDC800E4621C02246DB39cbF46104ed7A"
+            .parse()
+            .unwrap();
+        let deriver = Create2Deriver::sender_salted(sender);
+        assert_eq!(deriver.derive(factory, &salt, &init_code_hash), reference);
+        // the factory hands CREATE2 the salted salt
+        assert_eq!(
+            Create2Deriver::default().derive(factory, &salted, &init_code_hash),
+            reference
+        );
+        let other_sender = Create2Deriver::sender_salted(Address::repeat_byte(0x22));
+        assert_ne!(
+            other_sender.derive(factory, &salt, &init_code_hash),
+            reference
+        );
+    }
+
+    #[test]
+    fn create3_ignores_the_init_code_hash() {
+        let (factory, salt, init_code_hash) = example();
+        assert_eq!(
+            Create3Deriver.derive(factory, &salt, &init_code_hash),
+            Create3Deriver.derive(factory, &salt, &[0u8; 32])
+        );
+        assert_eq!(
+            hex::encode(CREATE3_PROXY_INIT_CODE_HASH),
+            hex::encode(keccak256(
+                hex::decode("67363d3d37363d34f03d5260086018f3").unwrap()
+            ))
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+use rayon::ThreadPoolBuildError;
+
+/// Errors which can occur while setting up or running a search
+#[derive(Debug)]
+pub enum SearchError {
+    /// The requested number of threads cannot be used
+    InvalidThreadCount(usize),
+    /// The thread pool could not be created, even after retrying
+    ThreadPool(ThreadPoolBuildError),
+    /// An argument which should be hex could not be decoded
+    InvalidHex(String),
+    /// The creation bytecode is empty, so there is no contract to deploy
+    EmptyBytecode,
+    /// A required parameter was given neither on the command line nor in the config file
+    MissingArgument(&'static str),
+    /// A parameter has a value outside of its valid range
+    InvalidArgument(String),
+    /// The config file could not be parsed or written
+    ConfigFile(String),
+    /// The ledger could not be parsed or written, or belongs to another campaign
+    Ledger(String),
+    /// Reading or writing a file failed
+    Io(std::io::Error),
+    /// A JSON-RPC request failed or its response could not be parsed
+    Rpc(String),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::InvalidThreadCount(n) => {
+                write!(f, "invalid thread count {}, must be at least 1", n)
+            }
+            SearchError::ThreadPool(e) => write!(f, "failed to create thread pool: {}", e),
+            SearchError::InvalidHex(e) => write!(f, "invalid hex {}", e),
+            SearchError::EmptyBytecode => write!(f, "bytecode is empty"),
+            SearchError::MissingArgument(name) => {
+                write!(
+                    f,
+                    "missing --{}, on the command line, in CREATE2_{} or in the config file",
+                    name,
+                    name.to_uppercase().replace('-', "_")
+                )
+            }
+            SearchError::InvalidArgument(e) => write!(f, "{}", e),
+            SearchError::ConfigFile(e) => write!(f, "invalid config file {}", e),
+            SearchError::Ledger(e) => write!(f, "invalid ledger {}", e),
+            SearchError::Io(e) => write!(f, "{}", e),
+            SearchError::Rpc(e) => write!(f, "rpc request failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
@@ -0,0 +1,685 @@
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+    sync::mpsc::{sync_channel, SyncSender},
+    thread,
+};
+
+use ethers_core::{types::Address, utils::to_checksum};
+use num_format::{Locale, ToFormattedString};
+use serde::Serialize;
+
+use crate::error::SearchError;
+use crate::score::ScoreMode;
+use crate::search::AddressSalt;
+use crate::utils::{
+    bytes32, fmt_dms, leading_zero_nibbles, marginal_time_for_extra_score, MASKED_SALT,
+};
+
+/// How events are printed
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum EventFormat {
+    /// Colored lines for a terminal
+    #[default]
+    Human,
+    /// One JSON object per line, for other programs to parse
+    Json,
+    /// Only the round and best lines, exactly as the first versions printed them,
+    /// for scripts parsing that output
+    Legacy,
+}
+
+impl EventFormat {
+    /// The names accepted by `from_str`
+    pub const NAMES: [&'static str; 3] = ["human", "json", "legacy"];
+}
+
+impl FromStr for EventFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(EventFormat::Human),
+            "json" => Ok(EventFormat::Json),
+            "legacy" => Ok(EventFormat::Legacy),
+            _ => Err(format!(
+                "unknown log format {}, expected one of {}",
+                s,
+                EventFormat::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for EventFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventFormat::Human => write!(f, "human"),
+            EventFormat::Json => write!(f, "json"),
+            EventFormat::Legacy => write!(f, "legacy"),
+        }
+    }
+}
+
+/// An address and its salt as reported in events
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BestRecord {
+    pub address: Address,
+    pub salt: String,
+    pub score: u32,
+    /// The score in words, e.g. "12 zeros"
+    pub description: String,
+    /// How the score was computed, to estimate the next one in its own terms
+    #[serde(skip)]
+    pub score_mode: ScoreMode,
+}
+
+impl BestRecord {
+    pub fn new(best: &AddressSalt, score_mode: ScoreMode) -> Self {
+        Self {
+            address: best.address,
+            salt: format!("0x{}", hex::encode(bytes32(best.salt_n))),
+            score: score_mode.score(best.address),
+            description: score_mode.describe(best.address),
+            score_mode,
+        }
+    }
+}
+
+impl fmt::Display for BestRecord {
+    /// The score, the checksummed address and the full salt, everything needed
+    /// to deploy an interim best without waiting for the end of the run
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} salt {}",
+            self.description,
+            to_checksum(&self.address, None),
+            self.salt
+        )
+    }
+}
+
+/// Something which happened during a search
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// The search is about to start
+    Started {
+        factory: Address,
+        init_code_hash: String,
+        initial_salt: String,
+        round_size: u128,
+        num_rounds: u128,
+    },
+    /// A round finished
+    Heartbeat {
+        round: u128,
+        attempts: u128,
+        elapsed_ms: u128,
+        /// attempts per second
+        rate: f64,
+        best: BestRecord,
+    },
+    /// The attempts so far, rounds still running included, see
+    /// `Searcher::set_progress_interval`
+    Progress {
+        attempts: u128,
+        elapsed_ms: u128,
+        /// attempts per second
+        rate: f64,
+    },
+    /// A round found a better address than any before it
+    NewBest { best: BestRecord },
+    /// A periodic reminder of the best address so far
+    Best { best: BestRecord },
+    /// The best address reached one of the milestones for the first time
+    Milestone { milestone: u32, best: BestRecord },
+    /// The best address scored higher than the best before it, unlike `NewBest` a better
+    /// address with the same score doesn't count, so each score is reported once
+    ScoreReached {
+        score: u32,
+        /// The number of times the score went up in this search, this one included
+        improvements: u64,
+        best: BestRecord,
+    },
+    /// The best address reached the target score, the search is stopping
+    TargetReached { best: BestRecord },
+    /// The search is over
+    Finished {
+        rounds: u128,
+        attempts: u128,
+        elapsed_ms: u128,
+        /// The number of times the score went up
+        improvements: u64,
+        best: BestRecord,
+    },
+}
+
+impl Event {
+    /// The event with each salt replaced by `MASKED_SALT`, so a log shared with others
+    /// can't leak a salt before it's deployed
+    pub fn masked(&self) -> Event {
+        let mut event = self.clone();
+        match &mut event {
+            Event::Started { initial_salt, .. } => *initial_salt = MASKED_SALT.to_string(),
+            Event::Heartbeat { best, .. }
+            | Event::NewBest { best }
+            | Event::Best { best }
+            | Event::Milestone { best, .. }
+            | Event::ScoreReached { best, .. }
+            | Event::TargetReached { best }
+            | Event::Finished { best, .. } => best.salt = MASKED_SALT.to_string(),
+            Event::Progress { .. } => {}
+        }
+        return event;
+    }
+
+    /// Render the event as a single line
+    ///
+    /// # Arguments
+    /// * `format` - The format to render in
+    ///
+    /// # Returns
+    /// * The line, without a trailing newline
+    pub fn render(&self, format: EventFormat) -> String {
+        match format {
+            // only strings and numbers, serializing can't fail
+            EventFormat::Json => serde_json::to_string(self).unwrap(),
+            EventFormat::Human => self.render_human(),
+            EventFormat::Legacy => self.render_legacy().unwrap_or_default(),
+        }
+    }
+
+    /// Render the event as the first versions did, before it had the score in words,
+    /// the checksummed address or the marginal estimates
+    ///
+    /// # Returns
+    /// * The line, or `None` for the events those versions didn't print
+    fn render_legacy(&self) -> Option<String> {
+        let fmt_best = |best: &BestRecord| {
+            format!(
+                "{} zeros {:?} salt {}",
+                leading_zero_nibbles(best.address),
+                best.address,
+                best.salt
+            )
+        };
+        match self {
+            Event::Heartbeat {
+                round,
+                elapsed_ms,
+                rate,
+                best,
+                ..
+            } => Some(format!(
+                "Round {} @ {} attempts/sec {}",
+                round,
+                (*rate as u128).to_formatted_string(&Locale::en),
+                fmt_countdown(
+                    ScoreMode::LeadingZeros,
+                    leading_zero_nibbles(best.address) + 1,
+                    *rate,
+                    *elapsed_ms
+                )
+            )),
+            // print to terminal in green ANSI color
+            Event::NewBest { best } => Some(format!("\x1b[32m{}\x1b[0m", fmt_best(best))),
+            // print to terminal in cyan ANSI color
+            Event::Best { best } => Some(format!("\x1b[36m{}\x1b[0m", fmt_best(best))),
+            _ => None,
+        }
+    }
+
+    fn render_human(&self) -> String {
+        match self {
+            Event::Started { initial_salt, .. } => {
+                format!("Search started at salt {}", initial_salt)
+            }
+            Event::Heartbeat {
+                round,
+                elapsed_ms,
+                rate,
+                best,
+                ..
+            } => format!(
+                "Round {} @ {} attempts/sec {}{}",
+                round,
+                (*rate as u128).to_formatted_string(&Locale::en),
+                fmt_countdown(
+                    best.score_mode,
+                    score_u8(best.score).saturating_add(1),
+                    *rate,
+                    *elapsed_ms
+                ),
+                fmt_marginal(best.score_mode, score_u8(best.score), *rate, *elapsed_ms)
+            ),
+            // print to terminal in green ANSI color
+            Event::NewBest { best } => format!("\x1b[32m{}\x1b[0m", best),
+            // print to terminal in cyan ANSI color
+            Event::Best { best } => format!("\x1b[36m{}\x1b[0m", best),
+            Event::Milestone { milestone, best } => {
+                format!("Milestone {} reached: {}", milestone, best)
+            }
+            Event::TargetReached { best } => format!("Target reached: {}", best),
+            Event::Progress {
+                attempts,
+                elapsed_ms,
+                rate,
+            } => format!(
+                "{} attempts in {} @ {} attempts/sec",
+                attempts.to_formatted_string(&Locale::en),
+                fmt_dms(elapsed_ms / 1000),
+                (*rate as u128).to_formatted_string(&Locale::en)
+            ),
+            Event::ScoreReached {
+                improvements, best, ..
+            } => format!(
+                "Reached {} ({} improvements): {}",
+                best.description, improvements, best
+            ),
+            Event::Finished {
+                rounds,
+                attempts,
+                elapsed_ms,
+                improvements,
+                ..
+            } => format!(
+                "Search finished: {} rounds, {} attempts in {}, {} score improvements",
+                rounds.to_formatted_string(&Locale::en),
+                attempts.to_formatted_string(&Locale::en),
+                fmt_dms(elapsed_ms / 1000),
+                improvements
+            ),
+        }
+    }
+}
+
+/// Print an event, the single place events are written out
+///
+/// Each event is flushed as it's printed, see `write_event`, so a JSON lines log can be
+/// followed while the search runs.
+///
+/// # Arguments
+/// * `event` - The event to print
+/// * `format` - The format to print it in
+pub fn emit(event: &Event, format: EventFormat) {
+    write_event(&mut io::stdout().lock(), event, format).expect("failed printing to stdout");
+}
+
+/// Write an event as a line of its own and flush it, whatever the writer buffers
+///
+/// # Arguments
+/// * `out` - Where to write the event
+/// * `event` - The event to write
+/// * `format` - The format to write it in, nothing is written for the events the legacy
+///   format leaves out
+fn write_event(out: &mut impl Write, event: &Event, format: EventFormat) -> io::Result<()> {
+    let line = match format {
+        EventFormat::Legacy => match event.render_legacy() {
+            Some(line) => line,
+            None => return Ok(()),
+        },
+        _ => event.render(format),
+    };
+    writeln!(out, "{}", line)?;
+    out.flush()
+}
+
+/// Events waiting for the reader of an `EventPipe`, past this many they are dropped
+const EVENT_PIPE_CAPACITY: usize = 1024;
+
+/// Writes events as JSON lines to a named pipe, for a dashboard to follow the search
+/// without parsing stdout
+///
+/// A background thread opens the pipe, which blocks until a reader connects, and
+/// reopens it when the reader goes away. Events are queued for it and dropped once
+/// `EVENT_PIPE_CAPACITY` are waiting, so a missing or slow reader never stalls the search.
+pub struct EventPipe {
+    sender: SyncSender<String>,
+}
+
+impl EventPipe {
+    /// Start writing to a named pipe
+    ///
+    /// # Arguments
+    /// * `path` - The pipe, e.g. made with `mkfifo`, which must already exist
+    ///
+    /// # Returns
+    /// * The pipe, or an error if the path doesn't exist
+    pub fn open(path: &Path) -> Result<Self, SearchError> {
+        if fs::metadata(path).is_err() {
+            return Err(SearchError::InvalidArgument(format!(
+                "fifo {} does not exist, create it with mkfifo",
+                path.display()
+            )));
+        }
+        let (sender, receiver) = sync_channel::<String>(EVENT_PIPE_CAPACITY);
+        let path = path.to_path_buf();
+        thread::spawn(move || loop {
+            let mut pipe = match OpenOptions::new().write(true).open(&path) {
+                Ok(pipe) => pipe,
+                Err(e) => {
+                    eprintln!("Error: failed to open fifo {}: {}", path.display(), e);
+                    return;
+                }
+            };
+            // a line is one write, up to PIPE_BUF bytes it reaches the reader whole
+            let disconnected = receiver
+                .iter()
+                .any(|line| pipe.write_all(line.as_bytes()).is_err());
+            if !disconnected {
+                // the searcher was dropped
+                return;
+            }
+        });
+        Ok(Self { sender })
+    }
+
+    /// Queue an event for the reader, as a JSON line
+    ///
+    /// # Arguments
+    /// * `event` - The event to write
+    pub fn send(&self, event: &Event) {
+        let line = format!("{}\n", event.render(EventFormat::Json));
+        // a full queue means nobody is reading, drop the event rather than wait
+        let _ = self.sender.try_send(line);
+    }
+}
+
+/// A score as the `u8` the expected attempts are computed for, saturating
+fn score_u8(score: u32) -> u8 {
+    score.min(u8::MAX as u32) as u8
+}
+
+/// What the score counts, leading zeros keep the terse "0s" of the first versions
+fn score_unit(score_mode: ScoreMode) -> &'static str {
+    match score_mode {
+        ScoreMode::LeadingZeros => "0s",
+        _ => score_mode.unit(),
+    }
+}
+
+/// Format the expected time to score one and two more, from now
+/// e.g. [+1 0 ~0d1h2m3s, +2 0s ~0d16h33m0s]
+///
+/// # Arguments
+/// * `score_mode` - How the best is scored
+/// * `score` - The score of the best so far
+/// * `rate` - The rate of attempts per second
+/// * `elapsed_ms` - The number of milliseconds elapsed
+fn fmt_marginal(score_mode: ScoreMode, score: u8, rate: f64, elapsed_ms: u128) -> String {
+    let [one, two] = marginal_time_for_extra_score(
+        score_mode,
+        score,
+        rate,
+        std::time::Duration::from_millis(elapsed_ms as u64),
+    );
+    let unit = score_unit(score_mode);
+    let unit_of_one = match score_mode {
+        ScoreMode::LeadingZeros => "0",
+        _ => unit,
+    };
+    format!(
+        " [+1 {} ~{}, +2 {} ~{}]",
+        unit_of_one,
+        fmt_dms(one),
+        unit,
+        fmt_dms(two)
+    )
+}
+
+/// Format the countdown to the next score
+/// e.g. (5 0s T-1d 2h 3m 4s)
+///
+/// # Arguments
+/// * `score_mode` - How the best is scored
+/// * `score` - The score counted down to
+/// * `rate` - The rate of attempts per second
+/// * `elapsed_ms` - The number of milliseconds elapsed
+///
+/// # Returns
+/// * A string in the format "(X 0s T-YdZhSmSs)" where X is the score in its unit,
+/// Y is the number of days, Z is the number of hours, S is the number of minutes, and S is
+/// the number of seconds.
+fn fmt_countdown(score_mode: ScoreMode, score: u8, rate: f64, elapsed_ms: u128) -> String {
+    let expected_attempts = score_mode.expected_attempts(score);
+    let expected_attempts_secs_at_current_rate = expected_attempts / rate;
+    // this is a statistical fallacy, but humans want to see progress
+    let expected_remaining_time_at_rate =
+        (expected_attempts_secs_at_current_rate - (elapsed_ms as f64 / 1000.0)) as u128;
+    return format!(
+        " ({} {} T-{})",
+        score,
+        score_unit(score_mode),
+        fmt_dms(expected_remaining_time_at_rate)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ethers_core::types::U256;
+
+    use crate::utils::{create2_address, parse_hex_u256};
+
+    /// A writer keeping how much had been written at each flush
+    #[derive(Default)]
+    struct FlushLog {
+        written: Vec<u8>,
+        flushed_at: Vec<usize>,
+    }
+
+    impl Write for FlushLog {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed_at.push(self.written.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn each_json_event_is_a_flushed_line_of_its_own() {
+        let found = AddressSalt {
+            address: Address::from_low_u64_be(0x1234),
+            salt_n: U256::from(7),
+        };
+        let best = BestRecord::new(&found, ScoreMode::LeadingZeros);
+        let events = [
+            Event::Started {
+                factory: Address::repeat_byte(0x11),
+                init_code_hash: "0x1234".to_string(),
+                initial_salt: "0x07".to_string(),
+                round_size: 100,
+                num_rounds: 8,
+            },
+            Event::Progress {
+                attempts: 50,
+                elapsed_ms: 10,
+                rate: 5000.0,
+            },
+            Event::NewBest { best: best.clone() },
+            Event::Finished {
+                rounds: 8,
+                attempts: 800,
+                elapsed_ms: 20,
+                improvements: 1,
+                best,
+            },
+        ];
+        let mut log = FlushLog::default();
+        for event in &events {
+            let start = log.written.len();
+            write_event(&mut log, event, EventFormat::Json).unwrap();
+            // flushed as soon as it is written, the whole line and only it
+            assert_eq!(log.flushed_at.last(), Some(&log.written.len()));
+            let line = std::str::from_utf8(&log.written[start..]).unwrap();
+            assert_eq!(line.matches('\n').count(), 1);
+            let parsed: serde_json::Value = serde_json::from_str(line.trim_end()).unwrap();
+            assert_eq!(parsed, serde_json::to_value(event).unwrap());
+        }
+        assert_eq!(log.flushed_at.len(), events.len());
+    }
+
+    #[test]
+    fn legacy_lines_match_the_first_versions() {
+        let found = AddressSalt {
+            address: "0x000000abcdef0123456789abcdef0123456789ab"
+                .parse()
+                .unwrap(),
+            salt_n: U256::from(0xcafe),
+        };
+        let best = BestRecord::new(&found, ScoreMode::LeadingZeros);
+        // 16^7 attempts for a 7th zero at a million a second, 68.435s in
+        let heartbeat = Event::Heartbeat {
+            round: 12,
+            attempts: 68_435_000,
+            elapsed_ms: 68_435,
+            rate: 1_000_000.0,
+            best: best.clone(),
+        };
+        let salt = "0x000000000000000000000000000000000000000000000000000000000000cafe";
+        let mut log = FlushLog::default();
+        for event in [heartbeat, Event::NewBest { best }] {
+            write_event(&mut log, &event, EventFormat::Legacy).unwrap();
+        }
+        // the countdown brings its own leading space, hence the two after attempts/sec
+        assert_eq!(
+            String::from_utf8(log.written).unwrap(),
+            format!(
+                "Round 12 @ 1,000,000 attempts/sec  (7 0s T-0d0h3m20s)\n\
+                 \x1b[32m6 zeros 0x000000abcdef0123456789abcdef0123456789ab salt {}\x1b[0m\n",
+                salt
+            )
+        );
+    }
+
+    #[test]
+    fn logged_salt_reconstructs_the_logged_address() {
+        let factory = Address::repeat_byte(0x11);
+        let init_code_hash = U256::from(0x1234);
+        let salt_n = U256::from(0xcafe) << 200usize;
+        let found = AddressSalt {
+            address: create2_address(factory, salt_n, init_code_hash),
+            salt_n,
+        };
+        let best = BestRecord::new(&found, ScoreMode::LeadingZeros);
+        for event in [Event::NewBest { best: best.clone() }, Event::Best { best }] {
+            let line = event.render(EventFormat::Human);
+            // "<n> zeros <address> salt <salt>", in color
+            let words: Vec<&str> = line.trim_end_matches("\x1b[0m").split(' ').collect();
+            let (address, salt) = match words[..] {
+                [.., address, "salt", salt] => (address, salt),
+                _ => panic!("no address and salt in {:?}", line),
+            };
+            let salt_n = parse_hex_u256("salt", salt).unwrap();
+            let derived = create2_address(factory, salt_n, init_code_hash);
+            assert_eq!(address, to_checksum(&derived, None));
+            assert_eq!(derived, found.address);
+        }
+    }
+
+    #[test]
+    fn masked_events_log_no_salt_hex() {
+        let found = AddressSalt {
+            address: Address::from_low_u64_be(0x1234),
+            salt_n: U256::from(0xc0ffee_u64) << 100usize,
+        };
+        let best = BestRecord::new(&found, ScoreMode::LeadingZeros);
+        let salt_hex = best.salt.trim_start_matches("0x").trim_start_matches('0');
+        let events = [
+            Event::Started {
+                factory: Address::repeat_byte(0x11),
+                init_code_hash: "0x1234".to_string(),
+                initial_salt: best.salt.clone(),
+                round_size: 100,
+                num_rounds: 8,
+            },
+            Event::Heartbeat {
+                round: 1,
+                attempts: 100,
+                elapsed_ms: 10,
+                rate: 10_000.0,
+                best: best.clone(),
+            },
+            Event::NewBest { best: best.clone() },
+            Event::Best { best: best.clone() },
+            Event::Milestone {
+                milestone: 3,
+                best: best.clone(),
+            },
+            Event::ScoreReached {
+                score: 37,
+                improvements: 1,
+                best: best.clone(),
+            },
+            Event::TargetReached { best: best.clone() },
+            Event::Finished {
+                rounds: 8,
+                attempts: 800,
+                elapsed_ms: 20,
+                improvements: 1,
+                best,
+            },
+        ];
+        for event in &events {
+            // every event but progress carries a salt, which the JSON always shows
+            assert!(event.render(EventFormat::Json).contains(salt_hex));
+            let masked = event.masked();
+            let json = masked.render(EventFormat::Json);
+            assert!(json.contains(MASKED_SALT), "{}", json);
+            for format in [EventFormat::Human, EventFormat::Json, EventFormat::Legacy] {
+                let line = masked.render(format);
+                assert!(!line.contains(salt_hex), "{}", line);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fifo_gets_each_event_as_a_json_line() {
+        let path = std::env::temp_dir().join(format!("search-create2-fifo-{}", std::process::id()));
+        assert!(matches!(
+            EventPipe::open(&path),
+            Err(SearchError::InvalidArgument(_))
+        ));
+        let made = std::process::Command::new("mkfifo").arg(&path).status();
+        assert!(made.unwrap().success());
+
+        let found = AddressSalt {
+            address: Address::from_low_u64_be(0x1234),
+            salt_n: U256::from(7),
+        };
+        let best = BestRecord::new(&found, ScoreMode::LeadingZeros);
+        let events = [
+            Event::NewBest { best: best.clone() },
+            Event::Best { best: best.clone() },
+            Event::TargetReached { best },
+        ];
+        let pipe = EventPipe::open(&path).unwrap();
+        // queued until a reader connects, and the pipe is closed once it is dropped
+        for event in &events {
+            pipe.send(event);
+        }
+        drop(pipe);
+        let read = fs::read_to_string(&path);
+        fs::remove_file(&path).unwrap();
+
+        let read = read.unwrap();
+        assert!(read.ends_with('\n'));
+        let lines: Vec<&str> = read.lines().collect();
+        assert_eq!(lines.len(), events.len());
+        for (line, event) in lines.iter().zip(&events) {
+            let parsed: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(parsed, serde_json::to_value(event).unwrap());
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::io::Write;
+
+use ethers_core::types::{Address, U256};
+use rayon::prelude::*;
+
+use crate::error::SearchError;
+use crate::utils::{bytes32, compute_batch, leading_zero_nibbles};
+
+/// Salts computed and written at a time, bounds the memory used by large ranges
+const EXPORT_CHUNK_SIZE: u64 = 1 << 16;
+
+/// Salts per parallel task within a chunk
+const EXPORT_TASK_SIZE: usize = 1024;
+
+/// The first line of the CSV
+const EXPORT_HEADER: &str = "salt,address,leading_zeros\n";
+
+/// The length of a row: the 0x-prefixed 32-byte salt, the 0x-prefixed address,
+/// a single digit of leading zeros, two commas and the newline
+const EXPORT_ROW_BYTES: u64 = 66 + 42 + 1 + 2 + 1;
+
+/// Estimate the size of the CSV `export_csv` writes for a range, before writing it
+///
+/// Exact unless an address has 10 or more leading zeros, whose count takes a digit more,
+/// which one row in 16^10 does.
+///
+/// # Arguments
+/// * `from_salt_n` - The first salt of the range
+/// * `to_salt_n` - The end of the range, excluded
+///
+/// # Returns
+/// * The number of bytes, saturating for ranges too large to ever write
+pub fn export_size_estimate(from_salt_n: U256, to_salt_n: U256) -> U256 {
+    let rows = to_salt_n.saturating_sub(from_salt_n);
+    rows.saturating_mul(U256::from(EXPORT_ROW_BYTES))
+        .saturating_add(U256::from(EXPORT_HEADER.len()))
+}
+
+/// Write the address of every salt in a range as CSV rows of salt, address and
+/// leading zeros, without tracking a best
+///
+/// The range is computed in chunks, each in parallel, and written in salt order
+/// before the next chunk is computed, so rows are streamed rather than buffered.
+///
+/// # Arguments
+/// * `writer` - Where the CSV is written, including a header row
+/// * `factory` - The CREATE2 factory address
+/// * `init_code_hash` - The init code hash as a number
+/// * `from_salt_n` - The first salt of the range
+/// * `to_salt_n` - The end of the range, excluded
+///
+/// # Returns
+/// * The number of rows written, or an error if the range is reversed or writing fails
+pub fn export_csv(
+    writer: &mut impl Write,
+    factory: Address,
+    init_code_hash: U256,
+    from_salt_n: U256,
+    to_salt_n: U256,
+) -> Result<u128, SearchError> {
+    if to_salt_n < from_salt_n {
+        return Err(SearchError::InvalidArgument(format!(
+            "to-salt {:#x} is before from-salt {:#x}",
+            to_salt_n, from_salt_n
+        )));
+    }
+
+    write!(writer, "{}", EXPORT_HEADER).map_err(SearchError::Io)?;
+    let mut rows: u128 = 0;
+    let mut chunk_start = from_salt_n;
+    while chunk_start < to_salt_n {
+        let chunk_len = (to_salt_n - chunk_start)
+            .min(U256::from(EXPORT_CHUNK_SIZE))
+            .as_u64();
+        let salts: Vec<U256> = (0..chunk_len)
+            .map(|i| chunk_start + U256::from(i))
+            .collect();
+        // collect keeps the order of the chunks, so the rows stay sorted by salt
+        let addresses: Vec<Address> = salts
+            .par_chunks(EXPORT_TASK_SIZE)
+            .flat_map_iter(|salts| compute_batch(factory, init_code_hash, salts))
+            .collect();
+        for (salt_n, address) in salts.iter().zip(&addresses) {
+            writeln!(
+                writer,
+                "0x{},{:?},{}",
+                hex::encode(bytes32(*salt_n)),
+                address,
+                leading_zero_nibbles(*address)
+            )
+            .map_err(SearchError::Io)?;
+        }
+        rows += chunk_len as u128;
+        chunk_start += U256::from(chunk_len);
+    }
+    writer.flush().map_err(SearchError::Io)?;
+    return Ok(rows);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create2_address;
+
+    #[test]
+    fn csv_rows_hold_the_salt_address_and_zeros_of_each_salt() {
+        let factory = Address::repeat_byte(0x11);
+        let init_code_hash = U256::from(0x1234);
+        let (from_salt_n, to_salt_n) = (U256::from(0xff), U256::from(0x104));
+        let mut csv = Vec::new();
+        let rows = export_csv(&mut csv, factory, init_code_hash, from_salt_n, to_salt_n).unwrap();
+        assert_eq!(rows, 5);
+
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("salt,address,leading_zeros"));
+        let first_salt = format!("0x{}ff,", "0".repeat(62));
+        assert!(csv.lines().nth(1).unwrap().starts_with(&first_salt));
+        for (i, line) in lines.enumerate() {
+            let salt_n = from_salt_n + U256::from(i);
+            let address = create2_address(factory, salt_n, init_code_hash);
+            let fields: Vec<&str> = line.split(',').collect();
+            assert_eq!(fields.len(), 3);
+            let mut salt = [0u8; 32];
+            salt_n.to_big_endian(&mut salt);
+            assert_eq!(fields[0], format!("0x{}", hex::encode(salt)));
+            assert_eq!(fields[1], format!("{:?}", address));
+            assert_eq!(fields[1].len(), 42);
+            assert_eq!(fields[1], fields[1].to_lowercase());
+            assert_eq!(fields[2], leading_zero_nibbles(address).to_string());
+        }
+        assert!(csv.ends_with('\n'));
+    }
+
+    #[test]
+    fn an_empty_range_writes_the_header_and_a_reversed_one_is_rejected() {
+        let mut csv = Vec::new();
+        let rows = export_csv(
+            &mut csv,
+            Address::zero(),
+            U256::zero(),
+            U256::from(5),
+            U256::from(5),
+        );
+        assert_eq!(rows.unwrap(), 0);
+        assert_eq!(csv, EXPORT_HEADER.as_bytes());
+
+        let reversed = export_csv(
+            &mut Vec::new(),
+            Address::zero(),
+            U256::zero(),
+            U256::from(5),
+            U256::from(4),
+        );
+        assert!(matches!(reversed, Err(SearchError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn size_estimate_matches_the_csv_written() {
+        let factory = Address::repeat_byte(0x11);
+        let init_code_hash = U256::from(0x1234);
+        for (from, to) in [(0u64, 0u64), (7, 8), (1000, 1300)] {
+            let (from_salt_n, to_salt_n) = (U256::from(from), U256::from(to));
+            let mut csv = Vec::new();
+            export_csv(&mut csv, factory, init_code_hash, from_salt_n, to_salt_n).unwrap();
+            assert_eq!(
+                export_size_estimate(from_salt_n, to_salt_n),
+                U256::from(csv.len()),
+                "{}..{}",
+                from,
+                to
+            );
+        }
+        // a reversed range, which is rejected, comes to the header, and a huge one saturates
+        assert_eq!(
+            export_size_estimate(U256::from(8), U256::from(7)),
+            U256::from(EXPORT_HEADER.len())
+        );
+        assert_eq!(export_size_estimate(U256::zero(), U256::MAX), U256::MAX);
+    }
+}
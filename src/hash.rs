@@ -0,0 +1,58 @@
+use std::{fmt, str::FromStr};
+
+use ethers_core::utils::keccak256;
+use sha2::{Digest, Sha256};
+
+/// How init code is hashed into the init code hash
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HashAlgo {
+    /// The hash CREATE2 uses on the EVM
+    #[default]
+    Keccak256,
+    /// For factories which hash the init code themselves with SHA-256
+    Sha256,
+}
+
+impl HashAlgo {
+    /// The names accepted by `from_str`
+    pub const NAMES: [&'static str; 2] = ["keccak256", "sha256"];
+
+    /// Hash data with the algorithm
+    ///
+    /// # Arguments
+    /// * `data` - The bytes to hash
+    ///
+    /// # Returns
+    /// * The 32-byte digest
+    pub fn digest(&self, data: &[u8]) -> [u8; 32] {
+        match self {
+            HashAlgo::Keccak256 => keccak256(data),
+            HashAlgo::Sha256 => Sha256::digest(data).into(),
+        }
+    }
+}
+
+impl FromStr for HashAlgo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keccak256" => Ok(HashAlgo::Keccak256),
+            "sha256" => Ok(HashAlgo::Sha256),
+            _ => Err(format!(
+                "unknown hash algorithm {}, expected one of {}",
+                s,
+                HashAlgo::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashAlgo::Keccak256 => write!(f, "keccak256"),
+            HashAlgo::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::process::{Command, ExitStatus};
+
+use ethers_core::utils::to_checksum;
+
+use crate::error::SearchError;
+use crate::search::AddressSalt;
+use crate::utils::bytes32;
+
+/// Replace the `{address}` and `{salt}` placeholders of a command with a result
+///
+/// The values are hex, so they need no quoting, but the rest of the command is
+/// passed to the shell as written and quoting it is up to the caller.
+///
+/// # Arguments
+/// * `command` - The command, with placeholders
+/// * `found` - The address and salt to substitute
+///
+/// # Returns
+/// * The command with the checksummed address and the 0x-prefixed 32-byte salt filled in
+pub fn fill_placeholders(command: &str, found: &AddressSalt) -> String {
+    command
+        .replace("{address}", &to_checksum(&found.address, None))
+        .replace(
+            "{salt}",
+            &format!("0x{}", hex::encode(bytes32(found.salt_n))),
+        )
+}
+
+/// Run a command through `sh -c` once a search has found an address meeting its target
+///
+/// The address and salt are substituted for the placeholders, see `fill_placeholders`,
+/// and also set as the `CREATE2_ADDRESS` and `CREATE2_SALT` environment variables.
+///
+/// # Arguments
+/// * `command` - The command, with placeholders
+/// * `found` - The address and salt found
+///
+/// # Returns
+/// * The exit status of the command, or an error if it could not be started
+pub fn run_on_success(command: &str, found: &AddressSalt) -> Result<ExitStatus, SearchError> {
+    Command::new("sh")
+        .arg("-c")
+        .arg(fill_placeholders(command, found))
+        .env("CREATE2_ADDRESS", to_checksum(&found.address, None))
+        .env(
+            "CREATE2_SALT",
+            format!("0x{}", hex::encode(bytes32(found.salt_n))),
+        )
+        .status()
+        .map_err(SearchError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ethers_core::types::U256;
+
+    /// The first example address of EIP-55
+    fn found() -> AddressSalt {
+        AddressSalt {
+            address: "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+                .parse()
+                .unwrap(),
+            salt_n: U256::from(0x2a),
+        }
+    }
+
+    #[test]
+    fn placeholders_are_filled_with_the_checksum_address_and_full_salt() {
+        let salt = format!("0x{}2a", "0".repeat(62));
+        assert_eq!(
+            fill_placeholders("deploy {salt} --expect {address}", &found()),
+            format!(
+                "deploy {} --expect 0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+                salt
+            )
+        );
+        // every occurrence is filled, and anything else is left as written
+        assert_eq!(
+            fill_placeholders("echo '{salt}' {salt} {other}", &found()),
+            format!("echo '{}' {} {{other}}", salt, salt)
+        );
+        assert_eq!(fill_placeholders("notify", &found()), "notify");
+    }
+
+    #[test]
+    fn the_command_sees_the_result_and_its_status_is_reported() {
+        let status = run_on_success("test \"$CREATE2_SALT\" = {salt} && exit 3", &found()).unwrap();
+        assert_eq!(status.code(), Some(3));
+        let status = run_on_success("test \"$CREATE2_ADDRESS\" = {address}", &found()).unwrap();
+        assert!(status.success());
+    }
+}
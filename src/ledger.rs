@@ -0,0 +1,305 @@
+use std::{fs, io, path::Path};
+
+use ethers_core::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::error::SearchError;
+use crate::score::{ScoreMode, TieBreak};
+use crate::search::AddressSalt;
+use crate::utils::{bytes32, parse_hex_u256};
+
+/// The all-time best of a mining campaign, kept across separate invocations
+///
+/// A ledger belongs to one factory, deployer and init code hash, since a salt
+/// found for any other combination can't be used to deploy the same contract.
+/// It also checkpoints which salts were searched, so the ledgers of shards of
+/// a distributed run can be merged into one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ledger {
+    pub factory: Address,
+    /// `None` when the salt isn't prefixed with a deployer
+    pub deployer: Option<Address>,
+    pub init_code_hash: String,
+    pub address: Address,
+    pub salt: String,
+    /// The salt ranges searched to the end, sorted and disjoint, empty in older ledgers
+    #[serde(default)]
+    pub covered: Vec<SaltRange>,
+}
+
+/// A range of salts searched, from the first up to but not including the last
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaltRange {
+    pub from: String,
+    pub to: String,
+}
+
+impl Ledger {
+    /// Record a best address for a campaign
+    ///
+    /// # Arguments
+    /// * `factory` - The CREATE2 factory
+    /// * `deployer` - The deployer prefixing the salt, if any
+    /// * `init_code_hash` - The init code hash
+    /// * `best` - The best address and its salt
+    pub fn new(
+        factory: Address,
+        deployer: Option<Address>,
+        init_code_hash: U256,
+        best: &AddressSalt,
+    ) -> Self {
+        Self {
+            factory,
+            deployer,
+            init_code_hash: format!("0x{}", hex::encode(bytes32(init_code_hash))),
+            address: best.address,
+            salt: format!("0x{}", hex::encode(bytes32(best.salt_n))),
+            covered: Vec::new(),
+        }
+    }
+
+    /// Read the ledger
+    ///
+    /// # Arguments
+    /// * `path` - The path of the ledger file
+    ///
+    /// # Returns
+    /// * The ledger, or `None` if the file doesn't exist yet
+    pub fn load(path: &Path) -> Result<Option<Self>, SearchError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(SearchError::Io(e)),
+        };
+        toml::from_str(&contents)
+            .map(Some)
+            .map_err(|e| SearchError::Ledger(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Write the ledger atomically, so an interrupted write can't lose the previous best
+    ///
+    /// # Arguments
+    /// * `path` - The path of the ledger file
+    pub fn save(&self, path: &Path) -> Result<(), SearchError> {
+        let contents = toml::to_string(self)
+            .map_err(|e| SearchError::Ledger(format!("{}: {}", path.display(), e)))?;
+        // rename is atomic within a filesystem, so write next to the ledger first
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, contents).map_err(SearchError::Io)?;
+        fs::rename(&tmp_path, path).map_err(SearchError::Io)
+    }
+
+    /// Whether the ledger was recorded for the same campaign
+    pub fn matches(
+        &self,
+        factory: Address,
+        deployer: Option<Address>,
+        init_code_hash: U256,
+    ) -> bool {
+        self.factory == factory
+            && self.deployer == deployer
+            && self.init_code_hash == format!("0x{}", hex::encode(bytes32(init_code_hash)))
+    }
+
+    /// The recorded best address and its salt
+    pub fn best(&self) -> Result<AddressSalt, SearchError> {
+        let salt_n =
+            parse_hex_u256("salt", &self.salt).map_err(|e| SearchError::Ledger(e.to_string()))?;
+        Ok(AddressSalt {
+            address: self.address,
+            salt_n,
+        })
+    }
+
+    /// Replace the best if an address beats it
+    ///
+    /// # Arguments
+    /// * `found` - The address and its salt
+    /// * `score_mode` - What makes an address better than another
+    /// * `tie_break` - How two addresses with the same score are ordered
+    ///
+    /// # Returns
+    /// * Whether the address beat the recorded best, or an error if the recorded salt
+    ///   isn't hex
+    pub fn record(
+        &mut self,
+        found: &AddressSalt,
+        score_mode: ScoreMode,
+        tie_break: TieBreak,
+    ) -> Result<bool, SearchError> {
+        if !score_mode.is_better(found, &self.best()?, tie_break) {
+            return Ok(false);
+        }
+        self.address = found.address;
+        self.salt = format!("0x{}", hex::encode(bytes32(found.salt_n)));
+        Ok(true)
+    }
+
+    /// The salt ranges searched, as numbers
+    ///
+    /// # Returns
+    /// * The `[from, to)` pairs, or an error naming the first salt that isn't hex
+    pub fn covered_ranges(&self) -> Result<Vec<(U256, U256)>, SearchError> {
+        self.covered
+            .iter()
+            .map(|range| {
+                let from = parse_hex_u256("covered from", &range.from);
+                let to = parse_hex_u256("covered to", &range.to);
+                match (from, to) {
+                    (Ok(from), Ok(to)) => Ok((from, to)),
+                    (Err(e), _) | (_, Err(e)) => Err(SearchError::Ledger(e.to_string())),
+                }
+            })
+            .collect()
+    }
+
+    /// Add salt ranges to those searched, joining any that overlap or touch
+    ///
+    /// # Arguments
+    /// * `ranges` - The `[from, to)` pairs searched
+    pub fn cover(&mut self, ranges: &[(U256, U256)]) -> Result<(), SearchError> {
+        let mut all = self.covered_ranges()?;
+        all.extend(ranges.iter().filter(|(from, to)| from < to));
+        all.sort();
+        let mut joined: Vec<(U256, U256)> = Vec::with_capacity(all.len());
+        for (from, to) in all {
+            match joined.last_mut() {
+                Some(last) if from <= last.1 => last.1 = last.1.max(to),
+                _ => joined.push((from, to)),
+            }
+        }
+        self.covered = joined
+            .into_iter()
+            .map(|(from, to)| SaltRange {
+                from: format!("0x{}", hex::encode(bytes32(from))),
+                to: format!("0x{}", hex::encode(bytes32(to))),
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Combine with the ledger of another shard of the same campaign, keeping the
+    /// better best and the union of the salts searched
+    ///
+    /// # Arguments
+    /// * `other` - The ledger to merge in
+    /// * `score_mode` - What makes an address better than another
+    /// * `tie_break` - How two addresses with the same score are ordered
+    ///
+    /// # Returns
+    /// * The number of salts both ledgers searched, duplicates of a misconfigured shard
+    ///   which the merged ledger counts once
+    pub fn merge(
+        &mut self,
+        other: &Ledger,
+        score_mode: ScoreMode,
+        tie_break: TieBreak,
+    ) -> Result<U256, SearchError> {
+        if self.factory != other.factory
+            || self.deployer != other.deployer
+            || self.init_code_hash != other.init_code_hash
+        {
+            return Err(SearchError::Ledger(
+                "can't merge ledgers of a different factory, deployer or init code hash"
+                    .to_string(),
+            ));
+        }
+        self.record(&other.best()?, score_mode, tie_break)?;
+        let other_covered = other.covered_ranges()?;
+        let duplicates = overlap(&self.covered_ranges()?, &other_covered);
+        self.cover(&other_covered)?;
+        Ok(duplicates)
+    }
+}
+
+/// The number of salts in both of two sorted lists of disjoint `[from, to)` ranges
+fn overlap(a: &[(U256, U256)], b: &[(U256, U256)]) -> U256 {
+    let (mut i, mut j) = (0, 0);
+    let mut total = U256::zero();
+    while i < a.len() && j < b.len() {
+        let from = a[i].0.max(b[j].0);
+        let to = a[i].1.min(b[j].1);
+        if from < to {
+            total += to - from;
+        }
+        // move past whichever range ends first, the other may overlap the next
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(address_byte: u8, salt: u64) -> AddressSalt {
+        AddressSalt {
+            address: Address::repeat_byte(address_byte),
+            salt_n: U256::from(salt),
+        }
+    }
+
+    #[test]
+    fn ledger_keeps_the_best_across_runs() {
+        let path =
+            std::env::temp_dir().join(format!("search-create2-ledger-{}.toml", std::process::id()));
+        let factory = Address::repeat_byte(0xfa);
+        let init_code_hash = U256::from(0x1234);
+        assert_eq!(Ledger::load(&path).unwrap(), None);
+        Ledger::new(factory, None, init_code_hash, &found(0x20, 1))
+            .save(&path)
+            .unwrap();
+
+        let mut ledger = Ledger::load(&path).unwrap().unwrap();
+        assert!(ledger.matches(factory, None, init_code_hash));
+        assert!(!ledger.matches(factory, Some(factory), init_code_hash));
+        assert_eq!(ledger.best().unwrap(), found(0x20, 1));
+        let score_mode = ScoreMode::LeadingZeros;
+        assert!(!ledger
+            .record(&found(0x30, 2), score_mode, TieBreak::default())
+            .unwrap());
+        assert!(ledger
+            .record(&found(0x10, 3), score_mode, TieBreak::default())
+            .unwrap());
+        ledger.save(&path).unwrap();
+
+        let ledger = Ledger::load(&path).unwrap().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(ledger.best().unwrap(), found(0x10, 3));
+    }
+
+    #[test]
+    fn cover_joins_overlapping_and_touching_ranges() {
+        let range = |from: u64, to: u64| (U256::from(from), U256::from(to));
+        let mut ledger = Ledger::new(Address::zero(), None, U256::zero(), &found(0x10, 0));
+        ledger.cover(&[range(10, 20), range(30, 40)]).unwrap();
+        ledger
+            .cover(&[range(20, 25), range(35, 50), range(60, 60)])
+            .unwrap();
+        assert_eq!(
+            ledger.covered_ranges().unwrap(),
+            vec![range(10, 25), range(30, 50)]
+        );
+    }
+
+    #[test]
+    fn merge_collapses_salts_searched_twice() {
+        let range = |from: u64, to: u64| (U256::from(from), U256::from(to));
+        let mut first = Ledger::new(Address::zero(), None, U256::zero(), &found(0x10, 5));
+        first.cover(&[range(0, 30), range(50, 60)]).unwrap();
+        // a shard overlapping the first by mistake, with the same best salt
+        let mut second = Ledger::new(Address::zero(), None, U256::zero(), &found(0x10, 5));
+        second.cover(&[range(20, 55)]).unwrap();
+
+        let duplicates = first
+            .merge(&second, ScoreMode::LeadingZeros, TieBreak::default())
+            .unwrap();
+        assert_eq!(duplicates, U256::from(15));
+        assert_eq!(first.covered_ranges().unwrap(), vec![range(0, 60)]);
+        assert_eq!(first.best().unwrap(), found(0x10, 5));
+    }
+}
@@ -0,0 +1,23 @@
+//! Search for CREATE2 salts giving addresses with many leading zeros
+
+pub mod analyze;
+pub mod config;
+pub mod derive;
+pub mod error;
+pub mod event;
+pub mod export;
+pub mod hash;
+pub mod hook;
+pub mod ledger;
+pub mod output;
+pub mod pressure;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod sample;
+pub mod score;
+pub mod search;
+pub mod sketch;
+pub mod template;
+pub mod utils;
+
+pub use utils::{compute_batch, create2_address};
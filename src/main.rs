@@ -1,33 +1,214 @@
-use clap::Parser;
-use ethers_core::types::{Address, U256};
-use num_format::{Locale, ToFormattedString};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
 
-use crate::utils::{bytes32, count_leading_zeroes};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use ethers_core::{
+    types::{Address, U256},
+    utils::{keccak256, to_checksum},
+};
+use num_format::{Locale, ToFormattedString};
 
-mod search;
-mod utils;
+use search_create2::analyze::{
+    max_deviation, nibble_frequencies, sparse_sample, sparse_stride, try_schemes,
+    NibbleFrequencies, SparseSample,
+};
+use search_create2::config::{
+    initial_salt, salt_space, window_attempts, BandOutcome, Config, ConfigFile,
+    DEFAULT_SALT_GUARD_BYTES,
+};
+use search_create2::derive::{sender_salt, AddressDeriver, Create2Deriver, Scheme};
+use search_create2::error::SearchError;
+use search_create2::event::{EventFormat, EventPipe};
+use search_create2::export::{export_csv, export_size_estimate};
+use search_create2::hash::HashAlgo;
+use search_create2::hook::run_on_success;
+use search_create2::ledger::Ledger;
+use search_create2::output::{
+    fmt_calldata, fmt_foundry, fmt_json, fmt_legacy, fmt_match_carets, fmt_match_color, FactoryAbi,
+    OutputFormat,
+};
+use search_create2::score::{
+    parse_byte, parse_leading_nibble, parse_score, CasedWord, ScoreMode, TieBreak,
+};
+use search_create2::search::{self, ThresholdReturn};
+use search_create2::sketch::DurationSketch;
+use search_create2::template::Template;
+use search_create2::utils::{
+    address_to_u256, bytes32, compute_batch, core_hours, create2_address, create2_preimage,
+    default_num_threads, fmt_bytes, fmt_cast_create2, fmt_dms, fmt_salt, init_code_hash,
+    known_factory, leading_zero_nibbles, parse_address, parse_hex, parse_hex_u256,
+    read_address_list, read_hash_list, read_init_code_file, read_salt_address_list, read_salt_list,
+    recover_init_code_hash, suspicious_init_code_hash, ADDRESS_NIBBLES, CREATE2_PREIMAGE_LAYOUT,
+};
 
-/// Simple program to greet a person
+/// Search for a CREATE2 salt giving a vanity address, e.g. one with many leading zeros
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
-    /// The CREATE2 deployer address
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The CREATE2 factory address, i.e. the contract executing CREATE2
     /// e.g. an ImmutableCreate2Factory or a UniSwap Pool Deployer
+    /// the resulting addresses are derived from this address
+    /// `eip2470` names the EIP-2470 singleton factory and implies --full-salt
+    #[arg(long, env = "CREATE2_FACTORY")]
+    factory: Option<String>,
+
+    /// The EOA deployer which will call the safeCreate2
+    /// the first 20 bytes of each salt are fixed to this address
+    /// required unless --full-salt is set
+    #[arg(long, env = "CREATE2_DEPLOYER")]
+    deployer: Option<String>,
+
+    /// a JSON-RPC endpoint to check the factory is a contract before searching, the
+    /// addresses of a factory without code can never be deployed to
+    /// needs the rpc feature, ignored with a warning without it
+    #[arg(long, env = "CREATE2_RPC")]
+    rpc: Option<String>,
+
+    /// make input which is almost certainly a mistake an error instead of a warning,
+    /// e.g. the zero address as the factory
     #[arg(long)]
-    deployer: String,
+    strict: bool,
 
-    /// The EOA sender which will call the safeCreate2
+    /// the number of leading salt bytes fixed to the deployer, 0 through 32, default 20
+    /// for factories guarding fewer bytes, past 20 the extra bytes are fixed to zero
+    #[arg(long, conflicts_with = "full_salt", value_parser = clap::value_parser!(u8).range(0..=32))]
+    salt_guard_bytes: Option<u8>,
+
+    /// search the full 32-byte salt instead of prefixing it with the deployer
+    /// for unpermissioned factories which do not check msg.sender against the salt
     #[arg(long)]
-    sender: String,
+    full_salt: bool,
 
     /// the init code hash
+    /// repeat to compare several contracts, the rounds are split evenly between them
+    /// and the hashes are ranked by the best address found for each
+    #[arg(long, env = "CREATE2_INIT_CODE_HASH")]
+    init_code_hash: Vec<String>,
+
+    /// compute the init code hash from a built-in proxy template instead of --init-code-hash
+    /// one of eip1167 (minimal proxy clone) or eip7511 (minimal proxy with PUSH0)
+    #[arg(long, conflicts_with = "init_code_hash", requires = "implementation")]
+    template: Option<Template>,
+
+    /// the creation bytecode with any constructor arguments, hex, hashed in place of
+    /// --init-code-hash, needed by --output calldata unless --template is used
+    #[arg(long, conflicts_with_all = ["init_code_hash", "template"])]
+    init_code: Option<String>,
+
+    /// a file holding the creation bytecode as hex, e.g. the bytecode of a build artifact,
+    /// hashed as --init-code is, files over 1 MB are rejected as no init code is that large
+    #[arg(long, conflicts_with_all = ["init_code_hash", "template", "init_code"])]
+    init_code_file: Option<PathBuf>,
+
+    /// how init code is hashed into the init code hash, by --init-code, --init-code-file,
+    /// --template and the hash command, one of keccak256 (the EVM standard) or sha256 for
+    /// factories with their own scheme
+    #[arg(long, global = true, default_value_t = HashAlgo::Keccak256)]
+    hash_algo: HashAlgo,
+
+    /// the implementation contract the --template proxy delegates to
+    #[arg(long, requires = "template")]
+    implementation: Option<String>,
+
+    /// a single salt to compute the address for, used with --compare-tools
     #[arg(long)]
-    init_code_hash: String,
+    salt: Option<String>,
+
+    /// print the address for --salt in the same format as `cast create2`
+    /// and exit, so the output can be cross-checked against foundry
+    #[arg(long, requires = "salt")]
+    compare_tools: bool,
 
     /// zeros to search for
+    /// with --leading-nibble, the length of the nibble run to search for
     #[arg(long)]
     zeros: Option<u8>,
 
+    /// search for the longest run of a nibble at the start of the address instead of zeros
+    /// e.g. 8 for 0x8888..., or any to take whichever nibble comes first
+    #[arg(long)]
+    leading_nibble: Option<String>,
+
+    /// what to score addresses by, one of leading-zeros (the default), total-zero-bytes
+    /// or trailing-then-leading
+    /// total-zero-bytes counts the 0x00 bytes wherever they are, --zeros is then a byte count
+    /// trailing-then-leading ranks by trailing zeros, breaking ties by leading zeros,
+    /// --zeros is then a trailing zero count
+    /// checksum-uppercase counts the upper case letters of the EIP-55 checksummed address,
+    /// --zeros is then a letter count
+    #[arg(long, conflicts_with = "leading_nibble")]
+    score: Option<String>,
+
+    /// search for the longest match of these hex characters at the start of the EIP-55
+    /// checksummed address, case included, e.g. DeaDBEEF, --zeros is then a character count
+    /// hashes the address again to checksum it, but only when a letter of the word matched
+    #[arg(long, conflicts_with_all = ["leading_nibble", "score"])]
+    checksum_word: Option<CasedWord>,
+
+    /// search for the most bytes of this value anywhere in the address, two hex digits,
+    /// e.g. ff, whole bytes so 0x0ff0 counts none, --zeros is then a byte count
+    #[arg(
+        long,
+        value_parser = parse_byte,
+        conflicts_with_all = ["leading_nibble", "score", "checksum_word"]
+    )]
+    maximize_byte: Option<u8>,
+
+    /// search for the address numerically closest to this one, above or below it,
+    /// e.g. a round number, --zeros is then the leading zeros of the distance
+    #[arg(
+        long,
+        conflicts_with_all = ["leading_nibble", "score", "checksum_word", "maximize_byte"]
+    )]
+    closest_to: Option<String>,
+
+    /// stop as soon as an address with --zeros leading zeros is found
+    /// instead of running all the rounds
+    #[arg(long)]
+    stop_at_target: bool,
+
+    /// which address to return when stopping at the target or the last milestone,
+    /// one of first (the first address to meet it, fastest) or round-best (the best of
+    /// the round which met it, that round finishes while the others stop), round-best
+    /// by default
+    #[arg(long)]
+    threshold_return: Option<ThresholdReturn>,
+
+    /// which of two addresses with the same score is kept, one of lowest-address
+    /// (the numerically lower address, the default) or lowest-salt (the lower salt, the
+    /// same result whichever round reports first)
+    #[arg(long)]
+    tie_break: Option<TieBreak>,
+
+    /// scores to report the first time the best reaches them, e.g. 6,8,10
+    /// the search stops once the highest is reached
+    #[arg(long, value_delimiter = ',')]
+    milestones: Vec<u32>,
+
+    /// run this shell command at each milestone, with the same placeholders and
+    /// environment variables as --on-success
+    #[arg(long, requires = "milestones")]
+    on_milestone: Option<String>,
+
+    /// the floor of the acceptance band: the search fails, exiting with an error, if its
+    /// best scores less once the rounds run out
+    #[arg(long)]
+    min_zeros: Option<u8>,
+
+    /// the cap of the acceptance band: stop as soon as the best scores this much, for when
+    /// a good enough address will do
+    #[arg(long)]
+    max_zeros: Option<u8>,
+
     /// number of rounds to search
     /// each round is a block of size = limit
     /// each round will increment the initial_salt_n by limit
@@ -36,6 +217,83 @@ struct Args {
     #[arg(long)]
     num_rounds: Option<u128>,
 
+    /// total number of attempts, an alternative to --num-rounds
+    /// the last round only scans what remains, so exactly this many addresses are scanned
+    #[arg(long, conflicts_with = "num_rounds", value_parser = clap::value_parser!(u64).range(1..))]
+    max_attempts: Option<u64>,
+
+    /// set the rounds to tile the whole salt window after the deployer prefix exactly,
+    /// all 2^96 salts by default, for when the target must not be missed by partial coverage
+    /// this is a very long run
+    #[arg(
+        long,
+        conflicts_with_all = ["num_rounds", "max_attempts", "full_salt", "watch", "salt_list"]
+    )]
+    cover_full_window: bool,
+
+    /// derive the starting salt of the search and of each --retry-with-random or --repeat
+    /// run from this seed instead of at random, so a search can be reproduced, workers
+    /// sharing a seed never search the same salts
+    #[arg(long, conflicts_with_all = ["cover_full_window", "watch", "sparse_sample"])]
+    seed: Option<u64>,
+
+    /// the index of this worker among those sharing --seed, each takes its own starts,
+    /// 0 by default
+    #[arg(long, requires = "seed")]
+    seed_index: Option<u32>,
+
+    /// rerun a round which panics this many times before skipping it, a skipped round is
+    /// reported and the search carries on with the best found so far
+    #[arg(long, default_value_t = 0)]
+    round_retries: u32,
+
+    /// also write each new best as a JSON line to this named pipe (see mkfifo), for a
+    /// dashboard to follow the search, events are dropped while nobody is reading
+    #[arg(long)]
+    fifo: Option<PathBuf>,
+
+    /// replace the salts in the logs of the search with a placeholder, for a shared terminal
+    /// or log a salt guarding a privileged deployment could be scraped from before it's used,
+    /// only the final best salt is printed
+    #[arg(long)]
+    mask_salts: bool,
+
+    /// with --mask-salts, append the final best as salt,address to this file, created
+    /// readable by its owner only, instead of printing its salt
+    #[arg(long, requires = "mask_salts")]
+    salt_file: Option<PathBuf>,
+
+    /// start with one round at a time and let more run at once over this many seconds,
+    /// up to one per thread, to avoid a thermal spike and throttling at the start
+    #[arg(long)]
+    ramp_up_secs: Option<u64>,
+
+    /// search on fewer threads while the machine is under load, memory or thermal pressure,
+    /// needs the pressure feature and reads the pressure on Linux only
+    #[arg(long)]
+    throttle_on_pressure: bool,
+
+    /// search the salt passed to a factory which hashes it with the caller,
+    /// keccak256(abi.encodePacked(sender, salt)), into the salt given to CREATE2
+    #[arg(long, conflicts_with_all = ["analyze", "sparse_sample", "compare_tools"])]
+    sender_salting: Option<String>,
+
+    /// warn when no attempts are counted for this many seconds, e.g. every thread is
+    /// blocked or the machine throttled to a halt, should be longer than a round takes
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    stall_warning_secs: Option<u64>,
+
+    /// log the attempts made so far every this many seconds, counting those of rounds
+    /// still running, for rounds too large to finish every few seconds
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    progress_secs: Option<u64>,
+
+    /// split the attempt budget into one contiguous block of salts per thread instead of
+    /// rounds, each thread scans its block to the end and merges its best once, the least
+    /// coordination for a fixed budget, but progress is only reported as blocks finish
+    #[arg(long, conflicts_with_all = ["watch", "salt_list"])]
+    thread_blocks: bool,
+
     /// number of attempts per round
     /// default is 1,000,000
     /// each round will increment the initial_salt_n by round_size
@@ -43,64 +301,2919 @@ struct Args {
     #[arg(long)]
     round_size: Option<u128>,
 
+    /// advance the salt by this much between attempts instead of 1
+    /// lets several processes cover disjoint salts, e.g. with coprime steps
+    /// default is 1
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    salt_step: Option<u64>,
+
     /// number of threads to use
-    /// default is 16
+    /// default is the number of available cores, or 4 if that can't be detected
     #[arg(long)]
     num_threads: Option<usize>,
+
+    /// pin each worker thread to a distinct CPU core
+    /// reduces variance when benchmarking, ignored with a warning where unsupported
+    #[arg(long)]
+    pin_threads: bool,
+
+    /// run on rayon's global thread pool instead of a dedicated one
+    /// its size is set by RAYON_NUM_THREADS, --num-threads is ignored with a warning
+    #[arg(long, conflicts_with_all = ["pin_threads", "scaling_test"])]
+    use_global_pool: bool,
+
+    /// evaluate the salts in this file, one hex salt per line, instead of searching a range
+    /// and report the best of them
+    #[arg(long, conflicts_with_all = ["watch", "retry_with_random", "max_attempts"])]
+    salt_list: Option<PathBuf>,
+
+    /// keep searching past --num-rounds until stopped, printing every new best
+    /// each batch of rounds continues where the last one ended, --ledger is updated
+    /// with the best and the salts searched after each batch
+    #[arg(long, conflicts_with_all = ["max_attempts", "compare_tools"])]
+    watch: bool,
+
+    /// with --watch, rather than stop at the end of the salt window of the deployer prefix,
+    /// move on to the next prefix, and past the last salt wrap around to 0, until every
+    /// 256-bit salt has been searched, the salts past the first window lose the deployer
+    #[arg(long, requires = "watch")]
+    rotate_prefix: bool,
+
+    /// measure the rate at 1, 2, 4, ... threads up to the available cores and exit,
+    /// to show where adding threads stops paying off
+    #[arg(long)]
+    scaling_test: bool,
+
+    /// scan this many salts from the initial salt and print how often each nibble value
+    /// appears at each of the 40 address positions, to check the addresses are uniform,
+    /// then exit
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    analyze: Option<u64>,
+
+    /// scan this many salts from the initial salt, computing each address as the raw CREATE2
+    /// salt, hashed with the --sender-salting address if given, and through CREATE3, print
+    /// the best under each, then exit, to find out how a factory derives its salt
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    try_schemes: Option<u64>,
+
+    /// print the bytes keccak256 hashes into the address of the initial salt, each field
+    /// labeled, and check the address against the search's derivation, then exit
+    #[arg(long)]
+    dump_preimage: bool,
+
+    /// compute the addresses of this many salts spread evenly over the salt window and
+    /// print the best and how often each score came up, to estimate how rare a target
+    /// is without a full scan, then exit
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    sparse_sample: Option<u64>,
+
+    /// print how many scanned addresses had each number of leading zeros
+    /// alongside the count expected from the 1/16 per zero distribution
+    #[arg(long)]
+    histogram: bool,
+
+    /// do not print the resolved configuration at startup
+    #[arg(long)]
+    quiet: bool,
+
+    /// sample where time is spent in the search loop and print a breakdown at the end
+    #[arg(long)]
+    profile: bool,
+
+    /// keep a uniform random sample of this many scanned addresses, regardless of
+    /// their score, and print it at the end, to study the distribution of addresses
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    sample: Option<u64>,
+
+    /// mark the matching nibbles in the final report with a line of carets
+    /// instead of ANSI colors
+    #[arg(long)]
+    no_color: bool,
+
+    /// print extra detail in the final report
+    #[arg(long)]
+    verbose: bool,
+
+    /// print the core-hours the run consumed, elapsed time times threads, and an
+    /// extrapolation of the core-hours one more leading zero would likely take
+    #[arg(long)]
+    stats: bool,
+
+    /// how to log the progress of the search, one of human, json or legacy
+    /// json prints one object per line for each event: started, heartbeat, new_best, best,
+    /// progress, score_reached, target_reached and finished
+    /// legacy prints only the round and best lines, as the first versions did
+    #[arg(long)]
+    log_format: Option<EventFormat>,
+
+    /// how to print the result, one of human, foundry, calldata, legacy, json or jsonl
+    /// foundry prints `bytes32 salt` and `address expected` declarations for a forge script
+    /// calldata prints the factory call deploying --init-code, to paste into a wallet
+    /// legacy prints the "Best:" block and, unless --log-format is given, the round lines
+    /// exactly as the first versions did, for scripts parsing them
+    /// json prints the result as one JSON object once the search is over
+    /// jsonl streams JSON lines, the events (as --log-format json) and the result last
+    #[arg(long, default_value_t = OutputFormat::Human)]
+    output: OutputFormat,
+
+    /// the function --output calldata calls, safe-create2 or eip2470, needed for a factory
+    /// other than the ImmutableCreate2Factory and the EIP-2470 singleton
+    #[arg(long)]
+    factory_abi: Option<FactoryAbi>,
+
+    /// read parameters from a TOML config file, flags given on the command line take precedence
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// never report this address, e.g. a contract already deployed, can be repeated
+    #[arg(long)]
+    deny_address: Vec<String>,
+
+    /// never report the addresses in this file, one per line, `#` starts a comment line
+    #[arg(long)]
+    deny_file: Option<PathBuf>,
+
+    /// run this shell command once an address meeting the --zeros target is found
+    /// {address} and {salt} are replaced by the result, which is also set in the
+    /// CREATE2_ADDRESS and CREATE2_SALT environment variables
+    #[arg(long)]
+    on_success: Option<String>,
+
+    /// if the search ends short of the --zeros target, search again from a random salt,
+    /// up to this many times, keeping the best across the searches
+    #[arg(long, conflicts_with = "watch")]
+    retry_with_random: Option<u32>,
+
+    /// run the search this many times, each from its own random salt, and report the best
+    /// of them along with each run, Ctrl-C stops after the run in progress
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["watch", "retry_with_random", "salt_list"]
+    )]
+    repeat: Option<u32>,
+
+    /// keep the all-time best across runs in this file, along with the salt ranges searched
+    /// the search only reports addresses beating it, and the file is updated after each run
+    #[arg(long)]
+    ledger: Option<PathBuf>,
+
+    /// check a TOML config file, report every problem found in it and exit
+    #[arg(long)]
+    validate_config: Option<PathBuf>,
+
+    /// print the keccak256 fingerprint of the parameters deciding the result and exit
+    /// rerunning with the same parameters, e.g. the same --config, gives the same fingerprint
+    #[arg(long)]
+    fingerprint: bool,
+
+    /// write the resolved parameters to a TOML config file and exit
+    /// the file can be passed to --config to rerun the same search
+    #[arg(long)]
+    dump_config: Option<PathBuf>,
+}
+
+/// Utilities which run instead of a search
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compute the init code hash from a contract's creation bytecode
+    /// and ABI-encoded constructor arguments
+    Hash {
+        /// the creation bytecode, hex
+        #[arg(long)]
+        bytecode: String,
+
+        /// the ABI-encoded constructor arguments appended to the bytecode, hex
+        #[arg(long)]
+        constructor_args: Option<String>,
+    },
+    /// Write the address of every salt in a range to a CSV file,
+    /// one row of salt, address and leading zeros per salt
+    Export {
+        /// the CREATE2 factory address
+        #[arg(long)]
+        factory: String,
+
+        /// the init code hash
+        #[arg(long)]
+        init_code_hash: String,
+
+        /// the first salt of the range, hex
+        #[arg(long)]
+        from_salt: String,
+
+        /// the end of the range, excluded, hex
+        #[arg(long)]
+        to_salt: String,
+
+        /// the CSV file to write
+        #[arg(long)]
+        out: PathBuf,
+
+        /// don't ask for confirmation when the file would be larger than 1 GB
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Show step by step how a salt becomes an address: the keccak256 preimage,
+    /// its hash, the address, its score and how rare that score is
+    Explain {
+        /// the CREATE2 factory address
+        #[arg(long)]
+        factory: String,
+
+        /// the init code hash
+        #[arg(long)]
+        init_code_hash: String,
+
+        /// the salt to explain, hex
+        #[arg(long)]
+        salt: String,
+
+        /// what to score the address by, as --score, default leading-zeros
+        #[arg(long)]
+        score: Option<String>,
+    },
+    /// Find which of a list of init code hashes a deployed address was created with,
+    /// from its factory and salt
+    RecoverHash {
+        /// the CREATE2 factory address
+        #[arg(long)]
+        factory: String,
+
+        /// the salt the address was deployed with, hex
+        #[arg(long)]
+        salt: String,
+
+        /// the deployed address
+        #[arg(long)]
+        address: String,
+
+        /// a file of candidate init code hashes, one per line, blank lines and lines
+        /// starting with # are skipped
+        #[arg(long)]
+        hash_list: PathBuf,
+    },
+    /// Check a list of mined salts against the addresses they were recorded with,
+    /// exiting with an error if any doesn't derive its address
+    VerifyBatch {
+        /// the CREATE2 factory address
+        #[arg(long)]
+        factory: String,
+
+        /// the init code hash
+        #[arg(long)]
+        init_code_hash: String,
+
+        /// a file of salt,address pairs, one per line, blank lines and lines
+        /// starting with # are skipped
+        #[arg(long)]
+        pair_list: PathBuf,
+    },
+    /// Combine the ledgers of shards of a distributed run into one, keeping the best
+    /// address of any shard and the union of the salt ranges they searched
+    #[command(alias = "merge-checkpoints")]
+    MergeLedgers {
+        /// the ledgers to merge, all for the same factory, deployer and init code hash
+        #[arg(required = true, num_args = 2..)]
+        ledgers: Vec<PathBuf>,
+
+        /// how to compare the best of each ledger, as --score, default leading-zeros
+        #[arg(long)]
+        score: Option<String>,
+
+        /// the ledger to write
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Print a completion script for a shell, generated from the flags and subcommands,
+    /// e.g. `search_create2 completions bash > /etc/bash_completion.d/search_create2`
+    Completions {
+        /// the shell, one of bash, zsh, fish, elvish or powershell
+        #[arg(value_enum)]
+        shell: Shell,
+    },
 }
 
 fn main() {
     let args = Args::parse();
 
-    let deployer: Address = args.deployer.parse().unwrap();
-    let sender: Address = args.sender.parse().unwrap();
+    if let Some(command) = &args.command {
+        run_command(command, args.hash_algo);
+        return;
+    }
 
-    // use U256 because it is copyable in struct via #[derive(Copy, Clone)]
-    let init_code_hash: U256 = U256::from_str_radix(
-        args.init_code_hash
-            .parse::<String>()
-            .unwrap()
-            .trim_start_matches("0x"),
-        16,
-    )
-    .unwrap();
+    if let Some(path) = &args.validate_config {
+        validate_config(path);
+        return;
+    }
+
+    let config = match resolve_config(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // checked before searching so the result isn't lost to a missing flag
+    if args.output == OutputFormat::Calldata && !matches!(init_code(&args), Ok(Some(_))) {
+        eprintln!("Error: --output calldata needs --init-code, --init-code-file or --template");
+        std::process::exit(1);
+    }
+    if args.output == OutputFormat::Calldata && factory_abi(&config, &args).is_none() {
+        eprintln!(
+            "Error: --output calldata doesn't know how factory {:?} deploys, pass --factory-abi",
+            config.factory
+        );
+        std::process::exit(1);
+    }
+
+    for init_code_hash in &config.init_code_hashes {
+        if let Err(e) = config.search_params(*init_code_hash).check_rounds() {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        if let Some(reason) = suspicious_init_code_hash(*init_code_hash) {
+            eprintln!(
+                "Warning: init code hash 0x{} looks wrong, {}",
+                hex::encode(bytes32(*init_code_hash)),
+                reason
+            );
+        }
+    }
+
+    if args.cover_full_window {
+        eprintln!(
+            "Warning: --cover-full-window scans {} addresses in {} rounds, this may take years",
+            config.total_attempts().to_formatted_string(&Locale::en),
+            config.num_rounds.to_formatted_string(&Locale::en)
+        );
+    }
+
+    if args.compare_tools {
+        let salt_n = match parse_hex_u256("salt", args.salt.as_ref().unwrap()) {
+            Ok(salt_n) => salt_n,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        };
+        for init_code_hash in &config.init_code_hashes {
+            let address = create2_address(config.factory, salt_n, *init_code_hash);
+            println!("{}", fmt_cast_create2(address, salt_n));
+        }
+        return;
+    }
+
+    if args.fingerprint {
+        println!("{}", config.canonical());
+        println!("Fingerprint: 0x{}", hex::encode(config.fingerprint()));
+        return;
+    }
+
+    if let Some(path) = &args.dump_config {
+        if let Err(e) = ConfigFile::try_from(&config).and_then(|file| file.save(path)) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        println!("Wrote config to {}", path.display());
+        return;
+    }
+
+    if args.scaling_test {
+        if let Err(e) = scaling_test(&config, args.pin_threads) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(sample_size) = args.analyze {
+        for init_code_hash in &config.init_code_hashes {
+            let frequencies = nibble_frequencies(
+                config.factory,
+                *init_code_hash,
+                config.initial_salt_n,
+                sample_size,
+                config.salt_step,
+            );
+            println!(
+                "\nInit code hash 0x{}",
+                hex::encode(bytes32(*init_code_hash))
+            );
+            print_nibble_frequencies(&frequencies);
+        }
+        return;
+    }
+
+    if args.dump_preimage {
+        for init_code_hash in &config.init_code_hashes {
+            if !dump_preimage(&config, *init_code_hash) {
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(sample_size) = args.try_schemes {
+        let mut schemes = vec![Scheme::Raw];
+        match config.sender_salting {
+            Some(sender) => schemes.push(Scheme::SenderSalted(sender)),
+            None => println!("Skipping keccak256(sender, salt), it needs --sender-salting"),
+        }
+        schemes.push(Scheme::Create3);
+        for init_code_hash in &config.init_code_hashes {
+            let best = try_schemes(
+                config.factory,
+                *init_code_hash,
+                config.initial_salt_n,
+                sample_size,
+                config.salt_step,
+                &schemes,
+                config.score_mode,
+                config.tie_break,
+            );
+            println!(
+                "\nInit code hash 0x{}, best of {} salts per scheme:\n",
+                hex::encode(bytes32(*init_code_hash)),
+                sample_size.to_formatted_string(&Locale::en)
+            );
+            for (scheme, found) in schemes.iter().zip(best) {
+                println!(
+                    "{}: {} {} salt 0x{}",
+                    scheme,
+                    config.score_mode.describe(found.address),
+                    to_checksum(&found.address, None),
+                    hex::encode(bytes32(found.salt_n))
+                );
+            }
+        }
+        return;
+    }
+
+    if let Some(sample_size) = args.sparse_sample {
+        let window = salt_space(config.deployer, config.salt_guard_bytes);
+        let stride = match sparse_stride(config.initial_salt_n, window, sample_size) {
+            Some(stride) => stride,
+            None => {
+                eprintln!(
+                    "Error: the salt window has fewer than {} salts",
+                    sample_size
+                );
+                std::process::exit(1);
+            }
+        };
+        println!(
+            "Sampling {} salts {:#x} apart from salt 0x{}",
+            sample_size.to_formatted_string(&Locale::en),
+            stride,
+            hex::encode(bytes32(config.initial_salt_n))
+        );
+        for init_code_hash in &config.init_code_hashes {
+            let sample = sparse_sample(
+                config.factory,
+                *init_code_hash,
+                config.initial_salt_n,
+                stride,
+                sample_size,
+                config.score_mode,
+                config.tie_break,
+            );
+            println!(
+                "\nInit code hash 0x{}",
+                hex::encode(bytes32(*init_code_hash))
+            );
+            print_sparse_sample(&sample, &config, &args);
+        }
+        return;
+    }
+
+    // the first versions printed only the expected attempts before searching,
+    // and a JSON lines stream has no room for either
+    let jsonl = args.output == OutputFormat::Jsonl;
+    if !args.quiet && config.event_format != EventFormat::Legacy && !jsonl {
+        println!("{}\n", config);
+    }
+
+    if !jsonl {
+        let expected_attempts = config.score_mode.expected_attempts(config.zeros);
+        println!(
+            "Expected attempts for {} {}: {}",
+            config.zeros,
+            config.score_mode.unit(),
+            (expected_attempts.round() as u128).to_formatted_string(&Locale::en)
+        );
+    }
+
+    if let Some(url) = &args.rpc {
+        check_factory_code(url, config.factory);
+    }
+
+    let deriver = match config.sender_salting {
+        Some(sender) => Create2Deriver::sender_salted(sender),
+        None => Create2Deriver::default(),
+    };
+    let searcher = if args.use_global_pool {
+        Ok(search::Searcher::with_global_pool(deriver))
+    } else {
+        search::Searcher::with_deriver(config.num_threads, args.pin_threads, deriver)
+    };
+    let mut searcher = match searcher {
+        Ok(searcher) => searcher,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    searcher.set_milestones(config.milestones.clone(), args.on_milestone.clone());
+    searcher.set_mask_salts(config.mask_salts);
+    searcher.set_round_retries(args.round_retries);
+    if let Some(secs) = args.ramp_up_secs {
+        searcher.set_ramp_up(Duration::from_secs(secs));
+    }
+    if args.throttle_on_pressure {
+        enable_pressure_throttle(&mut searcher);
+    }
+    if let Some(secs) = args.stall_warning_secs {
+        searcher.set_stall_warning(Duration::from_secs(secs));
+    }
+    if let Some(secs) = args.progress_secs {
+        searcher.set_progress_interval(Duration::from_secs(secs));
+    }
+    if let Some(path) = &args.fifo {
+        match EventPipe::open(path) {
+            Ok(pipe) => searcher.set_event_pipe(pipe),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    match args
+        .deny_address
+        .iter()
+        .map(|address| parse_address("deny-address", address))
+        .collect::<Result<Vec<Address>, SearchError>>()
+    {
+        Ok(addresses) => searcher.deny(addresses),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+    if let Some(path) = &args.deny_file {
+        match read_address_list(path) {
+            Ok(addresses) => searcher.deny(addresses),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let multiple_hashes = config.init_code_hashes.len() > 1;
+    let prior_best = match &args.ledger {
+        Some(path) => match load_ledger(path, &config) {
+            Ok(Some((prior_best, covered))) => {
+                searcher.skip(covered);
+                Some(prior_best)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    if let Some(path) = &args.salt_list {
+        search_salt_list(&searcher, &config, path, &args);
+        return;
+    }
+
+    // set once, the handler can't be replaced
+    let stop = Arc::new(AtomicBool::new(false));
+    if args.repeat.is_some() || args.watch || args.retry_with_random.is_some() {
+        let stop = stop.clone();
+        let unit = match (args.watch, args.repeat) {
+            (true, _) => "batch",
+            (false, Some(_)) => "run",
+            (false, None) => "search",
+        };
+        let result = ctrlc::set_handler(move || {
+            if stop.swap(true, Ordering::Relaxed) {
+                std::process::exit(130);
+            }
+            eprintln!("Stopping after this {}, Ctrl-C again to quit now", unit);
+        });
+        if let Err(e) = result {
+            eprintln!("Warning: failed to handle Ctrl-C: {}", e);
+        }
+    }
+
+    if args.watch {
+        if multiple_hashes {
+            eprintln!("Error: --watch searches a single init code hash");
+            std::process::exit(1);
+        }
+        let found = watch(
+            &searcher,
+            &config,
+            prior_best,
+            args.ledger.as_deref(),
+            args.rotate_prefix,
+            &stop,
+        );
+        let found = match found {
+            Some(found) => found,
+            None => {
+                eprintln!("Error: the salt window is empty, there is nothing to search");
+                std::process::exit(1);
+            }
+        };
+        match &args.salt_file {
+            Some(path) => reveal_to_file(path, &found, &config),
+            None => print_best(&found, &config, args.verbose, !args.no_color),
+        }
+        if let Some(command) = &args.on_success {
+            on_success(command, &config, &found);
+        }
+        return;
+    }
+
+    let mut results: Vec<(U256, search::AddressSalt, Option<u128>)> = Vec::new();
+    let mut below_floor = false;
+    for init_code_hash in &config.init_code_hashes {
+        searcher.reset();
+        if let Some(prior_best) = prior_best {
+            searcher.seed_best(prior_best);
+        }
+        let mut params = config.search_params(*init_code_hash);
+        let start_time = Instant::now();
+        let (found, mut attempts, mut covered) = match args.repeat {
+            Some(repeats) => repeat_search(&searcher, &config, &params, repeats, prior_best, &stop),
+            None => {
+                let found = searcher.search(params);
+                let covered: Vec<(U256, U256)> =
+                    searched_range(&searcher, &params).into_iter().collect();
+                (found, searcher.total_attempts(), covered)
+            }
+        };
+        let mut found = match found {
+            Some(found) => found,
+            None => {
+                eprintln!("Error: no address found, every round was already searched or failed");
+                std::process::exit(1);
+            }
+        };
+        // the runs of --repeat each start elsewhere, and a prior best wasn't found here
+        let mut position = match args.repeat {
+            Some(_) => None,
+            None => scan_position(&params, &found).filter(|_| Some(found) != prior_best),
+        };
+        let retries = args.retry_with_random.unwrap_or(0);
+        for retry in 1..=retries {
+            if config.succeeded(config.score_mode.score(found.address)) {
+                break;
+            }
+            if stop.load(Ordering::Relaxed) {
+                println!("Stopped before retry {} of {}", retry, retries);
+                break;
+            }
+            params.initial_salt_n = config.run_initial_salt(params.span(), retry);
+            println!(
+                "Target not met, retry {} of {} from a {} salt",
+                retry,
+                retries,
+                if config.seed.is_some() {
+                    "seeded"
+                } else {
+                    "random"
+                }
+            );
+            searcher.reset();
+            searcher.seed_best(found);
+            let previous = found;
+            // seeded, so there is always a best
+            found = searcher.search(params).unwrap_or(previous);
+            if found != previous {
+                position = scan_position(&params, &found);
+            }
+            attempts += searcher.total_attempts();
+            covered.extend(searched_range(&searcher, &params));
+        }
+        if retries > 0 {
+            println!(
+                "{} attempts in total",
+                attempts.to_formatted_string(&Locale::en)
+            );
+        }
+
+        if multiple_hashes {
+            println!(
+                "\nInit code hash 0x{}",
+                hex::encode(bytes32(*init_code_hash))
+            );
+        }
+        match &args.salt_file {
+            Some(path) => reveal_to_file(path, &found, &config),
+            None => print_result(&found, *init_code_hash, &config, &args),
+        }
+        below_floor |= report_band(&config, &found);
+
+        if let Some(path) = &args.ledger {
+            if !update_ledger(path, &config, &found, &covered) {
+                println!("No improvement on the ledger best");
+            }
+        }
+        if let Some(command) = &args.on_success {
+            on_success(command, &config, &found);
+        }
+
+        if args.histogram {
+            print_histogram(&searcher.histogram());
+        }
+        if args.profile {
+            print_profile(&searcher.timings(), searcher.total_attempts());
+        }
+        if config.sample_size.is_some() {
+            let mut sample = searcher.sample();
+            config.score_mode.sort_best_first(&mut sample);
+            print_sample(&sample);
+        }
+        if args.stats {
+            print_stats(
+                &config,
+                &found,
+                attempts,
+                start_time.elapsed(),
+                &searcher.round_durations(),
+            );
+        }
+        results.push((*init_code_hash, found, position));
+    }
+
+    if multiple_hashes {
+        print_ranking(&mut results, &config);
+    }
+    if below_floor {
+        std::process::exit(1);
+    }
+}
+
+/// Print where the best falls in the band of --min-zeros and --max-zeros, if one is set
+///
+/// # Arguments
+/// * `config` - The resolved configuration, for the band and scoring
+/// * `found` - The best address found
+///
+/// # Returns
+/// * Whether the best is short of the floor, which fails the search
+fn report_band(config: &Config, found: &search::AddressSalt) -> bool {
+    let score = config.score_mode.score(found.address);
+    match config.band_outcome(score) {
+        Some(BandOutcome::BelowFloor) => {
+            eprintln!(
+                "Error: the best scores {}, short of the --min-zeros floor of {}",
+                score,
+                config.min_zeros.unwrap_or(0)
+            );
+            return true;
+        }
+        Some(BandOutcome::WithinBand) => println!(
+            "Accepted at {}, within the band of {} to {}",
+            score,
+            config.min_zeros.unwrap_or(0),
+            config
+                .max_zeros
+                .map_or("any".to_string(), |max_zeros| max_zeros.to_string())
+        ),
+        Some(BandOutcome::ReachedCap) => {
+            println!("Accepted at {}, the --max-zeros cap was reached", score)
+        }
+        None => {}
+    }
+    return false;
+}
+
+/// Print the core-hours a search consumed and those one more leading zero would take
+///
+/// # Arguments
+/// * `config` - The resolved configuration, for the thread count and scoring
+/// * `found` - The best address found
+/// * `attempts` - The number of attempts made, including retries
+/// * `elapsed` - The wall-clock time the search took
+/// * `round_durations` - The distribution of the time each round took
+fn print_stats(
+    config: &Config,
+    found: &search::AddressSalt,
+    attempts: u128,
+    elapsed: Duration,
+    round_durations: &DurationSketch,
+) {
+    let elapsed_secs = elapsed.as_secs_f64();
+    if elapsed_secs == 0.0 || attempts == 0 {
+        return;
+    }
+    let rate = attempts as f64 / elapsed_secs;
+    let consumed = elapsed_secs * config.num_threads as f64 / 3600.0;
+    let score = config.score_mode.score(found.address);
+    let next = (score + 1).min(u8::MAX as u32) as u8;
+    let needed = core_hours(
+        config.score_mode.expected_attempts(next),
+        rate,
+        config.num_threads,
+    );
+    println!("\nStats:");
+    println!(
+        "  consumed:       {:.4} core-hours ({} on {} threads)",
+        consumed,
+        fmt_dms(elapsed.as_secs() as u128),
+        config.num_threads
+    );
+    println!(
+        "  rate:           {} attempts/s",
+        (rate.round() as u128).to_formatted_string(&Locale::en)
+    );
+    // a p99 far above the median points at a slow core or a busy machine
+    if let (Some(min), Some(median), Some(p99)) = (
+        round_durations.min(),
+        round_durations.quantile(0.5),
+        round_durations.quantile(0.99),
+    ) {
+        println!(
+            "  round time:     min {:.3?}, median {:.3?}, p99 {:.3?} over {} rounds",
+            min,
+            median,
+            p99,
+            round_durations.count()
+        );
+    }
+    println!(
+        "  one more:       ~{:.4} core-hours for {} {} at this rate",
+        needed,
+        next,
+        config.score_mode.unit()
+    );
+    // a whole score off is 16 times more or fewer attempts than expected for leading zeros
+    let expected = config.score_mode.expected_best_score_for_attempts(attempts);
+    let luck = match score as f64 - expected {
+        difference if difference >= 1.0 => "lucky",
+        difference if difference <= -1.0 => "unlucky",
+        _ => "as expected",
+    };
+    println!(
+        "  expected best:  {:.2} {} for {} attempts, reached {}, {}",
+        expected,
+        config.score_mode.unit(),
+        attempts.to_formatted_string(&Locale::en),
+        score,
+        luck
+    );
+}
 
-    let zeros: u8 = args.zeros.unwrap_or(12);
-    let num_rounds: u128 = args.num_rounds.unwrap_or(100_000);
-    let round_size: u128 = args.round_size.unwrap_or(1_000_000);
-    let num_threads: usize = args.num_threads.unwrap_or(16);
+/// Print the share of each nibble value at each address position, and the largest
+/// deviation from the 1/16 a uniform distribution gives
+///
+/// # Arguments
+/// * `frequencies` - The counts from `nibble_frequencies`
+fn print_nibble_frequencies(frequencies: &NibbleFrequencies) {
+    let total: u64 = frequencies[0].iter().sum();
+    println!(
+        "Share of each nibble per position in % over {} addresses, uniform is 6.25",
+        total.to_formatted_string(&Locale::en)
+    );
+    let header: Vec<String> = (0..16).map(|nibble| format!("{:>5x}", nibble)).collect();
+    println!("pos {}", header.join(""));
+    for (position, counts) in frequencies.iter().enumerate() {
+        let shares: Vec<String> = counts
+            .iter()
+            .map(|count| format!("{:>5.2}", 100.0 * *count as f64 / total as f64))
+            .collect();
+        println!("{:>3} {}", position, shares.join(""));
+    }
+    println!(
+        "Largest deviation from uniform: {:.2}%",
+        100.0 * max_deviation(frequencies)
+    );
+}
 
-    let expected_attempts: u128 = 16_u128.pow(zeros as u32);
+/// Print the best of a sparse sample and the share of the addresses reaching each score,
+/// beside the share expected
+///
+/// # Arguments
+/// * `sample` - The result of `sparse_sample`
+/// * `config` - The configuration, for the score mode
+/// * `args` - The command line, for how to print the best
+fn print_sparse_sample(sample: &SparseSample, config: &Config, args: &Args) {
+    print_best(&sample.best, config, args.verbose, !args.no_color);
+    let total: u64 = sample.scores.iter().sum();
     println!(
-        "Expected attempts for {} zeros: {}",
-        zeros,
-        expected_attempts.to_formatted_string(&Locale::en)
+        "\n{:>6} {:>16} {:>12} {:>12}",
+        "score", "count", "at least %", "expected %"
     );
+    let mut at_least = total;
+    for (score, count) in sample.scores.iter().enumerate() {
+        let expected = 100.0 / config.score_mode.expected_attempts(score as u8);
+        println!(
+            "{:>6} {:>16} {:>12.6} {:>12.6}",
+            score,
+            count.to_formatted_string(&Locale::en),
+            100.0 * at_least as f64 / total as f64,
+            expected
+        );
+        at_least -= count;
+    }
+}
 
-    // the initial salt should start with 20 bytes matching the sender address
-    // 20 bytes is 40 characters
-    let first_40_chars_of_sender = format!("{:x}", sender)[..40].to_string();
-    let initial_salt_hex = format!("{}000000000000000000000000", first_40_chars_of_sender);
-    // 20 bytes is leaves a search space of 12 bytes or 96 bits
+/// Check a config file, printing every problem found, exiting with an error if any
+///
+/// # Arguments
+/// * `path` - The path of the TOML file
+fn validate_config(path: &Path) {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("Error: {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    match ConfigFile::parse(&contents) {
+        Ok(_) => println!("{} is valid", path.display()),
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("Error: {}: {}", path.display(), error);
+            }
+            std::process::exit(1);
+        }
+    }
+}
 
-    // setup
-    let initial_salt_n = U256::from_str_radix(&initial_salt_hex, 16).unwrap();
-    let params = search::SearchParams {
+/// The init code given with --init-code or --init-code-file, or built from --template
+///
+/// # Arguments
+/// * `args` - The parsed command line
+///
+/// # Returns
+/// * The creation bytecode, `None` if only the init code hash is known
+fn init_code(args: &Args) -> Result<Option<Vec<u8>>, SearchError> {
+    if let Some(template) = args.template {
+        let implementation =
+            parse_address("implementation", args.implementation.as_ref().unwrap())?;
+        return Ok(Some(template.init_code(implementation)));
+    }
+    if let Some(path) = &args.init_code_file {
+        return read_init_code_file(path).map(Some);
+    }
+    match &args.init_code {
+        Some(init_code) => {
+            let init_code = parse_hex("init-code", init_code)?;
+            if init_code.is_empty() {
+                return Err(SearchError::EmptyBytecode);
+            }
+            Ok(Some(init_code))
+        }
+        None => Ok(None),
+    }
+}
+
+/// The function the factory deploys with, given by --factory-abi or known for the factory
+///
+/// # Returns
+/// * The function, or `None` for an unknown factory without --factory-abi
+fn factory_abi(config: &Config, args: &Args) -> Option<FactoryAbi> {
+    args.factory_abi.or_else(|| FactoryAbi::of(config.factory))
+}
+
+/// Print the transaction deploying a result through the factory
+///
+/// # Arguments
+/// * `found` - The address and salt to deploy
+/// * `config` - The resolved configuration, for the factory
+/// * `args` - The parsed command line, for the init code
+fn print_calldata(found: &search::AddressSalt, config: &Config, args: &Args) {
+    let abi = match factory_abi(config, args) {
+        Some(abi) => abi,
+        None => {
+            eprintln!("Error: --output calldata needs --factory-abi for this factory");
+            return;
+        }
+    };
+    match init_code(args) {
+        Ok(Some(init_code)) => println!("{}", fmt_calldata(found, config.factory, abi, &init_code)),
+        Ok(None) => {
+            eprintln!("Error: --output calldata needs --init-code, --init-code-file or --template")
+        }
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+/// Print the result of a search in the format of --output
+///
+/// # Arguments
+/// * `found` - The best address and its salt
+/// * `init_code_hash` - The init code hash it was searched for
+/// * `config` - The configuration of the run
+/// * `args` - The parsed command line
+fn print_result(found: &search::AddressSalt, init_code_hash: U256, config: &Config, args: &Args) {
+    match args.output {
+        OutputFormat::Human => print_best(found, config, args.verbose, !args.no_color),
+        OutputFormat::Foundry => println!("{}", fmt_foundry(found)),
+        OutputFormat::Calldata => print_calldata(found, config, args),
+        OutputFormat::Legacy => println!("{}", fmt_legacy(found)),
+        OutputFormat::Json | OutputFormat::Jsonl => println!(
+            "{}",
+            fmt_json(
+                found,
+                config.factory,
+                init_code_hash,
+                config.score_mode,
+                args.output == OutputFormat::Jsonl
+            )
+        ),
+    }
+}
+
+/// Append the final best to the --salt-file rather than printing its salt, for --mask-salts
+///
+/// # Arguments
+/// * `path` - The file to append `salt,address` to, the format `verify-batch` reads
+/// * `found` - The best address and its salt
+/// * `config` - The configuration of the run, for scoring
+fn reveal_to_file(path: &Path, found: &search::AddressSalt, config: &Config) {
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    // only the owner can read the salt
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let result = options.open(path).and_then(|mut file| {
+        writeln!(
+            file,
+            "0x{},{}",
+            hex::encode(bytes32(found.salt_n)),
+            to_checksum(&found.address, None)
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("Error: failed to write {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+    println!(
+        "Best: {} {}, salt written to {}",
+        config.score_mode.describe(found.address),
+        to_checksum(&found.address, None),
+        path.display()
+    );
+}
+
+/// Check the factory could be a deployed contract
+///
+/// # Arguments
+/// * `factory` - The factory address
+/// * `strict` - Fail rather than warn
+///
+/// # Returns
+/// * A warning to print if the factory is the zero address, or with `strict` an error
+fn check_factory(factory: Address, strict: bool) -> Result<Option<String>, SearchError> {
+    // no contract lives at the zero address, the factory was likely left unset
+    if !factory.is_zero() {
+        return Ok(None);
+    }
+    let problem = "factory is the zero address, where no factory is deployed";
+    if strict {
+        return Err(SearchError::InvalidArgument(problem.to_string()));
+    }
+    Ok(Some(format!(
+        "{}, its addresses can never be deployed to",
+        problem
+    )))
+}
+
+/// Combine the command line with the config file, if any, into the parameters of the run
+///
+/// Flags given on the command line take precedence over the config file,
+/// which takes precedence over the defaults. The factory, deployer and init code
+/// hash can also come from CREATE2_* environment variables, which clap reads in
+/// place of a missing flag, so they rank between the command line and the file.
+///
+/// # Arguments
+/// * `args` - The parsed command line
+///
+/// # Returns
+/// * The resolved configuration, or an error if a required parameter is missing
+fn resolve_config(args: &Args) -> Result<Config, SearchError> {
+    let file = match &args.config {
+        Some(path) => ConfigFile::load(path)?,
+        None => ConfigFile::default(),
+    };
+
+    let factory = args
+        .factory
+        .clone()
+        .or(file.factory)
+        .ok_or(SearchError::MissingArgument("factory"))?;
+    // a factory known by name, such as the EIP-2470 singleton, implies its salt convention
+    let named_factory = known_factory(&factory);
+    let factory: Address = match named_factory {
+        Some(factory) => factory,
+        None => parse_address("factory", &factory)?,
+    };
+    if let Some(warning) = check_factory(factory, args.strict)? {
+        eprintln!("Warning: {}", warning);
+    }
+
+    // --compare-tools takes the full salt as given, so it needs no deployer
+    let full_salt = args.full_salt
+        || file.full_salt.unwrap_or(false)
+        || args.compare_tools
+        || named_factory.is_some();
+    let salt_guard_bytes = args
+        .salt_guard_bytes
+        .map(usize::from)
+        .or(file.salt_guard_bytes)
+        .unwrap_or(DEFAULT_SALT_GUARD_BYTES);
+    let deployer: Option<Address> = if full_salt {
+        None
+    } else {
+        let deployer = args
+            .deployer
+            .clone()
+            .or(file.deployer)
+            .ok_or(SearchError::MissingArgument("deployer"))?;
+        Some(parse_address("deployer", &deployer)?)
+    };
+
+    let init_code_hashes = if let Some(init_code) = init_code(args)? {
+        let hash = init_code_hash(&init_code, &[], args.hash_algo);
+        vec![format!("0x{}", hex::encode(bytes32(hash)))]
+    } else if args.init_code_hash.is_empty() {
+        file.init_code_hashes.unwrap_or_default()
+    } else {
+        args.init_code_hash.clone()
+    };
+    if init_code_hashes.is_empty() {
+        return Err(SearchError::MissingArgument("init-code-hash"));
+    }
+    // use U256 because it is copyable in struct via #[derive(Copy, Clone)]
+    let init_code_hashes: Vec<U256> = init_code_hashes
+        .iter()
+        .map(|hash| parse_hex_u256("init-code-hash", hash))
+        .collect::<Result<_, _>>()?;
+
+    // --salt-step is range checked by clap, and the config file when it is loaded
+    let salt_step = args.salt_step.or(file.salt_step).unwrap_or(1);
+
+    let checksum_word = match (args.checksum_word, &file.checksum_word) {
+        (Some(word), _) => Some(word),
+        (None, Some(word)) => Some(word.parse().map_err(SearchError::InvalidArgument)?),
+        (None, None) => None,
+    };
+    let maximize_byte = match (args.maximize_byte, &file.maximize_byte) {
+        (Some(byte), _) => Some(byte),
+        (None, Some(byte)) => Some(parse_byte(byte).map_err(SearchError::InvalidArgument)?),
+        (None, None) => None,
+    };
+    let closest_to = args
+        .closest_to
+        .as_ref()
+        .or(file.closest_to.as_ref())
+        .map(|target| parse_address("closest-to", target))
+        .transpose()?;
+    let score_mode = match (
+        closest_to,
+        maximize_byte,
+        checksum_word,
+        args.leading_nibble
+            .as_ref()
+            .or(file.leading_nibble.as_ref()),
+        args.score.as_ref().or(file.score.as_ref()),
+    ) {
+        (Some(target), _, _, _, _) => ScoreMode::ClosestTo(target),
+        (None, Some(byte), _, _, _) => ScoreMode::ByteCount(byte),
+        (None, None, Some(word), _, _) => ScoreMode::ChecksumWord(word),
+        (None, None, None, Some(nibble), _) => ScoreMode::LeadingNibble(
+            parse_leading_nibble(nibble).map_err(SearchError::InvalidArgument)?,
+        ),
+        (None, None, None, None, Some(score)) => {
+            parse_score(score).map_err(SearchError::InvalidArgument)?
+        }
+        (None, None, None, None, None) => ScoreMode::LeadingZeros,
+    };
+
+    let event_format = match (args.log_format, file.log_format) {
+        (Some(format), _) => format,
+        (None, Some(format)) => format.parse().map_err(SearchError::InvalidArgument)?,
+        (None, None) if args.output == OutputFormat::Legacy => EventFormat::Legacy,
+        (None, None) if args.output == OutputFormat::Jsonl => EventFormat::Json,
+        (None, None) => EventFormat::default(),
+    };
+
+    let round_size = args
+        .round_size
+        .or(file.round_size.map(u128::from))
+        .unwrap_or(1_000_000);
+    // --num-rounds on the command line overrides --max-attempts in the config file
+    let max_attempts = if args.cover_full_window {
+        let window = salt_space(deployer, salt_guard_bytes)
+            .and_then(|space| window_attempts(space, salt_step))
+            .and_then(|attempts| attempts.checked_mul(init_code_hashes.len() as u128))
+            .ok_or_else(|| {
+                SearchError::InvalidArgument(
+                    "--cover-full-window needs a deployer prefix and a window of under 2^128 salts"
+                        .to_string(),
+                )
+            })?;
+        Some(window)
+    } else if args.num_rounds.is_some() {
+        None
+    } else {
+        args.max_attempts.or(file.max_attempts).map(u128::from)
+    };
+    let num_rounds = match max_attempts {
+        Some(max_attempts) => max_attempts.div_ceil(round_size),
+        None => args
+            .num_rounds
+            .or(file.num_rounds.map(u128::from))
+            .unwrap_or(100_000),
+    };
+    let num_threads = match args.num_threads.or(file.num_threads) {
+        // the global pool's size is fixed when it's first used
+        Some(num_threads) if args.use_global_pool => {
+            eprintln!(
+                "Warning: --num-threads {} is ignored with --use-global-pool, which runs on \
+                 rayon's {} threads, set RAYON_NUM_THREADS to change it",
+                num_threads,
+                rayon::current_num_threads()
+            );
+            rayon::current_num_threads()
+        }
+        _ if args.use_global_pool => rayon::current_num_threads(),
+        Some(num_threads) => num_threads,
+        None => default_num_threads(),
+    };
+
+    // the same budget as one contiguous block of salts per thread, a round each
+    let (round_size, num_rounds, max_attempts) = if args.thread_blocks {
+        let total = max_attempts.unwrap_or(round_size * num_rounds);
+        let per_hash = (total / init_code_hashes.len() as u128).max(1);
+        let block_size = per_hash.div_ceil(num_threads as u128);
+        (block_size, total.div_ceil(block_size), Some(total))
+    } else {
+        (round_size, num_rounds, max_attempts)
+    };
+
+    let threshold_return = match (args.threshold_return, &file.threshold_return) {
+        (Some(threshold_return), _) => threshold_return,
+        (None, Some(threshold_return)) => threshold_return
+            .parse()
+            .map_err(SearchError::InvalidArgument)?,
+        (None, None) => ThresholdReturn::default(),
+    };
+    let tie_break = match (args.tie_break, &file.tie_break) {
+        (Some(tie_break), _) => tie_break,
+        (None, Some(tie_break)) => tie_break.parse().map_err(SearchError::InvalidArgument)?,
+        (None, None) => TieBreak::default(),
+    };
+    let min_zeros = args.min_zeros.or(file.min_zeros);
+    let max_zeros = args.max_zeros.or(file.max_zeros);
+    if let (Some(min_zeros), Some(max_zeros)) = (min_zeros, max_zeros) {
+        if min_zeros > max_zeros {
+            return Err(SearchError::InvalidArgument(format!(
+                "--min-zeros {} is above --max-zeros {}",
+                min_zeros, max_zeros
+            )));
+        }
+    }
+
+    let mut config = Config {
+        factory,
         deployer,
-        initial_salt_n,
-        init_code_hash: init_code_hash.clone(),
+        salt_guard_bytes,
+        sender_salting: args
+            .sender_salting
+            .as_deref()
+            .or(file.sender_salting.as_deref())
+            .map(|sender| parse_address("sender-salting", sender))
+            .transpose()?,
+        initial_salt_n: initial_salt(deployer, salt_guard_bytes),
+        init_code_hashes,
+        zeros: args.zeros.or(file.zeros).unwrap_or(12),
+        score_mode,
+        stop_at_target: args.stop_at_target || file.stop_at_target.unwrap_or(false),
+        threshold_return,
+        tie_break,
+        milestones: if args.milestones.is_empty() {
+            file.milestones.unwrap_or_default()
+        } else {
+            args.milestones.clone()
+        },
+        min_zeros,
+        max_zeros,
         round_size,
         num_rounds,
+        max_attempts,
+        num_threads,
+        salt_step,
+        histogram: args.histogram || file.histogram.unwrap_or(false),
+        profile: args.profile || file.profile.unwrap_or(false),
+        sample_size: args.sample.map(|n| n as usize).or(file.sample),
+        event_format,
+        seed: args.seed.or(file.seed),
+        seed_index: args.seed_index.or(file.seed_index).unwrap_or(0),
+        mask_salts: args.mask_salts || file.mask_salts.unwrap_or(false),
     };
+    if config.seed.is_some() {
+        let span = config.search_params(config.init_code_hashes[0]).span();
+        config.initial_salt_n = config.run_initial_salt(span, 0);
+    }
+    Ok(config)
+}
 
-    let searcher = search::Searcher::new(num_threads);
-    let found: search::AddressSalt = searcher.search(params);
+/// Rounds run per thread by `--scaling-test` at each thread count,
+/// enough that the rate isn't dominated by the pool warming up
+const SCALING_TEST_ROUNDS_PER_THREAD: u128 = 4;
 
-    println!("Best:\n");
+/// Benchmark the search at 1, 2, 4, ... threads up to the available cores
+/// and print the rate and speedup over a single thread for each, and the rate of the
+/// same budget split into one block per thread as `--thread-blocks` does
+///
+/// # Arguments
+/// * `config` - The configuration, the first init code hash and the round size are used
+/// * `pin_threads` - Pin the worker threads of each pool to cores
+///
+/// # Returns
+/// * An error if a thread pool can't be built
+fn scaling_test(config: &Config, pin_threads: bool) -> Result<(), SearchError> {
+    let max_threads = default_num_threads();
+    let mut thread_counts: Vec<usize> = std::iter::successors(Some(1), |n| Some(n * 2))
+        .take_while(|n| *n < max_threads)
+        .collect();
+    thread_counts.push(max_threads);
+
+    println!(
+        "{:>8} {:>16} {:>8} {:>16}",
+        "threads", "attempts/s", "speedup", "blocks/s"
+    );
+    let mut single_thread_rate = None;
+    for num_threads in thread_counts {
+        let params = search::SearchParams {
+            num_rounds: num_threads as u128 * SCALING_TEST_ROUNDS_PER_THREAD,
+            max_attempts: None,
+            ..config.search_params(config.init_code_hashes[0])
+        };
+        let block_params = search::SearchParams {
+            round_size: params.round_size * SCALING_TEST_ROUNDS_PER_THREAD,
+            num_rounds: num_threads as u128,
+            ..params
+        };
+        // each pool is dropped, joining its threads, before the next one is built
+        let searcher = search::Searcher::new(num_threads, pin_threads)?;
+        let rate = searcher.measure_rate(params);
+        let block_rate = searcher.measure_rate(block_params);
+        let speedup = rate / *single_thread_rate.get_or_insert(rate);
+        println!(
+            "{:>8} {:>16} {:>7.2}x {:>16}",
+            num_threads,
+            (rate.round() as u128).to_formatted_string(&Locale::en),
+            speedup,
+            (block_rate.round() as u128).to_formatted_string(&Locale::en)
+        );
+    }
+    Ok(())
+}
+
+/// Report the best of the salts listed in a file, for each init code hash
+///
+/// # Arguments
+/// * `searcher` - The searcher, whose thread pool evaluates the salts
+/// * `config` - The configuration, for the factory, init code hashes and score mode
+/// * `path` - The file of salts, see `read_salt_list`
+/// * `args` - The command line, for how to print the result
+fn search_salt_list(searcher: &search::Searcher, config: &Config, path: &Path, args: &Args) {
+    let salts = match read_salt_list(path) {
+        Ok(salts) => salts,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "Evaluating {} salts from {}",
+        salts.len().to_formatted_string(&Locale::en),
+        path.display()
+    );
+
+    for init_code_hash in &config.init_code_hashes {
+        if config.init_code_hashes.len() > 1 {
+            println!(
+                "\nInit code hash 0x{}",
+                hex::encode(bytes32(*init_code_hash))
+            );
+        }
+        match searcher.search_list(&config.search_params(*init_code_hash), &salts) {
+            Some(found) => print_result(&found, *init_code_hash, config, args),
+            None => println!("No salt to evaluate, the list is empty or every address is denied"),
+        }
+    }
+}
+
+/// Rounds per thread in each batch of `--watch`, the best is persisted between batches
+const WATCH_ROUNDS_PER_THREAD: u128 = 16;
+
+/// Search batch after batch of rounds, each starting where the last one ended,
+/// until the target is reached or the salt space runs out
+///
+/// With a deployer prefix only the last 12 bytes of the salt are searched, stepping
+/// past them would change the prefix, so the batch which would is cut short to end at
+/// the last salt of the window, however small the window. With `rotate_prefix` the search
+/// instead carries on into the next prefix, the low bytes rolling over into it as the salt
+/// is one 256-bit number, and past the last salt wraps around to salt 0, stopping once it
+/// is back at the first salt: the batch which would cross the end of the space or the
+/// first salt is cut short to end exactly there.
+///
+/// # Arguments
+/// * `searcher` - The searcher, its best carries over from batch to batch
+/// * `config` - The configuration, `num_rounds` and `max_attempts` are ignored
+/// * `prior_best` - The best recorded in the ledger before the run, if any
+/// * `ledger` - The ledger to checkpoint each batch to, if any
+/// * `rotate_prefix` - Whether to walk on through the whole 256-bit space
+/// * `stop` - Set on Ctrl-C, no batch starts once it is
+///
+/// # Returns
+/// * The best address found, or the prior best if nothing beat it,
+///   `None` if there was neither as the salt window is empty
+fn watch<D: AddressDeriver>(
+    searcher: &search::Searcher<D>,
+    config: &Config,
+    prior_best: Option<search::AddressSalt>,
+    ledger: Option<&Path>,
+    rotate_prefix: bool,
+    stop: &AtomicBool,
+) -> Option<search::AddressSalt> {
+    let mut params = search::SearchParams {
+        num_rounds: config.num_threads as u128 * WATCH_ROUNDS_PER_THREAD,
+        max_attempts: None,
+        ..config.search_params(config.init_code_hashes[0])
+    };
+    let batch_span = U256::from(params.round_size)
+        * U256::from(params.salt_step)
+        * U256::from(params.num_rounds);
+    let first_salt = params.initial_salt_n;
+    let space = salt_space(config.deployer, config.salt_guard_bytes);
+    // the first salt past the searchable space, None when it's the end of the 32 bytes
+    let mut salt_end = match rotate_prefix {
+        true => None,
+        false => space.map(|space| first_salt + space),
+    };
+
+    let mut best = prior_best;
+    loop {
+        let batch_end = params.initial_salt_n.checked_add(batch_span);
+        let exhausted = match (batch_end, salt_end) {
+            (None, _) => true,
+            (Some(batch_end), Some(salt_end)) => batch_end > salt_end,
+            (Some(_), None) => false,
+        };
+        let mut batch = params;
+        if exhausted {
+            // fewer than a batch of salts left, so `left` can't be all 2^256 of them
+            let left = match salt_end {
+                Some(salt_end) => salt_end - params.initial_salt_n,
+                None => U256::MAX - params.initial_salt_n + 1,
+            };
+            let step = U256::from(params.salt_step);
+            // a salt in the last partial step still counts
+            let attempts = ((left + step - 1) / step).as_u128();
+            if attempts == 0 {
+                match rotate_prefix {
+                    true => println!("Searched the whole 256-bit salt space, stopping"),
+                    false => println!("Reached the end of the salt space, stopping"),
+                }
+                break;
+            }
+            batch.max_attempts = Some(attempts);
+            batch.num_rounds = attempts.div_ceil(params.round_size);
+        }
+
+        searcher.reset();
+        if let Some(best) = best {
+            searcher.seed_best(best);
+        }
+        let found = searcher.search(batch);
+        // written every batch to checkpoint the salts searched
+        if let (Some(path), Some(found)) = (ledger, &found) {
+            let covered: Vec<(U256, U256)> = searched_range(searcher, &batch).into_iter().collect();
+            update_ledger(path, config, found, &covered);
+        }
+        best = found.or(best);
+
+        if searcher.target_reached() {
+            break;
+        }
+        if stop.load(Ordering::Relaxed) {
+            println!("Stopped, the watch ends with this batch");
+            break;
+        }
+        let (next_salt, wrapped) = params.initial_salt_n.overflowing_add(batch.span());
+        if wrapped {
+            println!(
+                "Wrapped around past the last salt, searching on from 0x{} up to the first salt",
+                hex::encode(bytes32(next_salt))
+            );
+            salt_end = Some(first_salt);
+        } else if let Some(space) = space.filter(|_| rotate_prefix) {
+            if next_salt / space != params.initial_salt_n / space {
+                println!(
+                    "Rotated to the next prefix, salts from 0x{} on, which don't start with \
+                     the deployer",
+                    hex::encode(bytes32(next_salt / space * space))
+                );
+            }
+        }
+        if salt_end.is_some_and(|salt_end| rotate_prefix && next_salt >= salt_end) {
+            println!("Searched the whole 256-bit salt space, stopping");
+            break;
+        }
+        params.initial_salt_n = next_salt;
+    }
+    return best;
+}
+
+/// Warn if the factory has no code, e.g. an EOA passed by mistake, since a long search
+/// for it would find addresses no contract can ever be deployed to
+///
+/// # Arguments
+/// * `url` - The JSON-RPC endpoint
+/// * `factory` - The CREATE2 factory address
+#[cfg(feature = "rpc")]
+fn check_factory_code(url: &str, factory: Address) {
+    match search_create2::rpc::get_code(url, factory) {
+        Ok(code) if code.is_empty() => eprintln!(
+            "Warning: the factory {} has no code on this chain, it looks like an EOA \
+             and the addresses found can't be deployed to through it",
+            to_checksum(&factory, None)
+        ),
+        Ok(_) => {}
+        Err(e) => eprintln!("Warning: could not check the factory has code: {}", e),
+    }
+}
+
+#[cfg(not(feature = "rpc"))]
+fn check_factory_code(_url: &str, _factory: Address) {
+    eprintln!("Warning: built without the rpc feature, --rpc is ignored");
+}
+
+/// Throttle the search on the pressure read from the machine, with the default limits
+///
+/// # Arguments
+/// * `searcher` - The searcher to throttle
+#[cfg(feature = "pressure")]
+fn enable_pressure_throttle(searcher: &mut search::Searcher) {
+    use search_create2::pressure::{PressureLimits, SystemPressure};
+
+    if cfg!(not(target_os = "linux")) {
+        eprintln!("Warning: the pressure can only be read on Linux, the search won't throttle");
+    }
+    searcher.set_pressure_throttle(Box::new(SystemPressure), PressureLimits::default());
+}
+
+#[cfg(not(feature = "pressure"))]
+fn enable_pressure_throttle(_searcher: &mut search::Searcher) {
+    eprintln!("Warning: built without the pressure feature, --throttle-on-pressure is ignored");
+}
+
+/// Run the --on-success command if the result meets the target, reporting its exit status
+///
+/// # Arguments
+/// * `command` - The shell command, with placeholders
+/// * `config` - The configuration, for the score mode and the target
+/// * `found` - The best address found
+fn on_success(command: &str, config: &Config, found: &search::AddressSalt) {
+    // a search which ran out of rounds short of the target is not a success
+    if !config.succeeded(config.score_mode.score(found.address)) {
+        return;
+    }
+    match run_on_success(command, found) {
+        Ok(status) if status.success() => println!("--on-success command succeeded"),
+        Ok(status) => eprintln!("Error: --on-success command failed with {}", status),
+        Err(e) => eprintln!("Error: failed to run the --on-success command: {}", e),
+    }
+}
+
+/// Read the best and the salt ranges searched recorded in the ledger, if there is one
+///
+/// # Arguments
+/// * `path` - The path of the ledger file
+/// * `config` - The configuration of the run, which must match the ledger's campaign
+///
+/// # Returns
+/// * The all-time best and the `[from, to)` salt ranges searched, or `None` for a new ledger
+fn load_ledger(
+    path: &Path,
+    config: &Config,
+) -> Result<Option<(search::AddressSalt, Vec<(U256, U256)>)>, SearchError> {
+    if config.init_code_hashes.len() > 1 {
+        return Err(SearchError::InvalidArgument(
+            "--ledger supports a single init code hash".to_string(),
+        ));
+    }
+    let ledger = match Ledger::load(path)? {
+        Some(ledger) => ledger,
+        None => return Ok(None),
+    };
+    if !ledger.matches(config.factory, config.deployer, config.init_code_hashes[0]) {
+        return Err(SearchError::Ledger(format!(
+            "{} was recorded for a different factory, deployer or init code hash",
+            path.display()
+        )));
+    }
+
+    let best = ledger.best()?;
+    println!(
+        "Ledger best: {} {:?} salt {}",
+        config.score_mode.describe(best.address),
+        best.address,
+        fmt_salt(best.salt_n, config.mask_salts)
+    );
+    let covered = ledger.covered_ranges()?;
+    if !covered.is_empty() {
+        println!(
+            "Skipping the {} salt ranges the ledger records as searched",
+            covered.len()
+        );
+    }
+    Ok(Some((best, covered)))
+}
+
+/// Run a search several times, each from its own random salt, and print each run
+///
+/// # Arguments
+/// * `searcher` - The searcher, reset before each run so each reports its own best
+/// * `config` - The configuration, for the salt space and the score mode
+/// * `params` - The parameters of the search, the initial salt is replaced in each run
+/// * `repeats` - The number of runs
+/// * `prior_best` - The best recorded in the ledger before the run, if any
+/// * `stop` - Set on Ctrl-C, no run starts once it is
+///
+/// # Returns
+/// * The best of the runs and the prior best, `None` if there was neither, the attempts
+///   of all the runs, and the salt ranges they searched to the end
+fn repeat_search<D: AddressDeriver>(
+    searcher: &search::Searcher<D>,
+    config: &Config,
+    params: &search::SearchParams,
+    repeats: u32,
+    prior_best: Option<search::AddressSalt>,
+    stop: &AtomicBool,
+) -> (Option<search::AddressSalt>, u128, Vec<(U256, U256)>) {
+    let mut runs: Vec<(Option<search::AddressSalt>, u128, Duration)> = Vec::new();
+    let mut covered = Vec::new();
+    for repeat in 1..=repeats {
+        // the first run always starts
+        if repeat > 1 && stop.load(Ordering::Relaxed) {
+            println!("Stopped after {} of {} runs", repeat - 1, repeats);
+            break;
+        }
+        let params = search::SearchParams {
+            initial_salt_n: config.run_initial_salt(params.span(), repeat - 1),
+            ..*params
+        };
+        println!(
+            "Run {} of {} from a {} salt",
+            repeat,
+            repeats,
+            if config.seed.is_some() {
+                "seeded"
+            } else {
+                "random"
+            }
+        );
+        searcher.reset();
+        let start_time = Instant::now();
+        let found = searcher.search(params);
+        runs.push((found, searcher.total_attempts(), start_time.elapsed()));
+        covered.extend(searched_range(searcher, &params));
+    }
+
+    println!("\nRuns:");
+    for (repeat, (found, attempts, elapsed)) in runs.iter().enumerate() {
+        let found = match found {
+            Some(found) => format!(
+                "{} {:?} salt {}",
+                config.score_mode.describe(found.address),
+                found.address,
+                fmt_salt(found.salt_n, config.mask_salts)
+            ),
+            None => "no address found".to_string(),
+        };
+        println!(
+            "{}. {} attempts in {}: {}",
+            repeat + 1,
+            attempts.to_formatted_string(&Locale::en),
+            fmt_dms(elapsed.as_secs() as u128),
+            found
+        );
+    }
+    let attempts: u128 = runs.iter().map(|(_, attempts, _)| attempts).sum();
+    println!(
+        "{} attempts in total",
+        attempts.to_formatted_string(&Locale::en)
+    );
+
+    let best = runs
+        .iter()
+        .filter_map(|(found, _, _)| *found)
+        .chain(prior_best)
+        .reduce(|best, found| {
+            if config.score_mode.is_better(&found, &best, config.tie_break) {
+                found
+            } else {
+                best
+            }
+        });
+    (best, attempts, covered)
+}
+
+/// The salts a finished search scanned, to checkpoint in the ledger
+///
+/// # Arguments
+/// * `searcher` - The searcher which ran the search
+/// * `params` - The parameters of the search
+///
+/// # Returns
+/// * The `[from, to)` salt range, `None` if the search stopped at its target, since
+///   the rounds still running then stop partway and leave gaps, or skipped a round,
+///   or stepped over salts with a `salt_step` above 1, which a range can't express
+fn searched_range<D: AddressDeriver>(
+    searcher: &search::Searcher<D>,
+    params: &search::SearchParams,
+) -> Option<(U256, U256)> {
+    if searcher.target_reached() || searcher.failed_rounds() > 0 || params.salt_step != 1 {
+        return None;
+    }
+    // the whole range, including any salts skipped as searched before, a range ending at
+    // 2^256 is recorded as ending at the last salt, which can't be checkpointed
+    let end = params.initial_salt_n.checked_add(params.span());
+    Some((params.initial_salt_n, end.unwrap_or(U256::MAX)))
+}
+
+/// Write the result to the ledger if it beats the all-time best, and add the salts searched
+///
+/// # Arguments
+/// * `path` - The path of the ledger file
+/// * `config` - The configuration of the run
+/// * `found` - The best of the run, the prior best if nothing beat it
+/// * `covered` - The salt ranges the run searched to the end
+///
+/// # Returns
+/// * Whether the result beat the all-time best
+fn update_ledger(
+    path: &Path,
+    config: &Config,
+    found: &search::AddressSalt,
+    covered: &[(U256, U256)],
+) -> bool {
+    let new_ledger = || {
+        Ledger::new(
+            config.factory,
+            config.deployer,
+            config.init_code_hashes[0],
+            found,
+        )
+    };
+    // reread in case another run of the campaign wrote to it meanwhile
+    let (mut ledger, improved) = match Ledger::load(path) {
+        Ok(Some(mut ledger)) => match ledger.record(found, config.score_mode, config.tie_break) {
+            Ok(improved) => (ledger, improved),
+            Err(e) => {
+                eprintln!("Error: failed to update the ledger: {}", e);
+                return false;
+            }
+        },
+        Ok(None) => (new_ledger(), true),
+        Err(e) => {
+            eprintln!("Error: failed to update the ledger: {}", e);
+            return false;
+        }
+    };
+    let result = ledger.cover(covered).and_then(|()| ledger.save(path));
+    match result {
+        Ok(()) if improved => println!("New all-time best written to {}", path.display()),
+        Ok(()) => {}
+        Err(e) => eprintln!("Error: failed to update the ledger: {}", e),
+    }
+    improved
+}
+
+/// Merge the ledgers of the shards of a distributed run into one
+///
+/// # Arguments
+/// * `paths` - The ledgers to merge, all of the same campaign
+/// * `score` - How to compare addresses, as `--score`
+/// * `out` - The ledger to write
+fn merge_ledgers_command(
+    paths: &[PathBuf],
+    score: Option<&str>,
+    out: &Path,
+) -> Result<(), SearchError> {
+    let score_mode = match score {
+        Some(score) => parse_score(score).map_err(SearchError::InvalidArgument)?,
+        None => ScoreMode::LeadingZeros,
+    };
+    let mut merged: Option<Ledger> = None;
+    let mut duplicates = U256::zero();
+    for path in paths {
+        let ledger = Ledger::load(path)?
+            .ok_or_else(|| SearchError::Ledger(format!("{} does not exist", path.display())))?;
+        match &mut merged {
+            Some(merged) => {
+                duplicates += merged
+                    .merge(&ledger, score_mode, TieBreak::default())
+                    .map_err(|e| {
+                        SearchError::InvalidArgument(format!("{}: {}", path.display(), e))
+                    })?
+            }
+            None => merged = Some(ledger),
+        }
+    }
+    // clap requires at least two ledgers
+    let merged = merged.unwrap();
+    merged.save(out)?;
+
+    let best = merged.best()?;
+    println!(
+        "Merged {} ledgers into {}: best {} {:?} salt 0x{}, {} salt ranges searched",
+        paths.len(),
+        out.display(),
+        score_mode.describe(best.address),
+        best.address,
+        hex::encode(bytes32(best.salt_n)),
+        merged.covered.len()
+    );
+    if !duplicates.is_zero() {
+        println!(
+            "{} salts were searched by more than one shard, counted once, do the shards overlap?",
+            duplicates
+        );
+    }
+    Ok(())
+}
+
+/// Run a subcommand, exiting with an error message on invalid input
+///
+/// # Arguments
+/// * `command` - The subcommand to run
+/// * `hash_algo` - How init code is hashed
+fn run_command(command: &Command, hash_algo: HashAlgo) {
+    let result = match command {
+        Command::Hash {
+            bytecode,
+            constructor_args,
+        } => hash_command(bytecode, constructor_args.as_deref(), hash_algo),
+        Command::Export {
+            factory,
+            init_code_hash,
+            from_salt,
+            to_salt,
+            out,
+            yes,
+        } => export_command(factory, init_code_hash, from_salt, to_salt, out, *yes),
+        Command::Explain {
+            factory,
+            init_code_hash,
+            salt,
+            score,
+        } => explain_command(factory, init_code_hash, salt, score.as_deref()),
+        Command::MergeLedgers {
+            ledgers,
+            score,
+            out,
+        } => merge_ledgers_command(ledgers, score.as_deref(), out),
+        Command::RecoverHash {
+            factory,
+            salt,
+            address,
+            hash_list,
+        } => recover_hash_command(factory, salt, address, hash_list),
+        Command::VerifyBatch {
+            factory,
+            init_code_hash,
+            pair_list,
+        } => verify_batch_command(factory, init_code_hash, pair_list),
+        Command::Completions { shell } => completions_command(*shell),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Print the completion script of a shell to stdout
+///
+/// # Arguments
+/// * `shell` - The shell to complete in
+fn completions_command(shell: Shell) -> Result<(), SearchError> {
+    write_completions(shell, &mut std::io::stdout());
+    Ok(())
+}
+
+/// Write the completion script of a shell
+///
+/// # Arguments
+/// * `shell` - The shell to complete in
+/// * `out` - Where to write the script
+fn write_completions(shell: Shell, out: &mut impl Write) {
+    // built from the same definition as the parser, so it can't fall out of date
+    let mut command = Args::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+/// Print the init code hash of creation bytecode and constructor arguments
+///
+/// # Arguments
+/// * `bytecode` - The creation bytecode, hex
+/// * `constructor_args` - The ABI-encoded constructor arguments, hex
+/// * `hash_algo` - How the init code is hashed
+fn hash_command(
+    bytecode: &str,
+    constructor_args: Option<&str>,
+    hash_algo: HashAlgo,
+) -> Result<(), SearchError> {
+    let bytecode = parse_hex("bytecode", bytecode)?;
+    if bytecode.is_empty() {
+        return Err(SearchError::EmptyBytecode);
+    }
+    let constructor_args = match constructor_args {
+        Some(args) => parse_hex("constructor args", args)?,
+        None => Vec::new(),
+    };
+
+    let hash = init_code_hash(&bytecode, &constructor_args, hash_algo);
+    println!("0x{}", hex::encode(bytes32(hash)));
+    Ok(())
+}
+
+/// Print which candidate init code hash derives an address, exiting with an error if none
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address, or a known factory name
+/// * `salt` - The salt, hex
+/// * `address` - The deployed address
+/// * `hash_list` - The file of candidate init code hashes, see `read_hash_list`
+fn recover_hash_command(
+    factory: &str,
+    salt: &str,
+    address: &str,
+    hash_list: &Path,
+) -> Result<(), SearchError> {
+    let factory: Address = match known_factory(factory) {
+        Some(factory) => factory,
+        None => parse_address("factory", factory)?,
+    };
+    let salt_n = parse_hex_u256("salt", salt)?;
+    let address = parse_address("address", address)?;
+    let candidates = read_hash_list(hash_list)?;
+
+    match recover_init_code_hash(factory, salt_n, address, &candidates) {
+        Some(init_code_hash) => {
+            println!("0x{}", hex::encode(bytes32(init_code_hash)));
+            Ok(())
+        }
+        None => Err(SearchError::InvalidArgument(format!(
+            "none of the {} init code hashes in {} derive {} from salt 0x{}",
+            candidates.len(),
+            hash_list.display(),
+            to_checksum(&address, None),
+            hex::encode(bytes32(salt_n))
+        ))),
+    }
+}
+
+/// Print whether each salt of a list derives the address recorded with it
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address, or a known factory name
+/// * `init_code_hash` - The init code hash shared by the pairs, hex
+/// * `pair_list` - The file of `salt,address` pairs
+///
+/// # Returns
+/// * An error if the list can't be read or any pair doesn't verify
+fn verify_batch_command(
+    factory: &str,
+    init_code_hash: &str,
+    pair_list: &Path,
+) -> Result<(), SearchError> {
+    let factory: Address = match known_factory(factory) {
+        Some(factory) => factory,
+        None => parse_address("factory", factory)?,
+    };
+    let init_code_hash = parse_hex_u256("init code hash", init_code_hash)?;
+    let pairs = read_salt_address_list(pair_list)?;
+
+    let salts: Vec<U256> = pairs.iter().map(|(salt_n, _)| *salt_n).collect();
+    let addresses = compute_batch(factory, init_code_hash, &salts);
+    let mut mismatches = 0;
+    for ((salt_n, expected), address) in pairs.iter().zip(addresses) {
+        if address == *expected {
+            println!(
+                "ok       0x{} {}",
+                hex::encode(bytes32(*salt_n)),
+                to_checksum(&address, None)
+            );
+        } else {
+            mismatches += 1;
+            println!(
+                "MISMATCH 0x{} expected {}, derives {}",
+                hex::encode(bytes32(*salt_n)),
+                to_checksum(expected, None),
+                to_checksum(&address, None)
+            );
+        }
+    }
+
+    if mismatches > 0 {
+        return Err(SearchError::InvalidArgument(format!(
+            "{} of {} pairs in {} don't verify",
+            mismatches,
+            pairs.len(),
+            pair_list.display()
+        )));
+    }
+    println!("All {} pairs verify", pairs.len());
+    Ok(())
+}
+
+/// Print the CREATE2 preimage of the initial salt field by field, for auditing the inputs
+/// of the derivation, e.g. the byte order of the salt
+///
+/// # Arguments
+/// * `config` - The resolved configuration, for the factory, initial salt and sender salting
+/// * `init_code_hash` - The init code hash to hash the salt with
+///
+/// # Returns
+/// * Whether the address hashed from the preimage is the one the search derives
+fn dump_preimage(config: &Config, init_code_hash: U256) -> bool {
+    let mut salt = [0u8; 32];
+    config.initial_salt_n.to_big_endian(&mut salt);
+    let searched_salt = salt;
+    // the factory hashes the salt it's given before CREATE2 sees it
+    if let Some(sender) = config.sender_salting {
+        salt = sender_salt(sender, &salt);
+    }
+    let preimage = create2_preimage(config.factory, &salt, &bytes32(init_code_hash));
+    let hash = keccak256(preimage);
+    let address = Address::from_slice(&hash[12..]);
+
+    println!(
+        "\nPreimage of salt 0x{} with init code hash 0x{}, {} bytes:",
+        hex::encode(searched_salt),
+        hex::encode(bytes32(init_code_hash)),
+        preimage.len()
+    );
+    for (label, range) in CREATE2_PREIMAGE_LAYOUT {
+        println!(
+            "  [{:>2}..{:>2}] {:<14} {}",
+            range.start,
+            range.end,
+            label,
+            hex::encode(&preimage[range])
+        );
+    }
+    if config.sender_salting.is_some() {
+        println!("  the salt is keccak256(sender ++ salt), as --sender-salting hashes it");
+    }
+    println!("  full: 0x{}", hex::encode(preimage));
+    println!("keccak256: 0x{}", hex::encode(hash));
+    println!("address:   {}", to_checksum(&address, None));
+
+    let deriver = match config.sender_salting {
+        Some(sender) => Create2Deriver::sender_salted(sender),
+        None => Create2Deriver::default(),
+    };
+    let derived = deriver.derive(config.factory, &searched_salt, &bytes32(init_code_hash));
+    if derived != address {
+        eprintln!(
+            "Error: the search derives {} from this salt, the preimage doesn't match",
+            to_checksum(&derived, None)
+        );
+        return false;
+    }
+    println!("matches the address the search derives");
+    return true;
+}
+
+/// Print how a salt becomes an address, each step spelled out
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address, or a known factory name
+/// * `init_code_hash` - The init code hash, hex
+/// * `salt` - The salt, hex
+/// * `score` - How to score the address, as `--score`
+fn explain_command(
+    factory: &str,
+    init_code_hash: &str,
+    salt: &str,
+    score: Option<&str>,
+) -> Result<(), SearchError> {
+    let factory: Address = match known_factory(factory) {
+        Some(factory) => factory,
+        None => parse_address("factory", factory)?,
+    };
+    let init_code_hash = parse_hex_u256("init code hash", init_code_hash)?;
+    let salt_n = parse_hex_u256("salt", salt)?;
+    let score_mode = match score {
+        Some(score) => parse_score(score).map_err(SearchError::InvalidArgument)?,
+        None => ScoreMode::LeadingZeros,
+    };
+
+    let preimage = create2_preimage(factory, &bytes32(salt_n), &bytes32(init_code_hash));
+    let hash = keccak256(preimage);
+    let address = Address::from_slice(&hash[12..]);
+    // the explicit steps have to agree with the derivation the search uses
+    debug_assert_eq!(address, create2_address(factory, salt_n, init_code_hash));
+
+    let score = score_mode.score(address);
+    println!(
+        "preimage:  0xff ++ factory ++ salt ++ init code hash, {} bytes",
+        preimage.len()
+    );
+    println!("           0x ff");
+    println!("              {}", hex::encode(factory.as_bytes()));
+    println!("              {}", hex::encode(bytes32(salt_n)));
+    println!("              {}", hex::encode(bytes32(init_code_hash)));
+    println!("keccak256: 0x{}", hex::encode(hash));
     println!(
-        "{} zeros {:?} salt 0x{}",
-        count_leading_zeroes(found.address),
-        found.address,
-        hex::encode(bytes32(found.salt_n))
+        "address:   the last 20 bytes, {}",
+        to_checksum(&address, None)
     );
+    println!("score:     {}", score_mode.describe(address));
+    println!(
+        "rarity:    about 1 in {} addresses score {} {} or more",
+        (score_mode
+            .expected_attempts(score.min(u8::MAX as u32) as u8)
+            .round() as u128)
+            .to_formatted_string(&Locale::en),
+        score,
+        score_mode.unit()
+    );
+    Ok(())
+}
+
+/// Exports estimated larger than this ask for confirmation first, 1 GB is about 9 million rows
+const EXPORT_CONFIRM_BYTES: u64 = 1_000_000_000;
+
+/// Ask a yes or no question on the terminal
+///
+/// # Arguments
+/// * `question` - The question, without the answer hint
+///
+/// # Returns
+/// * Whether the answer was yes, no unless y or yes was typed, e.g. when stdin is closed
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    if std::io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(_) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+        Err(_) => false,
+    }
+}
+
+/// Write the addresses of a salt range to a CSV file
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address
+/// * `init_code_hash` - The init code hash, hex
+/// * `from_salt` - The first salt, hex
+/// * `to_salt` - The end of the range, excluded, hex
+/// * `out` - The path of the CSV file
+/// * `yes` - Write it without asking however large it would be
+fn export_command(
+    factory: &str,
+    init_code_hash: &str,
+    from_salt: &str,
+    to_salt: &str,
+    out: &Path,
+    yes: bool,
+) -> Result<(), SearchError> {
+    let factory: Address = match known_factory(factory) {
+        Some(factory) => factory,
+        None => parse_address("factory", factory)?,
+    };
+    let init_code_hash = parse_hex_u256("init code hash", init_code_hash)?;
+    let from_salt_n = parse_hex_u256("from salt", from_salt)?;
+    let to_salt_n = parse_hex_u256("to salt", to_salt)?;
+
+    let size = export_size_estimate(from_salt_n, to_salt_n);
+    if size > U256::from(EXPORT_CONFIRM_BYTES) && !yes {
+        let question = format!(
+            "Export {} rows, about {}, to {}?",
+            to_salt_n.saturating_sub(from_salt_n),
+            fmt_bytes(size.min(U256::from(u128::MAX)).as_u128() as f64),
+            out.display()
+        );
+        if !confirm(&question) {
+            return Err(SearchError::InvalidArgument(
+                "export cancelled, pass --yes to skip the confirmation".to_string(),
+            ));
+        }
+    }
+
+    let file = std::fs::File::create(out).map_err(SearchError::Io)?;
+    let mut writer = std::io::BufWriter::new(file);
+    let rows = export_csv(&mut writer, factory, init_code_hash, from_salt_n, to_salt_n)?;
+    println!(
+        "Wrote {} rows to {}",
+        rows.to_formatted_string(&Locale::en),
+        out.display()
+    );
+    Ok(())
+}
+
+/// Print the best address found by a search, with the nibbles matching
+/// the target highlighted
+///
+/// # Arguments
+/// * `found` - The best address and its salt
+/// * `config` - The configuration, for the score mode and the target
+/// * `verbose` - Also print the numeric value of the address
+/// * `color` - Highlight with ANSI colors, otherwise with a line of carets
+fn print_best(found: &search::AddressSalt, config: &Config, verbose: bool, color: bool) {
+    let score_mode = config.score_mode;
+    let description = score_mode.describe(found.address);
+    let mask = score_mode.match_mask(found.address);
+    let target_nibbles = score_mode.target_nibbles(config.zeros);
+
+    println!("Best:\n");
+    if color {
+        println!(
+            "{} {} salt 0x{}",
+            description,
+            fmt_match_color(
+                found.address,
+                &mask,
+                target_nibbles,
+                score_mode.checksummed()
+            ),
+            hex::encode(bytes32(found.salt_n))
+        );
+    } else {
+        let address = if score_mode.checksummed() {
+            to_checksum(&found.address, None)
+        } else {
+            format!("{:?}", found.address)
+        };
+        println!(
+            "{} {} salt 0x{}",
+            description,
+            address,
+            hex::encode(bytes32(found.salt_n))
+        );
+        println!(
+            "{} {}",
+            " ".repeat(description.len()),
+            fmt_match_carets(&mask, target_nibbles)
+        );
+    }
+    if verbose {
+        // two addresses with the same zero count can still differ in magnitude
+        let value = address_to_u256(found.address);
+        println!("value {:#x} ({})", value, value);
+    }
+}
+
+/// Print the uniform random sample of scanned addresses
+///
+/// # Arguments
+/// * `sample` - The sampled addresses and their salts, see `ScoreMode::sort_best_first`
+fn print_sample(sample: &[search::AddressSalt]) {
+    println!("\nSample of {} addresses:\n", sample.len());
+    for found in sample {
+        println!(
+            "{:?} salt 0x{} zeros {}",
+            found.address,
+            hex::encode(bytes32(found.salt_n)),
+            leading_zero_nibbles(found.address)
+        );
+    }
+}
+
+/// Print the share of time spent per phase of the search
+///
+/// The inner loop phases are only timed on a sample of iterations, so they are
+/// scaled up to the total number of attempts to compare them with the merge time.
+///
+/// # Arguments
+/// * `timings` - The sampled phase timings
+/// * `total_attempts` - The total number of addresses computed
+fn print_profile(timings: &search::PhaseTimings, total_attempts: u128) {
+    if timings.samples == 0 {
+        println!("\nProfile: no samples, increase --round-size");
+        return;
+    }
+    let scale = total_attempts as f64 / timings.samples as f64;
+    let phases = [
+        (
+            "salt increment",
+            timings.salt_increment.as_secs_f64() * scale,
+        ),
+        (
+            "address derivation",
+            timings.address_derivation.as_secs_f64() * scale,
+        ),
+        ("comparison", timings.comparison.as_secs_f64() * scale),
+        ("merge", timings.merge.as_secs_f64()),
+    ];
+    let total: f64 = phases.iter().map(|(_, secs)| secs).sum();
+
+    println!("\nProfile:\n");
+    for (phase, secs) in phases.iter() {
+        println!("{:>18}: {:>5.1}%", phase, 100.0 * secs / total);
+    }
+}
+
+/// The position of a salt in the scan order of a search, the attempts it took to get there
+///
+/// # Arguments
+/// * `params` - The parameters of the search
+/// * `found` - The best address found and its salt
+///
+/// # Returns
+/// * The number of attempts up to and including the salt, `None` if the search didn't
+///   scan it, e.g. a seeded best
+fn scan_position(params: &search::SearchParams, found: &search::AddressSalt) -> Option<u128> {
+    let offset = found.salt_n.checked_sub(params.initial_salt_n)?;
+    if offset >= params.span() {
+        return None;
+    }
+    Some((offset / U256::from(params.salt_step)).low_u128() + 1)
+}
+
+/// Print the init code hashes ranked by the best address found for each,
+/// ties going to the one which needed fewer attempts to get there, then the lower hash
+///
+/// # Arguments
+/// * `results` - The init code hash, the best address found for it and its position in
+///   the scan order, see `scan_position`
+/// * `config` - The configuration of the run
+fn print_ranking(results: &mut [(U256, search::AddressSalt, Option<u128>)], config: &Config) {
+    // the same order however the searches were scheduled, even for equal results,
+    // with the results found at an unknown position last
+    results.sort_by(|(hash_a, a, position_a), (hash_b, b, position_b)| {
+        config
+            .score_mode
+            .compare(a.address, b.address)
+            .then(
+                position_a
+                    .unwrap_or(u128::MAX)
+                    .cmp(&position_b.unwrap_or(u128::MAX)),
+            )
+            .then(hash_a.cmp(hash_b))
+    });
+
+    println!("\nRanking:\n");
+    for (rank, (init_code_hash, found, position)) in results.iter().enumerate() {
+        println!(
+            "{}. 0x{} {} {:?} {}",
+            rank + 1,
+            hex::encode(bytes32(*init_code_hash)),
+            config.score_mode.describe(found.address),
+            found.address,
+            match position {
+                Some(position) => format!(
+                    "after {} attempts",
+                    position.to_formatted_string(&Locale::en)
+                ),
+                None => "at an unknown position".to_string(),
+            }
+        );
+    }
+}
+
+/// Print the number of addresses seen per leading-zero count
+///
+/// # Arguments
+/// * `histogram` - The count of addresses indexed by number of leading zeros
+fn print_histogram(histogram: &[u128]) {
+    let total: u128 = histogram.iter().sum();
+    let max_zeros = histogram.iter().rposition(|count| *count > 0).unwrap_or(0);
+
+    println!("\nHistogram:\n");
+    for (zeros, count) in histogram.iter().enumerate().take(max_zeros + 1) {
+        // each zero is 1/16 as likely, so exactly `zeros` zeros is (1/16)^zeros * 15/16
+        let mut expected = total as f64 / 16_f64.powi(zeros as i32);
+        if zeros < ADDRESS_NIBBLES {
+            expected *= 15.0 / 16.0;
+        }
+        println!(
+            "{:>2} zeros: {} (expected {})",
+            zeros,
+            count.to_formatted_string(&Locale::en),
+            (expected.round() as u128).to_formatted_string(&Locale::en)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    use search_create2::derive::SaltDeriver;
+
+    /// Held while the CREATE2_* environment variables are set, which clap reads as it parses
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Parse a command line, never while another test has CREATE2_* variables set
+    fn parse<'a>(argv: impl IntoIterator<Item = &'a str>) -> Result<Args, clap::Error> {
+        let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        Args::try_parse_from(argv)
+    }
+
+    #[test]
+    fn dumped_config_reproduces_the_search() {
+        let args = parse([
+            "search_create2",
+            "--factory",
+            "0x0000000000FFe8B47B3e2130213B802212439497",
+            "--deployer",
+            "0x1111111111111111111111111111111111111111",
+            "--init-code-hash",
+            "0x2222222222222222222222222222222222222222222222222222222222222222",
+            "--sender-salting",
+            "0x3333333333333333333333333333333333333333",
+            "--zeros",
+            "6",
+            "--stop-at-target",
+            "--threshold-return",
+            "first",
+            "--tie-break",
+            "lowest-salt",
+            "--min-zeros",
+            "4",
+            "--max-zeros",
+            "8",
+            "--seed",
+            "42",
+            "--seed-index",
+            "3",
+            "--mask-salts",
+            "--round-size",
+            "1000",
+            "--num-rounds",
+            "10",
+            "--num-threads",
+            "2",
+        ])
+        .unwrap();
+        let config = resolve_config(&args).unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "search-create2-dump-config-{}.toml",
+            std::process::id()
+        ));
+        ConfigFile::try_from(&config).unwrap().save(&path).unwrap();
+
+        let args = parse(["search_create2", "--config", path.to_str().unwrap()]).unwrap();
+        let loaded = resolve_config(&args);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        let init_code_hash = config.init_code_hashes[0];
+        assert_eq!(
+            loaded.search_params(init_code_hash),
+            config.search_params(init_code_hash)
+        );
+        assert_eq!(loaded.canonical(), config.canonical());
+        assert_eq!(loaded.sender_salting, config.sender_salting);
+        assert_eq!((loaded.min_zeros, loaded.max_zeros), (Some(4), Some(8)));
+        assert_eq!((loaded.seed, loaded.seed_index), (Some(42), 3));
+        assert!(loaded.mask_salts);
+    }
+
+    #[test]
+    fn flags_take_precedence_over_the_environment_over_the_file() {
+        let address = |byte: u8| format!("{:?}", Address::repeat_byte(byte));
+        let hash = |byte: u8| format!("0x{}", hex::encode([byte; 32]));
+        let path = std::env::temp_dir().join(format!(
+            "search-create2-precedence-{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            format!(
+                "factory = \"{}\"\ndeployer = \"{}\"\ninit_code_hashes = [\"{}\"]\n",
+                address(0xf1),
+                address(0xd1),
+                hash(0xa1)
+            ),
+        )
+        .unwrap();
+        let file = path.to_str().unwrap();
+        // (variable, value) pairs set while parsing the flags
+        let resolve = |env: &[(&str, String)], flags: &[&str]| {
+            let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            for (key, value) in env {
+                std::env::set_var(key, value);
+            }
+            let args = Args::try_parse_from(["search_create2"].iter().chain(flags));
+            for (key, _) in env {
+                std::env::remove_var(key);
+            }
+            resolve_config(&args.unwrap())
+        };
+        let resolved = |config: Result<Config, SearchError>| {
+            let config = config.unwrap();
+            (
+                format!("{:?}", config.factory),
+                format!("{:?}", config.deployer.unwrap()),
+                format!("0x{}", hex::encode(bytes32(config.init_code_hashes[0]))),
+            )
+        };
+        let env = [
+            ("CREATE2_FACTORY", address(0xf2)),
+            ("CREATE2_DEPLOYER", address(0xd2)),
+            ("CREATE2_INIT_CODE_HASH", hash(0xa2)),
+        ];
+        let (factory, deployer, init_code_hash) = (address(0xf3), address(0xd3), hash(0xa3));
+        let flags: [&str; 6] = [
+            "--factory",
+            &factory,
+            "--deployer",
+            &deployer,
+            "--init-code-hash",
+            &init_code_hash,
+        ];
+
+        let from_file = resolved(resolve(&[], &["--config", file]));
+        let from_env = resolved(resolve(&env, &["--config", file]));
+        let from_flags = resolved(resolve(
+            &env,
+            &[&["--config", file][..], &flags[..]].concat(),
+        ));
+        // each parameter falls back on its own
+        let mixed = resolved(resolve(
+            &env[..1],
+            &["--config", file, "--deployer", deployer.as_str()],
+        ));
+        let missing = resolve(&[], &[]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(from_file, (address(0xf1), address(0xd1), hash(0xa1)));
+        assert_eq!(from_env, (address(0xf2), address(0xd2), hash(0xa2)));
+        assert_eq!(from_flags, (address(0xf3), address(0xd3), hash(0xa3)));
+        assert_eq!(mixed, (address(0xf2), address(0xd3), hash(0xa1)));
+        assert!(matches!(
+            missing,
+            Err(SearchError::MissingArgument("factory"))
+        ));
+    }
+
+    #[test]
+    fn zero_address_factory_warns_or_fails_with_strict() {
+        let factory = Address::repeat_byte(0x11);
+        assert!(matches!(check_factory(factory, false), Ok(None)));
+        assert!(matches!(check_factory(factory, true), Ok(None)));
+        let warning = check_factory(Address::zero(), false).unwrap().unwrap();
+        assert!(warning.starts_with("factory is the zero address"));
+        assert!(matches!(
+            check_factory(Address::zero(), true),
+            Err(SearchError::InvalidArgument(_))
+        ));
+
+        // as given on the command line
+        let resolve = |strict: &[&str]| {
+            let argv = [
+                "search_create2",
+                "--factory",
+                "0x0000000000000000000000000000000000000000",
+                "--deployer",
+                "0x1111111111111111111111111111111111111111",
+                "--init-code-hash",
+                "0x2222222222222222222222222222222222222222222222222222222222222222",
+            ];
+            resolve_config(&parse(argv.iter().chain(strict).copied()).unwrap())
+        };
+        assert!(resolve(&[]).unwrap().factory.is_zero());
+        assert!(matches!(
+            resolve(&["--strict"]),
+            Err(SearchError::InvalidArgument(e)) if e.contains("zero address")
+        ));
+    }
+
+    #[test]
+    fn completions_are_generated_for_each_shell() {
+        use clap::ValueEnum;
+
+        for shell in Shell::value_variants() {
+            let mut script = Vec::new();
+            write_completions(*shell, &mut script);
+            let script = String::from_utf8(script).unwrap();
+            // the flags and subcommands come from the parser
+            for word in [
+                "search_create2",
+                "init-code-hash",
+                "completions",
+                "recover-hash",
+            ] {
+                assert!(script.contains(word), "{} completions lack {}", shell, word);
+            }
+        }
+    }
+
+    #[test]
+    fn address_is_derived_from_the_factory_not_the_deployer() {
+        let args = parse([
+            "search_create2",
+            "--factory",
+            "0x0000000000FFe8B47B3e2130213B802212439497",
+            "--deployer",
+            "0x1111111111111111111111111111111111111111",
+            "--init-code-hash",
+            "0x2222222222222222222222222222222222222222222222222222222222222222",
+        ])
+        .unwrap();
+        let config = resolve_config(&args).unwrap();
+        // the deployer only fixes the first 20 bytes of the salt
+        let mut salt = [0u8; 32];
+        salt[..20].fill(0x11);
+        assert_eq!(config.initial_salt_n, U256::from_big_endian(&salt));
+
+        let params = search::SearchParams {
+            round_size: 1,
+            num_rounds: 1,
+            max_attempts: None,
+            ..config.search_params(config.init_code_hashes[0])
+        };
+        let found = search::Searcher::new(1, false)
+            .unwrap()
+            .search(params)
+            .unwrap();
+        // keccak256(0xff ++ factory ++ salt ++ hash)[12..], with the deployer as the
+        // factory it would be 0x526930B68B59C5000922C4fE49d65B820c42c2ea
+        let expected: Address = "0x3873bCF51796feFE2057b660b10FFd0Dfcedef97"
+            .parse()
+            .unwrap();
+        assert_eq!(found.address, expected);
+    }
+
+    #[test]
+    fn stepped_runs_leave_the_skipped_salts_to_later_runs() {
+        let args = parse([
+            "search_create2",
+            "--factory",
+            "0x0000000000FFe8B47B3e2130213B802212439497",
+            "--deployer",
+            "0x1111111111111111111111111111111111111111",
+            "--init-code-hash",
+            "0x2222222222222222222222222222222222222222222222222222222222222222",
+        ])
+        .unwrap();
+        let config = resolve_config(&args).unwrap();
+        let params = |salt_step: u64, round_size: u128| search::SearchParams {
+            round_size,
+            num_rounds: 2,
+            max_attempts: None,
+            salt_step,
+            target_score: None,
+            ..config.search_params(config.init_code_hashes[0])
+        };
+
+        // 32 even salts, the odd salts between them are left unscanned
+        let searcher = search::Searcher::new(2, false).unwrap();
+        let stepped = searcher.search(params(2, 16)).unwrap();
+        assert_eq!(searcher.total_attempts(), 32);
+        let covered: Vec<(U256, U256)> = searched_range(&searcher, &params(2, 16))
+            .into_iter()
+            .collect();
+        assert!(covered.is_empty());
+
+        // so a run over the same 64 salts one by one still scans the odd ones
+        let mut searcher = search::Searcher::new(2, false).unwrap();
+        searcher.skip(covered);
+        searcher.seed_best(stepped);
+        searcher.search(params(1, 32)).unwrap();
+        assert_eq!(searcher.total_attempts(), 64);
+        assert_eq!(
+            searched_range(&searcher, &params(1, 32)),
+            Some((config.initial_salt_n, config.initial_salt_n + 64))
+        );
+    }
+
+    #[test]
+    fn repeats_return_the_best_of_the_runs_and_the_prior_best() {
+        let args = parse([
+            "search_create2",
+            "--factory",
+            "0x0000000000FFe8B47B3e2130213B802212439497",
+            "--full-salt",
+            "--init-code-hash",
+            "0x2222222222222222222222222222222222222222222222222222222222222222",
+            "--seed",
+            "177",
+        ])
+        .unwrap();
+        let config = resolve_config(&args).unwrap();
+        let params = search::SearchParams {
+            round_size: 16,
+            num_rounds: 2,
+            max_attempts: None,
+            target_score: None,
+            ..config.search_params(config.init_code_hashes[0])
+        };
+        // with SaltDeriver the address is the last 20 bytes of the salt
+        let found = |salt_n: U256| search::AddressSalt {
+            address: Address::from_slice(&bytes32(salt_n)[12..]),
+            salt_n,
+        };
+        let best_of_runs = (0..3)
+            .flat_map(|run| {
+                let start = config.run_initial_salt(params.span(), run);
+                (0..32u64).map(move |i| start + i)
+            })
+            .map(found)
+            .min_by_key(|found| found.address)
+            .unwrap();
+
+        let searcher = search::Searcher::with_deriver(2, false, SaltDeriver).unwrap();
+        let stop = AtomicBool::new(false);
+        let worse = found(U256::MAX);
+        let (best, attempts, covered) =
+            repeat_search(&searcher, &config, &params, 3, Some(worse), &stop);
+        assert_eq!(best, Some(best_of_runs));
+        assert_eq!(attempts, 3 * 32);
+        assert_eq!(covered.len(), 3);
+
+        let better = found(U256::one());
+        let (best, _, _) = repeat_search(&searcher, &config, &params, 3, Some(better), &stop);
+        assert_eq!(best, Some(better));
+    }
+
+    #[test]
+    fn cover_full_window_tiles_a_small_window() {
+        let config = |salt_step: &str| {
+            let args = parse([
+                "search_create2",
+                "--factory",
+                "0x0000000000FFe8B47B3e2130213B802212439497",
+                "--deployer",
+                "0x1111111111111111111111111111111111111111",
+                "--init-code-hash",
+                "0x2222222222222222222222222222222222222222222222222222222222222222",
+                // a 2-byte window of 65,536 salts
+                "--salt-guard-bytes",
+                "30",
+                "--round-size",
+                "1000",
+                "--salt-step",
+                salt_step,
+                "--cover-full-window",
+            ])
+            .unwrap();
+            resolve_config(&args).unwrap()
+        };
+
+        let whole = config("1");
+        assert_eq!(whole.max_attempts, Some(65_536));
+        assert_eq!(whole.num_rounds, 66);
+        let params = whole.search_params(whole.init_code_hashes[0]);
+        assert!(params.check_rounds().is_ok());
+        // up to the last salt of the window and no further
+        assert_eq!(params.span(), U256::from(65_536));
+
+        // every third salt, the last one 65,535 from the start
+        let stepped = config("3");
+        assert_eq!(stepped.max_attempts, Some(21_846));
+        assert_eq!(stepped.num_rounds, 22);
+        assert!(stepped
+            .search_params(stepped.init_code_hashes[0])
+            .check_rounds()
+            .is_ok());
+    }
+
+    #[test]
+    fn rotating_the_prefix_rolls_the_low_bytes_over_into_it() {
+        let args = parse([
+            "search_create2",
+            "--factory",
+            "0x0000000000FFe8B47B3e2130213B802212439497",
+            "--deployer",
+            "0x1111111111111111111111111111111111111111",
+            "--init-code-hash",
+            "0x2222222222222222222222222222222222222222222222222222222222222222",
+            // a 1-byte window, salts 0x1111...1100 through 0x1111...11ff after the prefix
+            "--salt-guard-bytes",
+            "31",
+            "--round-size",
+            "64",
+            "--num-threads",
+            "1",
+            // with SaltDeriver the address of salt first + 0x2a5, two prefixes on
+            "--closest-to",
+            "0x11111111111111110000000000000000000002a5",
+        ])
+        .unwrap();
+        let config = resolve_config(&args).unwrap();
+        let first_salt = config.initial_salt_n;
+        assert_eq!(bytes32(first_salt)[30..], [0, 0]);
+        // set beforehand, so the watch ends after a batch of 16 rounds of 64 salts
+        let stop = AtomicBool::new(true);
+
+        let searcher = search::Searcher::with_deriver(1, false, SaltDeriver).unwrap();
+        let best = watch(&searcher, &config, None, None, false, &stop).unwrap();
+        // cut short at the end of the window, ...11ff is the closest of it
+        assert_eq!(searcher.total_attempts(), 0x100);
+        assert_eq!(best.salt_n, first_salt + 0xff);
+
+        let best = watch(&searcher, &config, None, None, true, &stop).unwrap();
+        // on past ...11ff into ...1200 and the prefixes after it without a gap
+        assert_eq!(searcher.total_attempts(), 0x400);
+        assert_eq!(best.salt_n, first_salt + 0x2a5);
+        assert_eq!(bytes32(best.salt_n)[30..], [0x02, 0xa5]);
+    }
+
+    #[test]
+    fn hash_command_hashes_bytecode_and_constructor_args() {
+        // abi.encode(uint256(42), address(0x1111...1111)) after a bytecode stub
+        let bytecode = "0x6080604052";
+        let args = "0x000000000000000000000000000000000000000000000000000000000000002a\
+                    0000000000000000000000001111111111111111111111111111111111111111";
+        let init_code = [
+            parse_hex("", bytecode).unwrap(),
+            parse_hex("", args).unwrap(),
+        ];
+        assert_eq!(
+            init_code_hash(&init_code[0], &init_code[1], HashAlgo::Keccak256),
+            parse_hex_u256(
+                "",
+                "0x3cbb10fca7a8af2ce176421b59da8b101d137e25974c4831dad6ebdc7b9dbf82"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            init_code_hash(&init_code[0], &init_code[1], HashAlgo::Sha256),
+            parse_hex_u256(
+                "",
+                "0x7cf4cec8dce1c9d301600bbe71b954867e4fd30f5f46ceb5c4ed26645cd43a5e"
+            )
+            .unwrap()
+        );
+        assert!(hash_command(bytecode, Some(args), HashAlgo::Keccak256).is_ok());
+        assert!(matches!(
+            hash_command("0x", None, HashAlgo::Keccak256),
+            Err(SearchError::EmptyBytecode)
+        ));
+        assert!(matches!(
+            hash_command(bytecode, Some("0x2"), HashAlgo::Keccak256),
+            Err(SearchError::InvalidHex(_))
+        ));
+    }
+
+    #[test]
+    fn eip2470_names_the_singleton_factory_in_full_salt_mode() {
+        let args = parse([
+            "search_create2",
+            "--factory",
+            "eip2470",
+            "--init-code-hash",
+            "0x2222222222222222222222222222222222222222222222222222222222222222",
+        ])
+        .unwrap();
+        let config = resolve_config(&args).unwrap();
+        // the address EIP-2470 deploys the singleton factory to on every chain
+        assert_eq!(
+            to_checksum(&config.factory, None),
+            "0xce0042B868300000d44A59004Da54A005ffdcf9f"
+        );
+        assert_eq!(config.deployer, None);
+        assert_eq!(config.initial_salt_n, U256::zero());
+    }
+
+    #[test]
+    fn verify_batch_fails_on_a_wrong_pair() {
+        let factory = Address::repeat_byte(0x11);
+        let init_code_hash = U256::from(0x1234);
+        let right = create2_address(factory, U256::one(), init_code_hash);
+        let path = std::env::temp_dir().join(format!(
+            "search-create2-verify-batch-{}",
+            std::process::id()
+        ));
+        let verify = |list: String| {
+            std::fs::write(&path, list).unwrap();
+            verify_batch_command(&format!("{:?}", factory), "0x1234", &path)
+        };
+
+        assert!(verify(format!("0x01,{:?}\n", right)).is_ok());
+        // the second salt doesn't derive the address of the first
+        match verify(format!("0x01,{:?}\n0x02,{:?}\n", right, right)) {
+            Err(SearchError::InvalidArgument(e)) => assert!(e.contains("1 of 2 pairs"), "{}", e),
+            other => panic!("expected the wrong pair to fail, got {:?}", other),
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,375 @@
+use std::{fmt, str::FromStr};
+
+use ethers_core::{
+    abi::{encode, Token},
+    types::{Address, U256},
+    utils::{id, to_checksum},
+};
+use serde::Serialize;
+
+use crate::score::ScoreMode;
+use crate::search::AddressSalt;
+use crate::utils::{
+    bytes32, known_factory, leading_zero_nibbles, ADDRESS_NIBBLES, IMMUTABLE_CREATE2_FACTORY,
+};
+
+/// How the result of a search is printed
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The zero count, address and salt on one line
+    #[default]
+    Human,
+    /// Solidity declarations to paste into a forge script
+    Foundry,
+    /// The factory call deploying the contract, to paste into a wallet
+    Calldata,
+    /// The "Best:" block and round lines exactly as the first versions printed them,
+    /// for scripts parsing that output
+    Legacy,
+    /// A single JSON object, complete once the search is over
+    Json,
+    /// JSON lines, the events as they happen and the result as the last line,
+    /// each line a complete object so the output can be followed with `tail -f`
+    Jsonl,
+}
+
+impl OutputFormat {
+    /// The names accepted by `from_str`
+    pub const NAMES: [&'static str; 6] =
+        ["human", "foundry", "calldata", "legacy", "json", "jsonl"];
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(OutputFormat::Human),
+            "foundry" => Ok(OutputFormat::Foundry),
+            "calldata" => Ok(OutputFormat::Calldata),
+            "legacy" => Ok(OutputFormat::Legacy),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            _ => Err(format!(
+                "unknown output format {}, expected one of {}",
+                s,
+                OutputFormat::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Human => write!(f, "human"),
+            OutputFormat::Foundry => write!(f, "foundry"),
+            OutputFormat::Calldata => write!(f, "calldata"),
+            OutputFormat::Legacy => write!(f, "legacy"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Jsonl => write!(f, "jsonl"),
+        }
+    }
+}
+
+/// The function a factory deploys with, for `fmt_calldata`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FactoryAbi {
+    /// `safeCreate2(bytes32 salt, bytes initCode)`, as the ImmutableCreate2Factory takes it
+    SafeCreate2,
+    /// `deploy(bytes initCode, bytes32 salt)`, as the EIP-2470 singleton factory takes it
+    Eip2470,
+}
+
+impl FactoryAbi {
+    /// The names accepted by `from_str`
+    pub const NAMES: [&'static str; 2] = ["safe-create2", "eip2470"];
+
+    /// The function of a well-known factory
+    ///
+    /// # Arguments
+    /// * `factory` - The factory address
+    ///
+    /// # Returns
+    /// * The function, or `None` if the factory isn't known, there's no telling then
+    pub fn of(factory: Address) -> Option<Self> {
+        if known_factory("eip2470") == Some(factory) {
+            Some(FactoryAbi::Eip2470)
+        } else if IMMUTABLE_CREATE2_FACTORY.parse::<Address>().unwrap() == factory {
+            Some(FactoryAbi::SafeCreate2)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for FactoryAbi {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "safe-create2" => Ok(FactoryAbi::SafeCreate2),
+            "eip2470" => Ok(FactoryAbi::Eip2470),
+            _ => Err(format!(
+                "unknown factory ABI {}, expected one of {}",
+                s,
+                FactoryAbi::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FactoryAbi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FactoryAbi::SafeCreate2 => write!(f, "safe-create2"),
+            FactoryAbi::Eip2470 => write!(f, "eip2470"),
+        }
+    }
+}
+
+/// Format an address with the nibbles counting towards its score in green
+/// and the nibbles it missed of the target in red
+///
+/// # Arguments
+/// * `address` - The address to format
+/// * `mask` - Which nibbles count towards the score, see `ScoreMode::match_mask`
+/// * `target_nibbles` - The leading nibbles the target asks for, if the score is positional
+/// * `checksummed` - Write the address in its EIP-55 mixed case rather than lower case
+///
+/// # Returns
+/// * The 0x-prefixed address with ANSI colors
+pub fn fmt_match_color(
+    address: Address,
+    mask: &[bool; ADDRESS_NIBBLES],
+    target_nibbles: Option<usize>,
+    checksummed: bool,
+) -> String {
+    let hex_address = if checksummed {
+        to_checksum(&address, None)[2..].to_string()
+    } else {
+        hex::encode(address.as_bytes())
+    };
+    let mut out = "0x".to_string();
+    for (i, nibble) in hex_address.chars().enumerate() {
+        if mask[i] {
+            out.push_str(&format!("\x1b[32m{}\x1b[0m", nibble));
+        } else if target_nibbles.is_some_and(|target| i < target) {
+            out.push_str(&format!("\x1b[31m{}\x1b[0m", nibble));
+        } else {
+            out.push(nibble);
+        }
+    }
+    out
+}
+
+/// Annotate the nibbles of an address without colors, to print under it
+///
+/// # Arguments
+/// * `mask` - Which nibbles count towards the score, see `ScoreMode::match_mask`
+/// * `target_nibbles` - The leading nibbles the target asks for, if the score is positional
+///
+/// # Returns
+/// * A `^` under each matching nibble and an `x` under each missed nibble of the target,
+///   aligned with the 0x-prefixed address
+pub fn fmt_match_carets(mask: &[bool; ADDRESS_NIBBLES], target_nibbles: Option<usize>) -> String {
+    let mut out = "  ".to_string();
+    for (i, matched) in mask.iter().enumerate() {
+        if *matched {
+            out.push('^');
+        } else if target_nibbles.is_some_and(|target| i < target) {
+            out.push('x');
+        } else {
+            out.push(' ');
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// Format a result as Solidity declarations for a forge script
+///
+/// # Arguments
+/// * `found` - The address and salt to declare
+///
+/// # Returns
+/// * The `bytes32 salt` and `address expected` lines, the address is checksummed
+///   since solidity rejects address literals which aren't
+pub fn fmt_foundry(found: &AddressSalt) -> String {
+    format!(
+        "bytes32 salt = 0x{};\naddress expected = {};",
+        hex::encode(bytes32(found.salt_n)),
+        to_checksum(&found.address, None)
+    )
+}
+
+/// Format a result as the first versions printed it
+///
+/// # Arguments
+/// * `found` - The address and salt to print
+///
+/// # Returns
+/// * The "Best:" line, a blank line and the zero count, address and salt, unchecksummed
+pub fn fmt_legacy(found: &AddressSalt) -> String {
+    format!(
+        "Best:\n\n{} zeros {:?} salt 0x{}",
+        leading_zero_nibbles(found.address),
+        found.address,
+        hex::encode(bytes32(found.salt_n))
+    )
+}
+
+/// A result as JSON, see `fmt_json`
+#[derive(Serialize)]
+struct JsonResult {
+    /// "result" when the result ends a stream of events, which carry the same tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event: Option<&'static str>,
+    factory: Address,
+    init_code_hash: String,
+    salt: String,
+    address: Address,
+    score: u32,
+    /// The score in words, e.g. "12 zeros"
+    description: String,
+}
+
+/// Format a result as JSON
+///
+/// # Arguments
+/// * `found` - The address and salt to print
+/// * `factory` - The factory the address is derived from
+/// * `init_code_hash` - The init code hash it is derived from
+/// * `score_mode` - What the address was scored by
+/// * `streamed` - Format it as the last line of a JSON lines stream of events, on one line
+///   and tagged `"event": "result"`, rather than as an indented object of its own
+///
+/// # Returns
+/// * The JSON object
+pub fn fmt_json(
+    found: &AddressSalt,
+    factory: Address,
+    init_code_hash: U256,
+    score_mode: ScoreMode,
+    streamed: bool,
+) -> String {
+    let result = JsonResult {
+        event: streamed.then_some("result"),
+        factory,
+        init_code_hash: format!("0x{}", hex::encode(bytes32(init_code_hash))),
+        salt: format!("0x{}", hex::encode(bytes32(found.salt_n))),
+        address: found.address,
+        score: score_mode.score(found.address),
+        description: score_mode.describe(found.address),
+    };
+    // a struct of strings, numbers and addresses always serializes
+    if streamed {
+        serde_json::to_string(&result).unwrap()
+    } else {
+        serde_json::to_string_pretty(&result).unwrap()
+    }
+}
+
+/// Format a result as the transaction deploying it through the factory
+///
+/// # Arguments
+/// * `found` - The address and salt to deploy
+/// * `factory` - The factory to call
+/// * `abi` - The function the factory deploys with, see `FactoryAbi::of`
+/// * `init_code` - The creation bytecode with any constructor arguments
+///
+/// # Returns
+/// * The `to` and ABI-encoded `data` lines of the transaction
+pub fn fmt_calldata(
+    found: &AddressSalt,
+    factory: Address,
+    abi: FactoryAbi,
+    init_code: &[u8],
+) -> String {
+    let salt = Token::FixedBytes(bytes32(found.salt_n).to_vec());
+    let init_code = Token::Bytes(init_code.to_vec());
+    let (selector, args) = match abi {
+        FactoryAbi::Eip2470 => (id("deploy(bytes,bytes32)"), [init_code, salt]),
+        FactoryAbi::SafeCreate2 => (id("safeCreate2(bytes32,bytes)"), [salt, init_code]),
+    };
+    format!(
+        "to: {}\ndata: 0x{}{}",
+        to_checksum(&factory, None),
+        hex::encode(selector),
+        hex::encode(encode(&args))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::create2_address;
+
+    /// Example 5 of EIP-1014, the address of salt 0xcafebabe with init code 0xdeadbeef
+    fn eip1014_example() -> AddressSalt {
+        let factory: Address = "0x00000000000000000000000000000000deadbeef"
+            .parse()
+            .unwrap();
+        let init_code_hash =
+            U256::from_big_endian(&ethers_core::utils::keccak256([0xde, 0xad, 0xbe, 0xef]));
+        let salt_n = U256::from(0xcafe_babe_u64);
+        AddressSalt {
+            address: create2_address(factory, salt_n, init_code_hash),
+            salt_n,
+        }
+    }
+
+    #[test]
+    fn foundry_declares_the_winning_salt() {
+        let found = eip1014_example();
+        let snippet = fmt_foundry(&found);
+        assert_eq!(
+            snippet,
+            "bytes32 salt = 0x00000000000000000000000000000000000000000000000000000000cafebabe;\n\
+             address expected = 0x60f3f640a8508fC6a86d45DF051962668E1e8AC7;"
+        );
+        let salt = snippet
+            .strip_prefix("bytes32 salt = 0x")
+            .and_then(|rest| rest.split(';').next())
+            .unwrap();
+        assert_eq!(U256::from_str_radix(salt, 16).unwrap(), found.salt_n);
+    }
+
+    #[test]
+    fn legacy_prints_the_best_block() {
+        assert_eq!(
+            fmt_legacy(&eip1014_example()),
+            "Best:\n\n\
+             0 zeros 0x60f3f640a8508fc6a86d45df051962668e1e8ac7 \
+             salt 0x00000000000000000000000000000000000000000000000000000000cafebabe"
+        );
+    }
+
+    #[test]
+    fn calldata_calls_the_factory_abi() {
+        let found = eip1014_example();
+        let factory: Address = IMMUTABLE_CREATE2_FACTORY.parse().unwrap();
+        assert_eq!(FactoryAbi::of(factory), Some(FactoryAbi::SafeCreate2));
+        assert_eq!(
+            FactoryAbi::of(known_factory("eip2470").unwrap()),
+            Some(FactoryAbi::Eip2470)
+        );
+        assert_eq!(FactoryAbi::of(Address::repeat_byte(0x11)), None);
+
+        // safeCreate2(0x...cafebabe, 0xdeadbeef), the selector is keccak256 of the signature
+        assert_eq!(
+            fmt_calldata(
+                &found,
+                factory,
+                FactoryAbi::SafeCreate2,
+                &[0xde, 0xad, 0xbe, 0xef]
+            ),
+            "to: 0x0000000000FFe8B47B3e2130213B802212439497\n\
+             data: 0x64e03087\
+             00000000000000000000000000000000000000000000000000000000cafebabe\
+             0000000000000000000000000000000000000000000000000000000000000040\
+             0000000000000000000000000000000000000000000000000000000000000004\
+             deadbeef00000000000000000000000000000000000000000000000000000000"
+        );
+    }
+}
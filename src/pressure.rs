@@ -0,0 +1,280 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// How long the throttle holds after changing the threads before it changes them again,
+/// the load average takes about a minute to catch up with a change
+pub const THROTTLE_HOLD: Duration = Duration::from_secs(60);
+
+/// The share of a limit the pressure has to fall under before threads are added back,
+/// so the throttle doesn't flap around a limit
+const RESUME_FRACTION: f64 = 0.9;
+
+/// A reading of the pressure on the machine, each signal `None` where it can't be read
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Pressure {
+    /// The 1-minute load average per core, the search itself included
+    pub load_per_core: Option<f64>,
+    /// The share of memory still available, 0 to 1
+    pub memory_available: Option<f64>,
+    /// The temperature of the hottest thermal zone, in degrees Celsius
+    pub temperature: Option<f64>,
+}
+
+impl fmt::Display for Pressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut signals = Vec::new();
+        if let Some(load) = self.load_per_core {
+            signals.push(format!("load {:.2} per core", load));
+        }
+        if let Some(memory) = self.memory_available {
+            signals.push(format!("{:.0}% memory available", memory * 100.0));
+        }
+        if let Some(temperature) = self.temperature {
+            signals.push(format!("{:.0}°C", temperature));
+        }
+        if signals.is_empty() {
+            return write!(f, "no readings");
+        }
+        write!(f, "{}", signals.join(", "))
+    }
+}
+
+/// Where the pressure readings come from, the machine itself or a stand-in
+pub trait PressureSource: Send + Sync {
+    /// Read the current pressure
+    fn read(&self) -> Pressure;
+}
+
+/// The pressure above which the search gives up threads
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PressureLimits {
+    pub max_load_per_core: f64,
+    pub min_memory_available: f64,
+    pub max_temperature: f64,
+}
+
+impl Default for PressureLimits {
+    /// A load a quarter above one per core, the search alone makes about one, under 10%
+    /// of memory available, or 85°C
+    fn default() -> Self {
+        Self {
+            max_load_per_core: 1.25,
+            min_memory_available: 0.1,
+            max_temperature: 85.0,
+        }
+    }
+}
+
+impl PressureLimits {
+    /// Whether any signal is past its limit
+    pub fn is_high(&self, pressure: &Pressure) -> bool {
+        pressure
+            .load_per_core
+            .is_some_and(|load| load > self.max_load_per_core)
+            || pressure
+                .memory_available
+                .is_some_and(|memory| memory < self.min_memory_available)
+            || pressure
+                .temperature
+                .is_some_and(|temperature| temperature > self.max_temperature)
+    }
+
+    /// Whether every signal is comfortably within its limit, see `RESUME_FRACTION`
+    pub fn is_low(&self, pressure: &Pressure) -> bool {
+        pressure
+            .load_per_core
+            .map_or(true, |load| load < self.max_load_per_core * RESUME_FRACTION)
+            && pressure.memory_available.map_or(true, |memory| {
+                memory * RESUME_FRACTION > self.min_memory_available
+            })
+            && pressure.temperature.map_or(true, |temperature| {
+                temperature < self.max_temperature * RESUME_FRACTION
+            })
+    }
+}
+
+/// Decides how many threads may search from the pressure readings: halves them while the
+/// pressure is high, adds one back at a time once it's low, and holds in between
+pub struct Throttle {
+    limits: PressureLimits,
+    num_threads: usize,
+    allowed: usize,
+    /// When the threads last changed, see `THROTTLE_HOLD`
+    changed_at: Option<Instant>,
+}
+
+impl Throttle {
+    /// Start with every thread allowed
+    ///
+    /// # Arguments
+    /// * `num_threads` - The threads of the search
+    /// * `limits` - The pressure above which threads are given up
+    pub fn new(num_threads: usize, limits: PressureLimits) -> Self {
+        Self {
+            limits,
+            num_threads,
+            allowed: num_threads,
+            changed_at: None,
+        }
+    }
+
+    /// The threads currently allowed to search
+    pub fn allowed(&self) -> usize {
+        self.allowed
+    }
+
+    /// Record a pressure reading
+    ///
+    /// # Arguments
+    /// * `pressure` - The reading
+    /// * `now` - The time it was taken
+    ///
+    /// # Returns
+    /// * The threads now allowed if the reading changed them, `None` otherwise
+    pub fn observe(&mut self, pressure: &Pressure, now: Instant) -> Option<usize> {
+        let holding = self
+            .changed_at
+            .is_some_and(|changed_at| now.saturating_duration_since(changed_at) < THROTTLE_HOLD);
+        if holding {
+            return None;
+        }
+        let allowed = if self.limits.is_high(pressure) {
+            (self.allowed / 2).max(1)
+        } else if self.limits.is_low(pressure) {
+            (self.allowed + 1).min(self.num_threads)
+        } else {
+            self.allowed
+        };
+        if allowed == self.allowed {
+            return None;
+        }
+        self.allowed = allowed;
+        self.changed_at = Some(now);
+        Some(allowed)
+    }
+}
+
+/// The pressure on this machine, read from `/proc` and `/sys` on Linux
+///
+/// Elsewhere no signal can be read yet, so the search is never throttled.
+#[cfg(feature = "pressure")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemPressure;
+
+#[cfg(feature = "pressure")]
+impl PressureSource for SystemPressure {
+    fn read(&self) -> Pressure {
+        #[cfg(target_os = "linux")]
+        return linux::read();
+        #[cfg(not(target_os = "linux"))]
+        return Pressure::default();
+    }
+}
+
+#[cfg(all(feature = "pressure", target_os = "linux"))]
+mod linux {
+    use std::fs;
+
+    use super::Pressure;
+
+    pub fn read() -> Pressure {
+        Pressure {
+            load_per_core: load_per_core(),
+            memory_available: memory_available(),
+            temperature: temperature(),
+        }
+    }
+
+    /// The first field of /proc/loadavg over the cores
+    fn load_per_core() -> Option<f64> {
+        let loadavg = fs::read_to_string("/proc/loadavg").ok()?;
+        let load: f64 = loadavg.split_whitespace().next()?.parse().ok()?;
+        let cores = std::thread::available_parallelism().ok()?.get();
+        Some(load / cores as f64)
+    }
+
+    /// MemAvailable over MemTotal from /proc/meminfo
+    fn memory_available() -> Option<f64> {
+        let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+        let field = |name: &str| -> Option<f64> {
+            let line = meminfo.lines().find(|line| line.starts_with(name))?;
+            line.split_whitespace().nth(1)?.parse().ok()
+        };
+        Some(field("MemAvailable:")? / field("MemTotal:")?)
+    }
+
+    /// The hottest of /sys/class/thermal/thermal_zone*/temp, which are in millidegrees
+    fn temperature() -> Option<f64> {
+        fs::read_dir("/sys/class/thermal")
+            .ok()?
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("thermal_zone")
+            })
+            .filter_map(|entry| fs::read_to_string(entry.path().join("temp")).ok())
+            .filter_map(|temp| temp.trim().parse::<f64>().ok())
+            .map(|millidegrees| millidegrees / 1000.0)
+            .reduce(f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A reading of the load alone
+    fn load(load_per_core: f64) -> Pressure {
+        Pressure {
+            load_per_core: Some(load_per_core),
+            ..Pressure::default()
+        }
+    }
+
+    #[test]
+    fn throttle_follows_synthetic_readings() {
+        let start = Instant::now();
+        let at = |held: u32| start + THROTTLE_HOLD * held;
+        let mut throttle = Throttle::new(8, PressureLimits::default());
+
+        // high pressure halves the threads, but not again until the hold is over
+        assert_eq!(throttle.observe(&load(2.0), at(0)), Some(4));
+        assert_eq!(
+            throttle.observe(&load(2.0), at(0) + THROTTLE_HOLD / 2),
+            None
+        );
+        assert_eq!(throttle.observe(&load(2.0), at(1)), Some(2));
+        assert_eq!(throttle.observe(&load(2.0), at(2)), Some(1));
+        // never below one thread
+        assert_eq!(throttle.observe(&load(2.0), at(3)), None);
+        assert_eq!(throttle.allowed(), 1);
+
+        // between the resume fraction and the limit, the threads hold
+        assert_eq!(throttle.observe(&load(1.2), at(4)), None);
+        // low pressure adds one back at a time, up to every thread
+        for allowed in 2..=8 {
+            assert_eq!(
+                throttle.observe(&load(0.5), at(3 + allowed as u32)),
+                Some(allowed)
+            );
+        }
+        assert_eq!(throttle.observe(&load(0.5), at(12)), None);
+
+        // any one signal past its limit is high pressure, and a missing one is no pressure
+        let hot = Pressure {
+            temperature: Some(90.0),
+            ..load(0.5)
+        };
+        assert_eq!(throttle.observe(&hot, at(13)), Some(4));
+        let short_of_memory = Pressure {
+            memory_available: Some(0.05),
+            ..Pressure::default()
+        };
+        assert_eq!(throttle.observe(&short_of_memory, at(14)), Some(2));
+        assert_eq!(throttle.observe(&Pressure::default(), at(15)), Some(3));
+    }
+}
@@ -0,0 +1,62 @@
+use ethers_core::types::Address;
+use serde_json::Value;
+
+use crate::error::SearchError;
+use crate::utils::strip_hex_prefix;
+
+/// Fetch the code deployed at an address, at the latest block
+///
+/// # Arguments
+/// * `url` - The JSON-RPC endpoint of a node
+/// * `address` - The account to look up
+///
+/// # Returns
+/// * The code, empty for an externally owned account or an account with nothing deployed
+pub fn get_code(url: &str, address: Address) -> Result<Vec<u8>, SearchError> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_getCode",
+        "params": [format!("{:?}", address), "latest"],
+    });
+    let body = ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&request.to_string())
+        .map_err(|e| SearchError::Rpc(e.to_string()))?
+        .into_string()
+        .map_err(|e| SearchError::Rpc(e.to_string()))?;
+    parse_get_code_response(&body)
+}
+
+/// Read the code out of an `eth_getCode` response
+///
+/// # Arguments
+/// * `body` - The JSON-RPC response
+///
+/// # Returns
+/// * The code, or an error for a JSON-RPC error or a result which isn't hex
+pub fn parse_get_code_response(body: &str) -> Result<Vec<u8>, SearchError> {
+    let response: Value =
+        serde_json::from_str(body).map_err(|e| SearchError::Rpc(format!("{}: {}", e, body)))?;
+    if let Some(error) = response.get("error") {
+        return Err(SearchError::Rpc(error.to_string()));
+    }
+    let code = response
+        .get("result")
+        .and_then(Value::as_str)
+        .ok_or_else(|| SearchError::Rpc(format!("no result in {}", body)))?;
+    hex::decode(strip_hex_prefix(code)).map_err(|e| SearchError::Rpc(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_code_result_takes_either_prefix() {
+        for result in ["0x6001", "0X6001", "6001"] {
+            let body = format!(r#"{{"jsonrpc":"2.0","id":1,"result":"{}"}}"#, result);
+            assert_eq!(parse_get_code_response(&body).unwrap(), vec![0x60, 0x01]);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use rand::Rng;
+
+use crate::search::AddressSalt;
+
+/// A uniform random sample of the addresses offered, independent of their score
+#[derive(Clone, Default)]
+pub struct Reservoir {
+    /// The size of the sample, 0 keeps nothing
+    capacity: usize,
+    /// Number of addresses offered, `items` is a uniform sample of them
+    seen: u128,
+    items: Vec<AddressSalt>,
+}
+
+impl Reservoir {
+    /// Create an empty reservoir
+    ///
+    /// # Arguments
+    /// * `capacity` - The number of addresses to keep
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            items: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Offer an address, keeping it with probability capacity / seen (algorithm R)
+    ///
+    /// # Arguments
+    /// * `item` - The address and its salt
+    /// * `rng` - The source of randomness
+    pub fn offer(&mut self, item: AddressSalt, rng: &mut impl Rng) {
+        self.seen += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(item);
+            return;
+        }
+        let slot = rng.gen_range(0..self.seen);
+        if slot < self.capacity as u128 {
+            self.items[slot as usize] = item;
+        }
+    }
+
+    /// Combine with the reservoir of a disjoint set of addresses, as if a single
+    /// reservoir had been offered both
+    ///
+    /// Each kept address is drawn from one side or the other in proportion to the
+    /// number of addresses that side has yet to stand for, so a side which saw more
+    /// addresses contributes more of the sample.
+    ///
+    /// # Arguments
+    /// * `other` - The reservoir to merge in
+    /// * `rng` - The source of randomness
+    pub fn merge(&mut self, other: Reservoir, rng: &mut impl Rng) {
+        let capacity = self.capacity.max(other.capacity);
+        let seen = self.seen + other.seen;
+        let mut sides = [
+            (std::mem::take(&mut self.items), self.seen),
+            (other.items, other.seen),
+        ];
+
+        let mut merged = Vec::with_capacity(capacity);
+        // a side runs out of items only once its remaining count reaches zero
+        while merged.len() < capacity && sides[0].1 + sides[1].1 > 0 {
+            let side = if rng.gen_range(0..sides[0].1 + sides[1].1) < sides[0].1 {
+                0
+            } else {
+                1
+            };
+            let (items, remaining) = &mut sides[side];
+            let index = rng.gen_range(0..items.len());
+            merged.push(items.swap_remove(index));
+            *remaining -= 1;
+        }
+
+        self.capacity = capacity;
+        self.seen = seen;
+        self.items = merged;
+    }
+
+    /// The number of addresses offered, including those merged in
+    pub fn seen(&self) -> u128 {
+        self.seen
+    }
+
+    /// The sampled addresses, `capacity` of them unless fewer were offered
+    pub fn items(&self) -> &[AddressSalt] {
+        &self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::types::{Address, U256};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// A reservoir offered one address per salt
+    fn offered(salts: std::ops::Range<u64>, capacity: usize, rng: &mut StdRng) -> Reservoir {
+        let mut reservoir = Reservoir::new(capacity);
+        for salt in salts {
+            let item = AddressSalt {
+                address: Address::from_low_u64_be(salt),
+                salt_n: U256::from(salt),
+            };
+            reservoir.offer(item, rng);
+        }
+        reservoir
+    }
+
+    #[test]
+    fn sample_size_is_exactly_k_past_k_addresses() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(offered(0..1000, 10, &mut rng).items().len(), 10);
+        // fewer addresses than the capacity are all kept
+        assert_eq!(offered(0..4, 10, &mut rng).items().len(), 4);
+        assert!(offered(0..1000, 0, &mut rng).items().is_empty());
+
+        let mut merged = offered(0..600, 10, &mut rng);
+        merged.merge(offered(600..603, 10, &mut rng), &mut rng);
+        assert_eq!(merged.seen(), 603);
+        assert_eq!(merged.items().len(), 10);
+        let mut small = offered(0..3, 10, &mut rng);
+        small.merge(offered(3..6, 10, &mut rng), &mut rng);
+        assert_eq!(small.items().len(), 6);
+    }
+}
@@ -0,0 +1,917 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use ethers_core::{
+    types::{Address, U256},
+    utils::keccak256,
+};
+
+use crate::search::AddressSalt;
+use crate::utils::{
+    address_to_u256, leading_zero_nibbles, strip_hex_prefix, ADDRESS_BYTES, ADDRESS_NIBBLES,
+};
+
+/// How two addresses with the same score are ordered
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    /// The lower address wins, for leading zeros that is the numerically lowest address
+    #[default]
+    LowestAddress,
+    /// The lower salt wins, so the result doesn't depend on which address came up
+    LowestSalt,
+}
+
+impl TieBreak {
+    /// The names accepted by `from_str`
+    pub const NAMES: [&'static str; 2] = ["lowest-address", "lowest-salt"];
+}
+
+impl FromStr for TieBreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lowest-address" => Ok(TieBreak::LowestAddress),
+            "lowest-salt" => Ok(TieBreak::LowestSalt),
+            _ => Err(format!(
+                "unknown tie break {}, expected one of {}",
+                s,
+                TieBreak::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TieBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TieBreak::LowestAddress => write!(f, "lowest-address"),
+            TieBreak::LowestSalt => write!(f, "lowest-salt"),
+        }
+    }
+}
+
+/// What makes one address better than another
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ScoreMode {
+    /// The numerically lowest address, i.e. the most leading zeros
+    #[default]
+    LeadingZeros,
+    /// The longest run of one nibble at the start of the address
+    /// either a specific nibble, e.g. 0x8888..., or `None` for whichever nibble comes first
+    LeadingNibble(Option<u8>),
+    /// The most 0x00 bytes anywhere in the address
+    TotalZeroBytes,
+    /// The most bytes of a given value anywhere in the address, e.g. 0xff,
+    /// whole bytes rather than nibbles so 0x0ff0 counts none
+    ByteCount(u8),
+    /// The most trailing zeros, and of addresses with as many the most leading zeros
+    TrailingThenLeading,
+    /// The most upper case letters in the EIP-55 checksummed form of the address
+    ChecksumUppercase,
+    /// The longest match of a word at the start of the checksummed form, case included,
+    /// e.g. 0xDeaDBEEF... matches DeaDB of DeaDbeef
+    ChecksumWord(CasedWord),
+    /// The address numerically closest to a target, above or below it,
+    /// `LeadingZeros` is the same with the zero address as the target
+    ClosestTo(Address),
+}
+
+impl ScoreMode {
+    /// The names accepted by `parse_score`
+    pub const NAMES: [&'static str; 4] = [
+        "leading-zeros",
+        "total-zero-bytes",
+        "trailing-then-leading",
+        "checksum-uppercase",
+    ];
+
+    /// Score an address, higher is better
+    ///
+    /// # Arguments
+    /// * `address` - The address to score
+    ///
+    /// # Returns
+    /// * The number of leading zeros, the length of the leading nibble run,
+    ///   the number of zero bytes or of bytes of the value counted, the number of
+    ///   trailing zeros, the primary criterion of `TrailingThenLeading`, the number
+    ///   of upper case letters, the length of the word matched or the leading zeros
+    ///   of the distance to the target, a coarse measure as the distance is compared
+    pub fn score(&self, address: Address) -> u32 {
+        match self {
+            ScoreMode::LeadingZeros => leading_zero_nibbles(address) as u32,
+            ScoreMode::LeadingNibble(nibble) => leading_nibble_run(address, *nibble).1 as u32,
+            ScoreMode::TotalZeroBytes => zero_bytes(address) as u32,
+            ScoreMode::ByteCount(byte) => count_bytes(address, *byte) as u32,
+            ScoreMode::TrailingThenLeading => trailing_zero_nibbles(address) as u32,
+            ScoreMode::ChecksumUppercase => match letter_nibbles(address) {
+                0 => 0,
+                _ => checksum_uppercase(address)
+                    .iter()
+                    .filter(|upper| **upper)
+                    .count() as u32,
+            },
+            ScoreMode::ChecksumWord(word) => word.matched(address) as u32,
+            ScoreMode::ClosestTo(target) => distance_zero_nibbles(distance(address, *target)),
+        }
+    }
+
+    /// A bound on the score which doesn't need the checksum, for the checksum modes
+    ///
+    /// # Returns
+    /// * The letters of the address, which are all the checksum can make upper case, or
+    ///   the length of the word matched ignoring case, `score` for the other modes
+    fn score_bound(&self, address: Address) -> u32 {
+        match self {
+            ScoreMode::ChecksumUppercase => letter_nibbles(address) as u32,
+            ScoreMode::ChecksumWord(word) => word.matched_ignoring_case(address) as u32,
+            _ => self.score(address),
+        }
+    }
+
+    /// Order two addresses best first by score alone, `Equal` is a tie
+    ///
+    /// Trailing then leading compares the trailing zeros first and the leading zeros second.
+    /// The checksum modes only hash `a` when its bound could reach the score of `b`, since
+    /// `a` is the candidate of the hot loop and rarely can, and only hash `b` when its
+    /// score isn't passed in, since `b` is the best so far and rarely changes.
+    fn compare_scores(&self, a: Address, b: Address, score_b: Option<u32>) -> Ordering {
+        match self {
+            ScoreMode::TrailingThenLeading => self
+                .score(b)
+                .cmp(&self.score(a))
+                .then_with(|| leading_zero_nibbles(b).cmp(&leading_zero_nibbles(a))),
+            ScoreMode::ClosestTo(target) => distance(a, *target).cmp(&distance(b, *target)),
+            ScoreMode::ChecksumUppercase | ScoreMode::ChecksumWord(_) => {
+                let score_b = score_b.unwrap_or_else(|| self.score(b));
+                if self.score_bound(a) < score_b {
+                    return Ordering::Greater;
+                }
+                score_b.cmp(&self.score(a))
+            }
+            _ => self.score(b).cmp(&self.score(a)),
+        }
+    }
+
+    /// Order two addresses best first, ties going to the lower address
+    ///
+    /// For leading zeros the whole address is compared rather than the zero count,
+    /// which is the same order, so of two addresses with as many zeros the lower one wins.
+    ///
+    /// # Returns
+    /// * `Ordering::Less` if `a` is better than `b`
+    pub fn compare(&self, a: Address, b: Address) -> Ordering {
+        self.compare_scored(a, b, None)
+    }
+
+    /// `compare` with the score of `b` if known, see `compare_scores`
+    fn compare_scored(&self, a: Address, b: Address, score_b: Option<u32>) -> Ordering {
+        match self {
+            ScoreMode::LeadingZeros => a.cmp(&b),
+            _ => self.compare_scores(a, b, score_b).then_with(|| a.cmp(&b)),
+        }
+    }
+
+    /// Whether `candidate` is strictly better than `current`
+    ///
+    /// The one comparison behind the search, the merge of rounds and the ledger, so
+    /// every score mode breaks ties the same way.
+    ///
+    /// # Arguments
+    /// * `candidate` - The address found and its salt
+    /// * `current` - The best so far
+    /// * `tie_break` - How two addresses with the same score are ordered
+    pub fn is_better(
+        &self,
+        candidate: &AddressSalt,
+        current: &AddressSalt,
+        tie_break: TieBreak,
+    ) -> bool {
+        self.order(candidate, current, tie_break) == Ordering::Less
+    }
+
+    /// `is_better` with the score of `current` known, so the checksum modes don't hash
+    /// the best again for every candidate of the hot loop
+    ///
+    /// # Arguments
+    /// * `candidate` - The address found and its salt
+    /// * `current` - The best so far
+    /// * `current_score` - The score of `current`
+    /// * `tie_break` - How two addresses with the same score are ordered
+    pub fn is_better_scored(
+        &self,
+        candidate: &AddressSalt,
+        current: &AddressSalt,
+        current_score: u32,
+        tie_break: TieBreak,
+    ) -> bool {
+        self.order_scored(candidate, current, Some(current_score), tie_break) == Ordering::Less
+    }
+
+    /// Order two found addresses best first, as `is_better` does
+    ///
+    /// # Arguments
+    /// * `a` - An address found and its salt
+    /// * `b` - Another
+    /// * `tie_break` - How two addresses with the same score are ordered
+    ///
+    /// # Returns
+    /// * `Ordering::Less` if `a` is better than `b`, `Equal` only for the same address
+    ///   with `LowestAddress` or the same salt with `LowestSalt`
+    pub fn order(&self, a: &AddressSalt, b: &AddressSalt, tie_break: TieBreak) -> Ordering {
+        self.order_scored(a, b, None, tie_break)
+    }
+
+    /// `order` with the score of `b` if known, see `compare_scores`
+    fn order_scored(
+        &self,
+        a: &AddressSalt,
+        b: &AddressSalt,
+        score_b: Option<u32>,
+        tie_break: TieBreak,
+    ) -> Ordering {
+        match tie_break {
+            TieBreak::LowestAddress => self.compare_scored(a.address, b.address, score_b),
+            TieBreak::LowestSalt => self
+                .compare_scores(a.address, b.address, score_b)
+                .then_with(|| a.salt_n.cmp(&b.salt_n)),
+        }
+    }
+
+    /// Sort addresses found best first, ties going to the lower salt, then the lower
+    /// address, a total order so the result doesn't depend on the order they were found in,
+    /// e.g. by which thread got there first
+    ///
+    /// # Arguments
+    /// * `found` - The addresses and their salts, sorted in place
+    pub fn sort_best_first(&self, found: &mut [AddressSalt]) {
+        found.sort_by(|a, b| {
+            self.order(a, b, TieBreak::LowestSalt)
+                .then_with(|| a.address.cmp(&b.address))
+        });
+    }
+
+    /// Describe the score of an address for the logs, e.g. "12 zeros" or "7 leading 8s"
+    pub fn describe(&self, address: Address) -> String {
+        match self {
+            ScoreMode::LeadingZeros => format!("{} zeros", leading_zero_nibbles(address)),
+            ScoreMode::LeadingNibble(nibble) => {
+                let (nibble, run) = leading_nibble_run(address, *nibble);
+                format!("{} leading {:x}s", run, nibble)
+            }
+            ScoreMode::TotalZeroBytes => format!("{} zero bytes", zero_bytes(address)),
+            ScoreMode::ByteCount(byte) => {
+                format!("{} 0x{:02x} bytes", count_bytes(address, *byte), byte)
+            }
+            ScoreMode::TrailingThenLeading => format!(
+                "{} trailing zeros, {} leading zeros",
+                trailing_zero_nibbles(address),
+                leading_zero_nibbles(address)
+            ),
+            ScoreMode::ChecksumUppercase => format!("{} upper case", self.score(address)),
+            ScoreMode::ChecksumWord(word) => {
+                format!("{} chars of {}", word.matched(address), word)
+            }
+            ScoreMode::ClosestTo(target) => format!("distance {:#x}", distance(address, *target)),
+        }
+    }
+
+    /// Whether the score is read from the checksummed form, which the address should
+    /// then be shown in
+    pub fn checksummed(&self) -> bool {
+        matches!(
+            self,
+            ScoreMode::ChecksumUppercase | ScoreMode::ChecksumWord(_)
+        )
+    }
+
+    /// Which nibbles of an address count towards its score
+    ///
+    /// # Arguments
+    /// * `address` - The address to score
+    ///
+    /// # Returns
+    /// * One flag per nibble, most significant first
+    pub fn match_mask(&self, address: Address) -> [bool; ADDRESS_NIBBLES] {
+        let mut mask = [false; ADDRESS_NIBBLES];
+        match self {
+            ScoreMode::LeadingZeros | ScoreMode::LeadingNibble(_) | ScoreMode::ChecksumWord(_) => {
+                let run = self.score(address) as usize;
+                mask[..run].fill(true);
+            }
+            ScoreMode::ChecksumUppercase => mask = checksum_uppercase(address),
+            ScoreMode::ClosestTo(target) => {
+                // the nibbles shared with the target, close addresses share the most
+                let shared = (address_to_u256(address) ^ address_to_u256(*target))
+                    .leading_zeros()
+                    .saturating_sub(96)
+                    / 4;
+                mask[..shared as usize].fill(true);
+            }
+            ScoreMode::TotalZeroBytes | ScoreMode::ByteCount(_) => {
+                let counted = match self {
+                    ScoreMode::ByteCount(byte) => *byte,
+                    _ => 0,
+                };
+                for (i, byte) in address.as_bytes().iter().enumerate() {
+                    if *byte == counted {
+                        mask[2 * i] = true;
+                        mask[2 * i + 1] = true;
+                    }
+                }
+            }
+            ScoreMode::TrailingThenLeading => {
+                mask[..leading_zero_nibbles(address) as usize].fill(true);
+                mask[ADDRESS_NIBBLES - trailing_zero_nibbles(address) as usize..].fill(true);
+            }
+        }
+        mask
+    }
+
+    /// The nibbles an address has to match to reach a target, for the leading modes
+    ///
+    /// # Arguments
+    /// * `target` - The score aimed for
+    ///
+    /// # Returns
+    /// * The number of leading nibbles, or `None` when the score isn't positional
+    pub fn target_nibbles(&self, target: u8) -> Option<usize> {
+        match self {
+            ScoreMode::LeadingZeros | ScoreMode::LeadingNibble(_) => {
+                Some((target as usize).min(ADDRESS_NIBBLES))
+            }
+            ScoreMode::ChecksumWord(word) => Some((target as usize).min(word.len())),
+            ScoreMode::TotalZeroBytes
+            | ScoreMode::ByteCount(_)
+            | ScoreMode::TrailingThenLeading
+            | ScoreMode::ChecksumUppercase
+            | ScoreMode::ClosestTo(_) => None,
+        }
+    }
+
+    /// What the score counts, e.g. "zeros" in "Expected attempts for 12 zeros"
+    pub fn unit(&self) -> &'static str {
+        match self {
+            ScoreMode::LeadingZeros => "zeros",
+            ScoreMode::LeadingNibble(_) => "leading nibbles",
+            ScoreMode::TotalZeroBytes => "zero bytes",
+            ScoreMode::ByteCount(_) => "matching bytes",
+            ScoreMode::TrailingThenLeading => "trailing zeros",
+            ScoreMode::ChecksumUppercase => "upper case letters",
+            ScoreMode::ChecksumWord(_) => "word characters",
+            ScoreMode::ClosestTo(_) => "zeros of the distance",
+        }
+    }
+
+    /// The expected number of attempts to find an address scoring at least `target`
+    ///
+    /// Each nibble of an address is taken as independent and uniform, as the output
+    /// of keccak256 is. For zero bytes, or bytes of any one value, this gives a binomial
+    /// tail over the 20 bytes, which unlike the nibble modes isn't a power of 16, e.g.
+    /// 4 zero bytes take about 2^32 / 4845 attempts since they can be any 4 of the 20
+    /// bytes. The checksum modes
+    /// take each letter as upper case with even odds, as the bits of the checksum hash are.
+    ///
+    /// # Arguments
+    /// * `target` - The score aimed for
+    ///
+    /// # Returns
+    /// * The expected number of attempts, the inverse of the chance of a single attempt
+    pub fn expected_attempts(&self, target: u8) -> f64 {
+        match self {
+            ScoreMode::LeadingZeros
+            | ScoreMode::LeadingNibble(Some(_))
+            | ScoreMode::TrailingThenLeading => 16_f64.powi(target as i32),
+            // the run starts with whichever nibble comes first
+            ScoreMode::LeadingNibble(None) => 16_f64.powi(target.saturating_sub(1) as i32),
+            ScoreMode::TotalZeroBytes | ScoreMode::ByteCount(_) => {
+                let bytes = ADDRESS_BYTES as i32;
+                let p = 1.0 / 256.0;
+                // P(at least target of the bytes are zero)
+                let chance: f64 = (target as i32..=bytes)
+                    .map(|k| binomial(bytes, k) * p.powi(k) * (1.0 - p).powi(bytes - k))
+                    .sum();
+                1.0 / chance
+            }
+            ScoreMode::ChecksumUppercase => {
+                let nibbles = ADDRESS_NIBBLES as i32;
+                // a letter, 6 of the 16 nibbles, made upper case
+                let p = 6.0 / 16.0 / 2.0;
+                // P(at least target of the nibbles are upper case letters)
+                let chance: f64 = (target as i32..=nibbles)
+                    .map(|k| binomial(nibbles, k) * p.powi(k) * (1.0 - p).powi(nibbles - k))
+                    .sum();
+                1.0 / chance
+            }
+            // within 16^(40 - target) of the target on either side
+            ScoreMode::ClosestTo(_) => 16_f64.powi(target as i32) / 2.0,
+            // a digit matches one nibble in 16, a letter also has to get its case right
+            ScoreMode::ChecksumWord(word) => word.chars[..(target as usize).min(word.len())]
+                .iter()
+                .map(|c| if c.is_ascii_digit() { 16.0 } else { 32.0 })
+                .product(),
+        }
+    }
+
+    /// The score a search of so many attempts should expect to reach, the inverse of
+    /// `expected_attempts`: the score whose expected attempts equal the budget
+    ///
+    /// The expected attempts grow geometrically from one score to the next, so between
+    /// two whole scores the fraction is interpolated on their logarithm, e.g. 16^7.5
+    /// attempts give 7.5 leading zeros.
+    ///
+    /// # Arguments
+    /// * `attempts` - The number of addresses computed
+    ///
+    /// # Returns
+    /// * The expected best score, at most the highest score there is
+    pub fn expected_best_score_for_attempts(&self, attempts: u128) -> f64 {
+        let attempts = attempts as f64;
+        let mut score = 0u8;
+        while score < u8::MAX {
+            let current = self.expected_attempts(score);
+            let next = self.expected_attempts(score + 1);
+            // no address scores higher, or a word is matched in full
+            if next.is_infinite() || (next <= current && current > 1.0) {
+                break;
+            }
+            if next > attempts {
+                if next <= current {
+                    break;
+                }
+                let fraction = (attempts.ln() - current.ln()) / (next.ln() - current.ln());
+                return score as f64 + fraction.max(0.0);
+            }
+            score += 1;
+        }
+        return score as f64;
+    }
+}
+
+/// The binomial coefficient n choose k, as a float since it only feeds an estimate
+fn binomial(n: i32, k: i32) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Count the 0x00 bytes of an address, wherever they are
+///
+/// # Arguments
+/// * `address` - The address to count in
+///
+/// # Returns
+/// * The number of zero bytes, 0 through 20
+pub fn zero_bytes(address: Address) -> u8 {
+    count_bytes(address, 0)
+}
+
+/// Count the bytes of an address equal to a value, wherever they are
+///
+/// # Arguments
+/// * `address` - The address to count in
+/// * `value` - The byte to count
+///
+/// # Returns
+/// * The number of bytes equal to `value`, 0 through 20
+pub fn count_bytes(address: Address, value: u8) -> u8 {
+    address
+        .as_bytes()
+        .iter()
+        .filter(|byte| **byte == value)
+        .count() as u8
+}
+
+/// Count the nibbles of an address which are letters, a through f, when written in hex
+fn letter_nibbles(address: Address) -> u8 {
+    let bytes = address.as_bytes();
+    bytes
+        .iter()
+        .map(|byte| ((byte >> 4) >= 10) as u8 + ((byte & 0x0f) >= 10) as u8)
+        .sum()
+}
+
+/// Which nibbles of an address are upper case in its EIP-55 checksummed form
+///
+/// The same casing as `to_checksum` without building the string, each letter is upper
+/// case when the matching nibble of the keccak256 of the lower case hex is 8 or more.
+///
+/// # Arguments
+/// * `address` - The address to check
+///
+/// # Returns
+/// * One flag per nibble, most significant first, never set for digits
+pub fn checksum_uppercase(address: Address) -> [bool; ADDRESS_NIBBLES] {
+    let mut lower = [0u8; ADDRESS_NIBBLES];
+    hex::encode_to_slice(address.as_bytes(), &mut lower).expect("40 hex digits for 20 bytes");
+    let hash = keccak256(lower);
+    let mut upper = [false; ADDRESS_NIBBLES];
+    for (i, c) in lower.iter().enumerate() {
+        let hash_nibble = if i % 2 == 0 {
+            hash[i / 2] >> 4
+        } else {
+            hash[i / 2] & 0x0f
+        };
+        upper[i] = c.is_ascii_alphabetic() && hash_nibble >= 8;
+    }
+    upper
+}
+
+/// Hex characters to find at the start of the checksummed form of an address, where
+/// the case of each letter matters, see `ScoreMode::ChecksumWord`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CasedWord {
+    /// ASCII hex digits, only the first `len` are used
+    chars: [u8; ADDRESS_NIBBLES],
+    len: u8,
+}
+
+impl CasedWord {
+    /// The number of characters of the word
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Whether the word has no characters, never true for a parsed word
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The length of the start of the word matched by the address, ignoring case,
+    /// i.e. by its nibbles alone
+    fn matched_ignoring_case(&self, address: Address) -> usize {
+        let bytes = address.as_bytes();
+        self.chars[..self.len()]
+            .iter()
+            .enumerate()
+            .take_while(|(i, c)| {
+                let byte = bytes[i / 2];
+                let nibble = if i % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+                (**c as char).to_digit(16) == Some(nibble as u32)
+            })
+            .count()
+    }
+
+    /// The length of the start of the word matched by the checksummed form of the address,
+    /// only hashing the address when a letter matched ignoring case
+    fn matched(&self, address: Address) -> usize {
+        let run = self.matched_ignoring_case(address);
+        if self.chars[..run].iter().all(|c| c.is_ascii_digit()) {
+            return run;
+        }
+        let upper = checksum_uppercase(address);
+        self.chars[..run]
+            .iter()
+            .zip(upper)
+            .take_while(|(c, upper)| c.is_ascii_uppercase() == *upper)
+            .count()
+    }
+}
+
+impl FromStr for CasedWord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() > ADDRESS_NIBBLES {
+            return Err(format!(
+                "invalid word {}, expected 1 to {} hex characters",
+                s, ADDRESS_NIBBLES
+            ));
+        }
+        if let Some(c) = s.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(format!("invalid word {}, {} is not a hex character", s, c));
+        }
+        let mut chars = [0u8; ADDRESS_NIBBLES];
+        chars[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(CasedWord {
+            chars,
+            len: s.len() as u8,
+        })
+    }
+}
+
+impl fmt::Display for CasedWord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // only ASCII hex digits are ever stored
+        write!(f, "{}", String::from_utf8_lossy(&self.chars[..self.len()]))
+    }
+}
+
+/// The distance between two addresses as numbers, whichever is larger
+///
+/// # Arguments
+/// * `address` - An address
+/// * `target` - The address to measure from
+///
+/// # Returns
+/// * `|address - target|`, subtracting the smaller from the larger so it can't overflow
+pub fn distance(address: Address, target: Address) -> U256 {
+    let (address, target) = (address_to_u256(address), address_to_u256(target));
+    if address > target {
+        address - target
+    } else {
+        target - address
+    }
+}
+
+/// Count the zero nibbles at the start of a distance between addresses, as if it were
+/// an address itself, so the distance 0 counts 40
+fn distance_zero_nibbles(distance: U256) -> u32 {
+    // the distance fits the low 160 of the 256 bits
+    distance.leading_zeros().saturating_sub(96) / 4
+}
+
+/// Count the zero nibbles at the end of an address
+///
+/// # Arguments
+/// * `address` - The address to count in
+///
+/// # Returns
+/// * The number of trailing zero nibbles, 0 through 40
+pub fn trailing_zero_nibbles(address: Address) -> u8 {
+    let mut nibbles = 0;
+    for byte in address.as_bytes().iter().rev() {
+        if *byte != 0 {
+            if *byte & 0x0f == 0 {
+                nibbles += 1;
+            }
+            break;
+        }
+        nibbles += 2;
+    }
+    nibbles
+}
+
+/// Parse the value of `--score`
+///
+/// # Arguments
+/// * `s` - One of `ScoreMode::NAMES`
+///
+/// # Returns
+/// * The score mode, `--leading-nibble` selects the nibble modes
+pub fn parse_score(s: &str) -> Result<ScoreMode, String> {
+    match s {
+        "leading-zeros" => Ok(ScoreMode::LeadingZeros),
+        "total-zero-bytes" => Ok(ScoreMode::TotalZeroBytes),
+        "trailing-then-leading" => Ok(ScoreMode::TrailingThenLeading),
+        "checksum-uppercase" => Ok(ScoreMode::ChecksumUppercase),
+        _ => Err(format!(
+            "unknown score {}, expected one of {}",
+            s,
+            ScoreMode::NAMES.join(", ")
+        )),
+    }
+}
+
+/// Measure the run of a nibble at the start of an address
+///
+/// # Arguments
+/// * `address` - The address to measure
+/// * `nibble` - The nibble to count, or `None` to count whichever nibble comes first
+///
+/// # Returns
+/// * The nibble counted and the length of its run
+pub fn leading_nibble_run(address: Address, nibble: Option<u8>) -> (u8, u8) {
+    let bytes = address.as_bytes();
+    let target = nibble.unwrap_or(bytes[0] >> 4);
+
+    let mut run = 0;
+    for byte in bytes {
+        for n in [byte >> 4, byte & 0x0f] {
+            if n != target {
+                return (target, run);
+            }
+            run += 1;
+        }
+    }
+    (target, run)
+}
+
+/// Parse the value of `--maximize-byte`
+///
+/// # Arguments
+/// * `s` - Two hex digits, with or without a 0x or 0X prefix
+///
+/// # Returns
+/// * The byte
+pub fn parse_byte(s: &str) -> Result<u8, String> {
+    let digits = strip_hex_prefix(s);
+    match u8::from_str_radix(digits, 16) {
+        Ok(byte) if digits.len() == 2 => Ok(byte),
+        _ => Err(format!(
+            "invalid byte {}, expected two hex digits, e.g. ff",
+            s
+        )),
+    }
+}
+
+/// Parse the value of `--leading-nibble`
+///
+/// # Arguments
+/// * `s` - A single hex digit, or "any"
+///
+/// # Returns
+/// * The nibble, or `None` for any nibble
+pub fn parse_leading_nibble(s: &str) -> Result<Option<u8>, String> {
+    if s == "any" {
+        return Ok(None);
+    }
+    match u8::from_str_radix(s, 16) {
+        Ok(nibble) if s.len() == 1 => Ok(Some(nibble)),
+        _ => Err(format!("invalid nibble {}, expected 0-f or any", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    #[test]
+    fn checksum_word_matches_the_case_too() {
+        let found = |address: &str| AddressSalt {
+            address: address.parse().unwrap(),
+            salt_n: U256::zero(),
+        };
+        // EIP-1014 example 5, and an address with the same nibbles checksummed
+        // 0x60F3F640a8508FC6..., which matches the word ignoring case only up to the F
+        let example = found("0x60f3f640a8508fC6a86d45DF051962668E1e8AC7");
+        let recased = found("0x60f3f640a8508fc6000000000000000000000001");
+        let mode = ScoreMode::ChecksumWord("60f3f640a8508fC6".parse().unwrap());
+        assert_eq!(mode.score(example.address), 16);
+        assert_eq!(mode.score(recased.address), 2);
+        assert_eq!(mode.score(Address::zero()), 0);
+
+        for tie_break in [TieBreak::LowestAddress, TieBreak::LowestSalt] {
+            for (a, b) in [(example, recased), (recased, example), (example, example)] {
+                assert_eq!(
+                    mode.is_better_scored(&a, &b, mode.score(b.address), tie_break),
+                    mode.is_better(&a, &b, tie_break)
+                );
+            }
+        }
+        assert!(mode.is_better_scored(&example, &recased, 2, TieBreak::default()));
+        assert!(!mode.is_better_scored(&recased, &example, 16, TieBreak::default()));
+    }
+
+    #[test]
+    fn sort_best_first_is_a_total_order() {
+        let found = |address: u64, salt: u64| AddressSalt {
+            address: Address::from_low_u64_be(address),
+            salt_n: U256::from(salt),
+        };
+        // the last three tie on 36 zeros, two of them on the salt too
+        let expected = [
+            found(0x10, 9),
+            found(0x5678, 3),
+            found(0x9999, 3),
+            found(0x1234, 5),
+        ];
+        let mut rng = StdRng::seed_from_u64(188);
+        for _ in 0..8 {
+            let mut shuffled = expected.to_vec();
+            shuffled.shuffle(&mut rng);
+            ScoreMode::LeadingZeros.sort_best_first(&mut shuffled);
+            assert_eq!(shuffled, expected);
+        }
+    }
+
+    #[test]
+    fn expected_best_score_inverts_expected_attempts() {
+        for mode in [ScoreMode::LeadingZeros, ScoreMode::LeadingNibble(Some(8))] {
+            for target in 1..=12u8 {
+                let attempts = mode.expected_attempts(target) as u128;
+                assert_eq!(
+                    mode.expected_best_score_for_attempts(attempts),
+                    target as f64
+                );
+                // a quarter of the way to the next in attempts is half way on the log scale
+                let between = mode.expected_best_score_for_attempts(attempts * 4);
+                assert!(
+                    (between - (target as f64 + 0.5)).abs() < 1e-9,
+                    "{}",
+                    between
+                );
+            }
+        }
+        let mode = ScoreMode::TotalZeroBytes;
+        let mut previous = 0.0;
+        for target in 2..=5u8 {
+            let attempts = mode.expected_attempts(target).round() as u128;
+            let score = mode.expected_best_score_for_attempts(attempts);
+            assert!(
+                (score - target as f64).abs() < 1e-2,
+                "{} for {}",
+                score,
+                target
+            );
+            assert!(score > previous);
+            previous = score;
+        }
+    }
+
+    #[test]
+    fn match_mask_marks_the_nibbles_scored() {
+        let address: Address = "0x0000ff12000000ff0000000000000000000000ff"
+            .parse()
+            .unwrap();
+        let marked = |mode: ScoreMode| -> Vec<usize> {
+            let mask = mode.match_mask(address);
+            (0..ADDRESS_NIBBLES).filter(|i| mask[*i]).collect()
+        };
+        assert_eq!(marked(ScoreMode::LeadingZeros), vec![0, 1, 2, 3]);
+        assert_eq!(marked(ScoreMode::LeadingNibble(None)), vec![0, 1, 2, 3]);
+        // whole bytes only, both nibbles of each
+        assert_eq!(
+            marked(ScoreMode::ByteCount(0xff)),
+            vec![4, 5, 14, 15, 38, 39]
+        );
+        let zero_bytes = marked(ScoreMode::TotalZeroBytes);
+        assert_eq!(zero_bytes.len(), 2 * 16);
+        assert!(zero_bytes.contains(&8) && !zero_bytes.contains(&4) && !zero_bytes.contains(&6));
+        assert_eq!(marked(ScoreMode::TrailingThenLeading), vec![0, 1, 2, 3]);
+        // the first nibble of the target differs, so nothing is shared
+        assert_eq!(
+            marked(ScoreMode::ClosestTo(Address::repeat_byte(0x11))),
+            vec![]
+        );
+        let target: Address = "0x0000ff13000000000000000000000000000000ff"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            marked(ScoreMode::ClosestTo(target)),
+            vec![0, 1, 2, 3, 4, 5, 6]
+        );
+    }
+
+    #[test]
+    fn leading_nibble_run_on_crafted_addresses() {
+        let address = |hex: &str| -> Address { hex.parse().unwrap() };
+        let cases = [
+            ("0x8888881234567890123456789012345678901234", None, (8, 6)),
+            (
+                "0x8888881234567890123456789012345678901234",
+                Some(8),
+                (8, 6),
+            ),
+            (
+                "0x8888881234567890123456789012345678901234",
+                Some(0),
+                (0, 0),
+            ),
+            // a run ending in the low nibble of a byte
+            ("0xfff0000000000000000000000000000000000000", None, (0xf, 3)),
+            ("0x0000000000000000000000000000000000000001", None, (0, 39)),
+            (
+                "0x0000000000000000000000000000000000000001",
+                Some(0),
+                (0, 39),
+            ),
+            ("0x1111111111111111111111111111111111111111", None, (1, 40)),
+        ];
+        for (hex, nibble, expected) in cases {
+            assert_eq!(
+                leading_nibble_run(address(hex), nibble),
+                expected,
+                "{} {:?}",
+                hex,
+                nibble
+            );
+        }
+    }
+
+    #[test]
+    fn zero_bytes_count_interior_zeros_and_not_zero_nibbles() {
+        let address = |hex: &str| -> Address { hex.parse().unwrap() };
+        let cases = [
+            ("0x1111111111111111111111111111111111111111", 0),
+            // zero nibbles which don't make up a byte count for nothing
+            ("0x1011010110110101101101011011010110110101", 0),
+            ("0x1100110011001100110011001100110011001100", 10),
+            ("0x1111110000001111111111111111110011000011", 6),
+            ("0x0011111111111111111111111111111111111100", 2),
+            ("0x0000000000000000000000000000000000000000", 20),
+        ];
+        for (hex, expected) in cases {
+            assert_eq!(zero_bytes(address(hex)), expected, "{}", hex);
+            assert_eq!(
+                ScoreMode::TotalZeroBytes.score(address(hex)),
+                expected as u32,
+                "{}",
+                hex
+            );
+        }
+        assert_eq!(
+            ScoreMode::TotalZeroBytes.describe(address(cases[3].0)),
+            "6 zero bytes"
+        );
+    }
+
+    #[test]
+    fn parse_byte_takes_either_prefix() {
+        for s in ["ff", "0xff", "0XFF", " 0xFf "] {
+            assert_eq!(parse_byte(s), Ok(0xff), "{}", s);
+        }
+        for s in ["f", "0x", "0xfff", "gg"] {
+            assert!(parse_byte(s).is_err(), "{}", s);
+        }
+    }
+}
@@ -1,69 +1,1207 @@
-use std::sync::{Arc, Mutex};
-
-use ethers_core::{
-    types::{Address, Bytes, U256},
-    utils::get_create2_address_from_hash,
+use std::{
+    collections::HashSet,
+    fmt,
+    panic::{catch_unwind, AssertUnwindSafe},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use num_format::{Locale, ToFormattedString};
+use ethers_core::types::{Address, Bytes, U256};
+
 use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
 
-use crate::utils::{bytes32, count_leading_zeroes, fmt_dms};
+use crate::derive::{AddressDeriver, Create2Deriver};
+use crate::error::SearchError;
+use crate::event::{emit, BestRecord, Event, EventFormat, EventPipe};
+use crate::hook::run_on_success;
+use crate::pressure::{PressureLimits, PressureSource, Throttle};
+use crate::sample::Reservoir;
+use crate::score::{ScoreMode, TieBreak};
+use crate::sketch::DurationSketch;
+use crate::utils::{bytes32, fmt_dms, leading_zero_nibbles, ADDRESS_NIBBLES};
 
-#[derive(Copy, Clone)]
+/// One bucket per possible leading-zero count, 0 through 40 nibbles
+pub const HISTOGRAM_BUCKETS: usize = ADDRESS_NIBBLES + 1;
+
+/// When profiling, time one iteration of the inner loop out of this many
+/// timing every iteration would cost more than the work being measured
+const PROFILE_SAMPLE_INTERVAL: u128 = 1024;
+
+/// When stopping at a target, check whether another round reached it every this many
+/// iterations, a relaxed atomic load is cheap next to 65k hashes
+const STOP_CHECK_INTERVAL: u128 = 1 << 16;
+
+/// A round adds its attempts to the searcher's running count every this many iterations,
+/// so long rounds show progress before they finish, often enough for a progress line every
+/// second yet a relaxed atomic add per 65k hashes doesn't show in the rate
+const PROGRESS_INTERVAL: u128 = 1 << 16;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct AddressSalt {
     pub address: Address,
     // leading_zeros: u8,
     pub salt_n: U256,
 }
 
+/// A finished search, what `Searcher::continue_from` needs to pick up after it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    /// The parameters of the search
+    pub params: SearchParams,
+    /// The best address found
+    pub best: AddressSalt,
+}
+
+impl SearchResult {
+    /// The first salt past those the search scanned, a search bounded by `max_attempts`
+    /// ends partway through its last round
+    pub fn next_salt_n(&self) -> U256 {
+        return self.params.initial_salt_n + self.params.span();
+    }
+}
+
+/// Which address a search stopping at its target returns
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ThresholdReturn {
+    /// The first address to meet the target, its round stops right there
+    First,
+    /// The best address of the round which met the target, which finishes scanning
+    /// while the other rounds stop
+    #[default]
+    RoundBest,
+}
+
+impl ThresholdReturn {
+    /// The names accepted by `from_str`
+    pub const NAMES: [&'static str; 2] = ["first", "round-best"];
+}
+
+impl FromStr for ThresholdReturn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(ThresholdReturn::First),
+            "round-best" => Ok(ThresholdReturn::RoundBest),
+            _ => Err(format!(
+                "unknown threshold return {}, expected one of {}",
+                s,
+                ThresholdReturn::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ThresholdReturn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdReturn::First => write!(f, "first"),
+            ThresholdReturn::RoundBest => write!(f, "round-best"),
+        }
+    }
+}
+
+/// What to find: the deployment whose addresses are searched
+#[derive(Copy, Clone)]
+pub struct Target {
+    /// The CREATE2 factory, addresses are derived from it
+    pub factory: Address,
+    pub init_code_hash: U256,
+}
+
+/// What counts as a good address, and when one is good enough to stop
+#[derive(Copy, Clone, Default)]
+pub struct ScoreConfig {
+    /// What makes an address better than another
+    pub score_mode: ScoreMode,
+    /// Stop the search once the best address scores at least this much
+    pub target_score: Option<u32>,
+    /// Which address is returned once `target_score` is met
+    pub threshold_return: ThresholdReturn,
+    /// How two addresses with the same score are ordered
+    pub tie_break: TieBreak,
+}
+
+/// The expected number of attempts to find an address meeting the target of a score config,
+/// to budget a search before running it
+///
+/// This is the estimate of the score mode, see `ScoreMode::expected_attempts`, the inverse
+/// of the product of the chances of each nibble or byte scored: 16^n for n leading zeros or a
+/// prefix of n of one nibble, and for a checksum word 16 for each digit times 32 for each
+/// letter, whose case has to match too.
+///
+/// # Arguments
+/// * `score_config` - The score mode and target score
+///
+/// # Returns
+/// * The expected attempts, 1 without a target score as the first address meets it
+pub fn estimate_attempts(score_config: &ScoreConfig) -> f64 {
+    match score_config.target_score {
+        Some(target_score) => score_config
+            .score_mode
+            .expected_attempts(target_score.min(u8::MAX as u32) as u8),
+        None => 1.0,
+    }
+}
+
+/// How to search: the salts scanned, split into rounds, and what is recorded on the way
+///
+/// The number of threads is the searcher's, see `Searcher::new`.
 #[derive(Copy, Clone)]
+pub struct SearchStrategy {
+    pub initial_salt_n: U256,
+    pub round_size: u128,
+    pub num_rounds: u128,
+    /// Scan exactly this many addresses, the last round is shortened to fit
+    pub max_attempts: Option<u128>,
+    /// Distance between consecutive salts, at least 1
+    pub salt_step: u64,
+    /// Count how many addresses had each number of leading zeros
+    pub histogram: bool,
+    /// Sample how long each phase of the inner loop takes
+    pub profile: bool,
+    /// Keep a uniform random sample of this many addresses, regardless of score
+    pub sample_size: Option<usize>,
+    /// How progress and results are logged
+    pub event_format: EventFormat,
+}
+
+/// Everything a search needs in one flat struct, `Target`, `ScoreConfig` and
+/// `SearchStrategy` together, which is what the rounds copy around
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct SearchParams {
-    pub deployer: Address,
+    /// The CREATE2 factory, addresses are derived from it
+    pub factory: Address,
     pub initial_salt_n: U256,
     pub init_code_hash: U256,
     pub round_size: u128,
     pub num_rounds: u128,
+    /// Scan exactly this many addresses, the last round is shortened to fit
+    pub max_attempts: Option<u128>,
+    /// Distance between consecutive salts, at least 1
+    pub salt_step: u64,
+    /// Count how many addresses had each number of leading zeros
+    pub histogram: bool,
+    /// Sample how long each phase of the inner loop takes
+    pub profile: bool,
+    /// Keep a uniform random sample of this many addresses, regardless of score
+    pub sample_size: Option<usize>,
+    /// What makes an address better than another
+    pub score_mode: ScoreMode,
+    /// Stop the search once the best address scores at least this much
+    pub target_score: Option<u32>,
+    /// Which address is returned once `target_score` is met
+    pub threshold_return: ThresholdReturn,
+    /// How two addresses with the same score are ordered
+    pub tie_break: TieBreak,
+    /// How progress and results are logged
+    pub event_format: EventFormat,
+}
+
+impl SearchParams {
+    /// Combine what to find, what counts as good and how to search
+    ///
+    /// # Arguments
+    /// * `target` - The deployment whose addresses are searched
+    /// * `score` - What counts as a good address
+    /// * `strategy` - The salts scanned and how
+    pub fn new(target: Target, score: ScoreConfig, strategy: SearchStrategy) -> Self {
+        Self {
+            factory: target.factory,
+            init_code_hash: target.init_code_hash,
+            score_mode: score.score_mode,
+            target_score: score.target_score,
+            threshold_return: score.threshold_return,
+            tie_break: score.tie_break,
+            initial_salt_n: strategy.initial_salt_n,
+            round_size: strategy.round_size,
+            num_rounds: strategy.num_rounds,
+            max_attempts: strategy.max_attempts,
+            salt_step: strategy.salt_step,
+            histogram: strategy.histogram,
+            profile: strategy.profile,
+            sample_size: strategy.sample_size,
+            event_format: strategy.event_format,
+        }
+    }
+
+    /// The deployment whose addresses are searched
+    pub fn target(&self) -> Target {
+        Target {
+            factory: self.factory,
+            init_code_hash: self.init_code_hash,
+        }
+    }
+
+    /// What counts as a good address
+    pub fn score_config(&self) -> ScoreConfig {
+        ScoreConfig {
+            score_mode: self.score_mode,
+            target_score: self.target_score,
+            threshold_return: self.threshold_return,
+            tie_break: self.tie_break,
+        }
+    }
+
+    /// The salts scanned and how
+    pub fn strategy(&self) -> SearchStrategy {
+        SearchStrategy {
+            initial_salt_n: self.initial_salt_n,
+            round_size: self.round_size,
+            num_rounds: self.num_rounds,
+            max_attempts: self.max_attempts,
+            salt_step: self.salt_step,
+            histogram: self.histogram,
+            profile: self.profile,
+            sample_size: self.sample_size,
+            event_format: self.event_format,
+        }
+    }
+
+    /// The distance from `initial_salt_n` to the end of the search, the salts it covers
+    /// including those skipped by a `salt_step` above 1
+    pub fn span(&self) -> U256 {
+        let attempts = self
+            .max_attempts
+            .unwrap_or(self.round_size * self.num_rounds);
+        U256::from(attempts) * U256::from(self.salt_step)
+    }
+
+    /// Check the rounds tile the range from `initial_salt_n` without overlap or gap
+    ///
+    /// Round `r` starts at `initial_salt_n + round_size * salt_step * r`, so the rounds are
+    /// only disjoint and contiguous when every round but the last is full, `max_attempts`
+    /// ends inside the last round, and the range doesn't wrap past the largest salt.
+    ///
+    /// # Returns
+    /// * An error describing the first inconsistency found
+    pub fn check_rounds(&self) -> Result<(), SearchError> {
+        if self.round_size == 0 || self.num_rounds == 0 {
+            return Err(SearchError::InvalidArgument(
+                "rounds must scan at least one salt each, and there must be at least one round"
+                    .to_string(),
+            ));
+        }
+        if self.salt_step == 0 {
+            return Err(SearchError::InvalidArgument(
+                "a salt step of 0 scans the same salt over and over".to_string(),
+            ));
+        }
+        let attempts = self
+            .round_size
+            .checked_mul(self.num_rounds)
+            .ok_or_else(|| {
+                SearchError::InvalidArgument(format!(
+                    "{} rounds of {} salts overflow the attempt count",
+                    self.num_rounds, self.round_size
+                ))
+            })?;
+        if let Some(max_attempts) = self.max_attempts {
+            let full_rounds = self.round_size * (self.num_rounds - 1);
+            if max_attempts <= full_rounds || max_attempts > attempts {
+                return Err(SearchError::InvalidArgument(format!(
+                    "{} attempts don't end in the last of {} rounds of {} salts, \
+                    the rounds would overlap or leave a gap",
+                    max_attempts, self.num_rounds, self.round_size
+                )));
+            }
+        }
+        // the first salt past the last round, if it wraps the rounds overlap the first ones
+        let last_salt = U256::from(attempts)
+            .checked_mul(U256::from(self.salt_step))
+            .and_then(|span| self.initial_salt_n.checked_add(span - self.salt_step));
+        if last_salt.is_none() {
+            return Err(SearchError::InvalidArgument(format!(
+                "{} rounds of {} salts from 0x{} run past the largest salt and wrap around",
+                self.num_rounds,
+                self.round_size,
+                hex::encode(bytes32(self.initial_salt_n))
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl From<(Target, ScoreConfig, SearchStrategy)> for SearchParams {
+    fn from((target, score, strategy): (Target, ScoreConfig, SearchStrategy)) -> Self {
+        Self::new(target, score, strategy)
+    }
+}
+
+/// Time spent in each phase of the search, see `SearchParams::profile`
+#[derive(Copy, Clone, Default)]
+pub struct PhaseTimings {
+    /// Sampled time spent advancing the salt
+    pub salt_increment: Duration,
+    /// Sampled time spent hashing to derive the address
+    pub address_derivation: Duration,
+    /// Sampled time spent comparing against the round best
+    pub comparison: Duration,
+    /// Number of sampled iterations
+    pub samples: u128,
+    /// Total (not sampled) time spent merging round results into the global best
+    pub merge: Duration,
+}
+
+impl PhaseTimings {
+    fn add(&mut self, other: &PhaseTimings) {
+        self.salt_increment += other.salt_increment;
+        self.address_derivation += other.address_derivation;
+        self.comparison += other.comparison;
+        self.samples += other.samples;
+        self.merge += other.merge;
+    }
+}
+
+/// Statistics collected locally by a round and merged into the searcher once it's done
+/// so the hot loop never takes a lock
+#[derive(Clone)]
+pub struct RoundStats {
+    /// Number of addresses computed, less than round_size if the round stopped early
+    pub attempts: u128,
+    pub histogram: [u128; HISTOGRAM_BUCKETS],
+    pub timings: PhaseTimings,
+    /// Only filled when the search runs with `sample_size` set
+    pub sample: Reservoir,
+    /// The wall-clock time of each round, to spot stragglers
+    pub round_durations: DurationSketch,
+    /// Attempts already added to the running count of the searcher while the round ran,
+    /// taken back off when the round's attempts are added to the total
+    pub reported: u128,
+}
+
+impl Default for RoundStats {
+    fn default() -> Self {
+        Self {
+            attempts: 0,
+            histogram: [0; HISTOGRAM_BUCKETS],
+            timings: PhaseTimings::default(),
+            sample: Reservoir::default(),
+            round_durations: DurationSketch::default(),
+            reported: 0,
+        }
+    }
+}
+
+/// Number of attempts at creating the thread pool before giving up
+const THREAD_POOL_RETRIES: u32 = 3;
+
+/// Delay before the first retry, doubled after each failed attempt
+const THREAD_POOL_BACKOFF_MS: u64 = 100;
+
+/// How often a round held back by the ramp-up or the throttle checks whether it may start
+const RAMP_UP_POLL_MS: u64 = 10;
+
+/// How often the pressure on the machine is read, see `Searcher::set_pressure_throttle`
+const PRESSURE_POLL: Duration = Duration::from_secs(5);
+
+/// Tells when a counter has stopped moving for too long, see `Searcher::set_stall_warning`
+pub struct StallDetector {
+    attempts: u128,
+    /// When the counter last moved
+    since: Instant,
+    /// Whether the current stall was reported already
+    reported: bool,
+}
+
+impl StallDetector {
+    /// Start watching a counter
+    ///
+    /// # Arguments
+    /// * `attempts` - The current value of the counter
+    /// * `now` - The current time
+    pub fn new(attempts: u128, now: Instant) -> Self {
+        Self {
+            attempts,
+            since: now,
+            reported: false,
+        }
+    }
+
+    /// Record the value of the counter
+    ///
+    /// # Arguments
+    /// * `attempts` - The value of the counter
+    /// * `now` - The time it was read
+    /// * `limit` - How long the counter may stand still
+    ///
+    /// # Returns
+    /// * How long it has stood still, the first time that reaches `limit` since it last
+    ///   moved, `None` otherwise
+    pub fn observe(&mut self, attempts: u128, now: Instant, limit: Duration) -> Option<Duration> {
+        if attempts != self.attempts {
+            *self = Self::new(attempts, now);
+            return None;
+        }
+        let still = now.saturating_duration_since(self.since);
+        if still < limit || self.reported {
+            return None;
+        }
+        self.reported = true;
+        Some(still)
+    }
 }
 
-pub struct Searcher {
+pub struct Searcher<D = Create2Deriver> {
     best: Arc<Mutex<Option<AddressSalt>>>,
     total_attempts: Arc<Mutex<u128>>,
     total_rounds: Arc<Mutex<u128>>,
-    thread_pool: ThreadPool,
+    stats: Arc<Mutex<RoundStats>>,
+    target_reached: Arc<AtomicBool>,
+    /// Addresses never reported, even if they score well
+    denied: HashSet<Address>,
+    /// Salt ranges `[from, to)` searched before, passed over, sorted by `from`
+    skip: Vec<(U256, U256)>,
+    /// Scores to report when the best first reaches them, ascending
+    milestones: Vec<u32>,
+    /// The index of the next milestone to reach
+    next_milestone: Arc<Mutex<usize>>,
+    /// Shell command run at each milestone, see `hook::run_on_success`
+    on_milestone: Option<String>,
+    /// Turns salts into addresses, CREATE2 unless testing the search itself
+    deriver: D,
+    /// Times a round which panicked is rerun before it is skipped
+    round_retries: u32,
+    /// Rounds skipped after panicking on every try, their salts weren't all searched
+    failed_rounds: Arc<AtomicU64>,
+    /// The number of times the best score went up in the last search
+    score_improvements: AtomicU64,
+    /// Also written each new best, for a dashboard
+    event_pipe: Option<EventPipe>,
+    /// Replace the salts in events with a placeholder, see `Event::masked`
+    mask_salts: bool,
+    /// The time over which the rounds running at once grow from one to one per thread
+    ramp_up: Option<Duration>,
+    /// When the first search started, the ramp-up isn't repeated by later searches
+    ramp_up_start: OnceLock<Instant>,
+    /// Rounds started under the ramp-up or the pressure throttle and still running
+    gated_rounds: AtomicUsize,
+    /// Reads the pressure on the machine, and decides the rounds allowed at once under it
+    pressure: Option<(Box<dyn PressureSource>, Mutex<Throttle>)>,
+    /// The rounds allowed to run at once by the pressure throttle
+    thread_limit: AtomicUsize,
+    /// Warn when no attempts are counted for this long during a search
+    stall_warning: Option<Duration>,
+    /// Attempts of the rounds still running, see `PROGRESS_INTERVAL`
+    running_attempts: AtomicU64,
+    /// Log the attempts this often during a search, rounds running included
+    progress_interval: Option<Duration>,
+    /// The pool the rounds run on, rayon's global pool if `None`
+    thread_pool: Option<ThreadPool>,
 }
 
 impl Searcher {
-    pub fn new(num_threads: usize) -> Self {
+    /// Create a searcher deriving CREATE2 addresses, with its own thread pool
+    ///
+    /// # Arguments
+    /// * `num_threads` - The number of worker threads, must be at least 1
+    /// * `pin_threads` - Pin each worker to a distinct core where the platform supports it
+    pub fn new(num_threads: usize, pin_threads: bool) -> Result<Self, SearchError> {
+        Self::with_deriver(num_threads, pin_threads, Create2Deriver::default())
+    }
+}
+
+impl<D: AddressDeriver> Searcher<D> {
+    /// Create a searcher with its own thread pool and a custom address derivation
+    ///
+    /// # Arguments
+    /// * `num_threads` - The number of worker threads, must be at least 1
+    /// * `pin_threads` - Pin each worker to a distinct core where the platform supports it
+    /// * `deriver` - Turns salts into addresses, e.g. `SaltDeriver` to test without hashing
+    pub fn with_deriver(
+        num_threads: usize,
+        pin_threads: bool,
+        deriver: D,
+    ) -> Result<Self, SearchError> {
+        let thread_pool = Self::build_thread_pool(num_threads, pin_threads)?;
+        return Ok(Self::with_pool(Some(thread_pool), deriver));
+    }
+
+    /// Create a searcher running on rayon's global pool instead of its own, to share the
+    /// threads of an application which also uses rayon
+    ///
+    /// The global pool has as many threads as `RAYON_NUM_THREADS` or the cores, unless the
+    /// application built it otherwise, and its threads are not pinned. The searcher's
+    /// rounds compete with the application's other rayon work.
+    ///
+    /// # Arguments
+    /// * `deriver` - Turns salts into addresses, e.g. `Create2Deriver::default()`
+    pub fn with_global_pool(deriver: D) -> Self {
+        return Self::with_pool(None, deriver);
+    }
+
+    fn with_pool(thread_pool: Option<ThreadPool>, deriver: D) -> Self {
         let best = Arc::new(Mutex::new(None));
         let total_attempts = Arc::new(Mutex::new(0));
         let total_rounds = Arc::new(Mutex::new(0));
-
-        // Create a custom thread pool with the specified number of threads
-        let thread_pool = ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build()
-            .expect("Failed to create thread pool");
+        let stats = Arc::new(Mutex::new(RoundStats::default()));
+        let target_reached = Arc::new(AtomicBool::new(false));
 
         Self {
             best,
             total_attempts,
             total_rounds,
+            stats,
+            target_reached,
+            denied: HashSet::new(),
+            skip: Vec::new(),
+            milestones: Vec::new(),
+            next_milestone: Arc::new(Mutex::new(0)),
+            on_milestone: None,
+            deriver,
+            round_retries: 0,
+            failed_rounds: Arc::new(AtomicU64::new(0)),
+            score_improvements: AtomicU64::new(0),
+            event_pipe: None,
+            mask_salts: false,
+            ramp_up: None,
+            ramp_up_start: OnceLock::new(),
+            gated_rounds: AtomicUsize::new(0),
+            pressure: None,
+            thread_limit: AtomicUsize::new(usize::MAX),
+            stall_warning: None,
+            running_attempts: AtomicU64::new(0),
+            progress_interval: None,
             thread_pool,
         }
     }
 
-    pub fn search(&self, params: SearchParams) -> AddressSalt {
+    /// Run `op` on the searcher's pool, or the global pool if it has none
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.install(op),
+            // like `install`, moves onto a thread of the global pool if not on one
+            None => rayon::scope(|_| op()),
+        }
+    }
+
+    /// The number of threads the rounds run on
+    pub fn num_threads(&self) -> usize {
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.current_num_threads(),
+            None => rayon::current_num_threads(),
+        }
+    }
+
+    /// Create a custom thread pool with the specified number of threads
+    ///
+    /// Pool creation can fail transiently in constrained environments (e.g. when the
+    /// process is close to its thread limit), so it is retried with exponential backoff.
+    ///
+    /// # Arguments
+    /// * `num_threads` - The number of threads, must be at least 1
+    /// * `pin_threads` - Pin each thread to a distinct core, a no-op with a warning
+    ///   on platforms without thread affinity
+    ///
+    /// # Returns
+    /// * The thread pool, or the error of the last failed attempt
+    fn build_thread_pool(num_threads: usize, pin_threads: bool) -> Result<ThreadPool, SearchError> {
+        // rayon treats 0 as "pick a default", which is never what the caller asked for
+        if num_threads == 0 {
+            return Err(SearchError::InvalidThreadCount(num_threads));
+        }
+
+        let core_ids = if pin_threads {
+            Self::core_ids(num_threads)
+        } else {
+            None
+        };
+
+        let backoff = Duration::from_millis(THREAD_POOL_BACKOFF_MS);
+        retry_with_backoff(THREAD_POOL_RETRIES, backoff, "create thread pool", || {
+            let mut builder = ThreadPoolBuilder::new().num_threads(num_threads);
+            if let Some(core_ids) = core_ids.clone() {
+                builder = builder.start_handler(move |index| {
+                    let core_id = core_ids[index % core_ids.len()];
+                    if !core_affinity::set_for_current(core_id) {
+                        eprintln!(
+                            "Warning: failed to pin thread {} to core {}",
+                            index, core_id.id
+                        );
+                    }
+                });
+            }
+            builder.build()
+        })
+        .map_err(SearchError::ThreadPool)
+    }
+
+    /// The cores to pin worker threads to
+    ///
+    /// # Arguments
+    /// * `num_threads` - The number of worker threads, to warn if there are more than cores
+    ///
+    /// # Returns
+    /// * The core ids, or `None` if the platform doesn't support thread affinity
+    fn core_ids(num_threads: usize) -> Option<Vec<core_affinity::CoreId>> {
+        match core_affinity::get_core_ids() {
+            Some(core_ids) if !core_ids.is_empty() => {
+                if num_threads > core_ids.len() {
+                    eprintln!(
+                        "Warning: {} threads for {} cores, some cores will run several threads",
+                        num_threads,
+                        core_ids.len()
+                    );
+                }
+                Some(core_ids)
+            }
+            _ => {
+                eprintln!(
+                    "Warning: thread affinity is not supported here, threads won't be pinned"
+                );
+                None
+            }
+        }
+    }
+
+    /// Search the salts of the strategy for the best address of the target
+    ///
+    /// # Arguments
+    /// * `params` - A `SearchParams`, or a `(Target, ScoreConfig, SearchStrategy)` tuple
+    ///
+    /// # Returns
+    /// * The best address found, or the seeded best if nothing beat it, `None` if there
+    ///   is neither as every round was skipped as already searched or failed
+    ///
+    /// # Panics
+    /// * If the rounds overlap or leave a gap, see `SearchParams::check_rounds`, as the
+    ///   result would silently not cover the salts asked for
+    pub fn search(&self, params: impl Into<SearchParams>) -> Option<AddressSalt> {
+        let params: SearchParams = params.into();
+        if let Err(e) = params.check_rounds() {
+            panic!("{}", e);
+        }
         let start_time = std::time::Instant::now();
+        let ramp_up_start = *self.ramp_up_start.get_or_init(|| start_time);
+        emit(
+            &self.logged(Event::Started {
+                factory: params.factory,
+                init_code_hash: format!("0x{}", hex::encode(bytes32(params.init_code_hash))),
+                initial_salt: format!("0x{}", hex::encode(bytes32(params.initial_salt_n))),
+                round_size: params.round_size,
+                num_rounds: params.num_rounds,
+            }),
+            params.event_format,
+        );
 
-        self.thread_pool.install(|| {
-            (0..params.num_rounds).into_par_iter().for_each(|round| {
-                self.search_round(&params, round, start_time);
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            let done = &done;
+            let watchdog = self.stall_warning.map(|stall_warning| {
+                scope.spawn(move || self.watch_for_stall(stall_warning, done))
             });
+            let reporter = self.progress_interval.map(|interval| {
+                let event_format = params.event_format;
+                scope.spawn(move || self.report_progress(interval, start_time, event_format, done))
+            });
+            let throttle = self
+                .pressure
+                .as_ref()
+                .map(|_| scope.spawn(move || self.throttle_on_pressure(done)));
+            self.install(|| {
+                (0..params.num_rounds).into_par_iter().for_each(|round| {
+                    // skip the remaining rounds once the target is reached
+                    if self.target_reached.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let gated = self.pressure.is_some()
+                        || self
+                            .ramp_up
+                            .is_some_and(|ramp_up| ramp_up_start.elapsed() < ramp_up);
+                    if gated {
+                        self.wait_for_slot(ramp_up_start);
+                    }
+                    self.search_round_or_skip(&params, round, start_time);
+                    if gated {
+                        self.gated_rounds.fetch_sub(1, Ordering::Relaxed);
+                    }
+                });
+            });
+            done.store(true, Ordering::Relaxed);
+            for monitor in [watchdog, reporter, throttle].into_iter().flatten() {
+                monitor.thread().unpark();
+            }
         });
 
-        let the_best = self.best.lock().unwrap();
-        return the_best.unwrap();
+        let the_best = *self.best.lock().unwrap();
+        if let Some(best) = the_best {
+            emit(
+                &self.logged(Event::Finished {
+                    rounds: *self.total_rounds.lock().unwrap(),
+                    attempts: *self.total_attempts.lock().unwrap(),
+                    elapsed_ms: start_time.elapsed().as_millis(),
+                    improvements: self.score_improvements(),
+                    best: BestRecord::new(&best, params.score_mode),
+                }),
+                params.event_format,
+            );
+        }
+        return the_best;
+    }
+
+    /// Search the rounds right after a finished search, starting from its best
+    /// so the result never regresses and no salt is scanned twice
+    ///
+    /// # Arguments
+    /// * `result` - The finished search
+    /// * `additional_rounds` - The number of rounds to search
+    ///
+    /// # Returns
+    /// * This search, to continue from again, with the best address found so far
+    pub fn continue_from(&self, result: &SearchResult, additional_rounds: u128) -> SearchResult {
+        let params = SearchParams {
+            initial_salt_n: result.next_salt_n(),
+            num_rounds: additional_rounds,
+            max_attempts: None,
+            ..result.params
+        };
+
+        self.reset();
+        self.seed_best(result.best);
+        // seeded, so there is always a best
+        let best = self.search(params).unwrap_or(result.best);
+        return SearchResult { params, best };
+    }
+
+    /// Evaluate an explicit list of salts instead of a range, in parallel
+    ///
+    /// Denied addresses are skipped, but the list isn't split into rounds, so there
+    /// are no progress events, stats or early stop at the target.
+    ///
+    /// # Arguments
+    /// * `params` - The search parameters, the salt range and round settings are ignored
+    /// * `salts` - The salts to evaluate
+    ///
+    /// # Returns
+    /// * The best address by the score mode and tie break, the earliest in the list
+    ///   of identical addresses, or `None` if the list is empty or every address is denied
+    pub fn search_list(&self, params: &SearchParams, salts: &[U256]) -> Option<AddressSalt> {
+        let init_code_hash_bytes = bytes32(params.init_code_hash);
+        self.install(|| {
+            salts
+                .par_iter()
+                .map(|salt_n| AddressSalt {
+                    address: self.deriver.derive(
+                        params.factory,
+                        &bytes32(*salt_n),
+                        &init_code_hash_bytes,
+                    ),
+                    salt_n: *salt_n,
+                })
+                .filter(|found| !self.denied.contains(&found.address))
+                .reduce_with(|a, b| {
+                    if params.score_mode.is_better(&b, &a, params.tie_break) {
+                        b
+                    } else {
+                        a
+                    }
+                })
+        })
+    }
+
+    /// Clear the best, totals and stats so the thread pool can be reused for another search
+    pub fn reset(&self) {
+        *self.best.lock().unwrap() = None;
+        *self.total_attempts.lock().unwrap() = 0;
+        *self.total_rounds.lock().unwrap() = 0;
+        *self.stats.lock().unwrap() = RoundStats::default();
+        *self.next_milestone.lock().unwrap() = 0;
+        self.target_reached.store(false, Ordering::Relaxed);
+        self.failed_rounds.store(0, Ordering::Relaxed);
+        self.score_improvements.store(0, Ordering::Relaxed);
+        // only left over by rounds which panicked
+        self.running_attempts.store(0, Ordering::Relaxed);
+    }
+
+    /// Start the next search from a best found earlier, e.g. by a previous run,
+    /// so only addresses improving on it are reported as a new best
+    ///
+    /// # Arguments
+    /// * `best` - The address and salt to beat
+    pub fn seed_best(&self, best: AddressSalt) {
+        *self.best.lock().unwrap() = Some(best);
+    }
+
+    /// Never report these addresses, e.g. contracts that are already deployed
+    ///
+    /// # Arguments
+    /// * `addresses` - The addresses to skip, added to any denied before
+    pub fn deny(&mut self, addresses: impl IntoIterator<Item = Address>) {
+        self.denied.extend(addresses);
+    }
+
+    /// Pass over salts searched before, e.g. the ranges recorded in a ledger, so
+    /// restarts with different parameters never scan a salt twice
+    ///
+    /// The rounds keep their place, a round only scans its salts outside the ranges.
+    /// Seed a best with `seed_best` too, a search with every salt skipped returns it.
+    ///
+    /// # Arguments
+    /// * `ranges` - The `[from, to)` salt ranges, added to any skipped before
+    pub fn skip(&mut self, ranges: impl IntoIterator<Item = (U256, U256)>) {
+        self.skip.extend(ranges);
+        self.skip.sort();
+    }
+
+    /// Start with one round at a time and let more run at once over a period, up to one
+    /// per thread, to avoid the thermal spike of saturating every core at once
+    ///
+    /// Only when rounds start is held back, every round still runs, so the totals are
+    /// the same as without a ramp-up. It runs over the first search only.
+    ///
+    /// # Arguments
+    /// * `ramp_up` - The time until every thread runs rounds
+    pub fn set_ramp_up(&mut self, ramp_up: Duration) {
+        self.ramp_up = Some(ramp_up);
+    }
+
+    /// Run fewer rounds at once while the machine is under pressure, for long runs on a
+    /// shared machine, see `Throttle` for how the threads follow the readings
+    ///
+    /// Only when rounds start is held back, so a round already running finishes first
+    /// and the throttle takes up to a round to bite.
+    ///
+    /// # Arguments
+    /// * `source` - Reads the pressure, e.g. `SystemPressure` with the pressure feature
+    /// * `limits` - The pressure above which threads are given up
+    pub fn set_pressure_throttle(
+        &mut self,
+        source: Box<dyn PressureSource>,
+        limits: PressureLimits,
+    ) {
+        let throttle = Throttle::new(self.num_threads(), limits);
+        self.pressure = Some((source, Mutex::new(throttle)));
+    }
+
+    /// Warn when a search counts no attempts for a while, e.g. every thread is blocked
+    /// or the machine throttled to a halt
+    ///
+    /// Running rounds add to the count every `PROGRESS_INTERVAL` attempts, so even long
+    /// rounds move it every few milliseconds.
+    ///
+    /// # Arguments
+    /// * `stall_warning` - How long the count may stand still
+    pub fn set_stall_warning(&mut self, stall_warning: Duration) {
+        self.stall_warning = Some(stall_warning);
+    }
+
+    /// Log the attempts made so far at a fixed interval, including those of rounds still
+    /// running, so a search with rounds of many seconds doesn't look hung between them
+    ///
+    /// # Arguments
+    /// * `progress_interval` - The time between two progress lines
+    pub fn set_progress_interval(&mut self, progress_interval: Duration) {
+        self.progress_interval = Some(progress_interval);
+    }
+
+    /// Rerun a round which panics this many times before skipping it, a round is
+    /// skipped on its first panic by default
+    ///
+    /// # Arguments
+    /// * `round_retries` - The number of reruns
+    pub fn set_round_retries(&mut self, round_retries: u32) {
+        self.round_retries = round_retries;
+    }
+
+    /// The number of times the best score went up in the last search, e.g. from 6 to 7
+    /// to 9 zeros is 2, a better address with the same score doesn't count
+    pub fn score_improvements(&self) -> u64 {
+        self.score_improvements.load(Ordering::Relaxed)
+    }
+
+    /// The number of rounds of the last search skipped because they panicked
+    pub fn failed_rounds(&self) -> u64 {
+        self.failed_rounds.load(Ordering::Relaxed)
+    }
+
+    /// Report each of these scores the first time the best reaches it
+    ///
+    /// # Arguments
+    /// * `milestones` - The scores, in any order
+    /// * `on_milestone` - A shell command to run at each milestone, with the best
+    ///   substituted as for `--on-success`
+    pub fn set_milestones(&mut self, mut milestones: Vec<u32>, on_milestone: Option<String>) {
+        milestones.sort_unstable();
+        milestones.dedup();
+        self.milestones = milestones;
+        self.on_milestone = on_milestone;
+    }
+
+    /// Also write each new best to a named pipe, as a JSON line
+    ///
+    /// # Arguments
+    /// * `event_pipe` - The pipe, see `EventPipe::open`
+    pub fn set_event_pipe(&mut self, event_pipe: EventPipe) {
+        self.event_pipe = Some(event_pipe);
+    }
+
+    /// Keep salts out of the events, the caller reveals the final one as it sees fit
+    ///
+    /// # Arguments
+    /// * `mask_salts` - Whether to replace each salt with a placeholder
+    pub fn set_mask_salts(&mut self, mask_salts: bool) {
+        self.mask_salts = mask_salts;
+    }
+
+    /// An event as it's logged, with its salts masked if `set_mask_salts` asked for it
+    fn logged(&self, event: Event) -> Event {
+        if self.mask_salts {
+            return event.masked();
+        }
+        event
+    }
+
+    /// Whether the last search stopped because it reached `SearchParams::target_score`
+    pub fn target_reached(&self) -> bool {
+        self.target_reached.load(Ordering::Relaxed)
+    }
+
+    /// The total number of addresses computed so far
+    pub fn total_attempts(&self) -> u128 {
+        *self.total_attempts.lock().unwrap()
+    }
+
+    /// The total number of addresses computed so far plus those of the rounds still
+    /// running, up to `PROGRESS_INTERVAL` behind per round
+    pub fn live_attempts(&self) -> u128 {
+        let total_attempts = self.total_attempts.lock().unwrap();
+        // read under the lock, which rounds hold as they move their attempts to the total
+        *total_attempts + self.running_attempts.load(Ordering::Relaxed) as u128
+    }
+
+    /// The number of scanned addresses per leading-zero count
+    /// only populated when the search ran with `histogram` enabled
+    ///
+    /// # Returns
+    /// * An array where index `i` holds how many addresses had exactly `i` leading zeros
+    pub fn histogram(&self) -> [u128; HISTOGRAM_BUCKETS] {
+        self.stats.lock().unwrap().histogram
+    }
+
+    /// The uniform random sample of scanned addresses, only populated when the search
+    /// ran with `sample_size` set
+    pub fn sample(&self) -> Vec<AddressSalt> {
+        self.stats.lock().unwrap().sample.items().to_vec()
+    }
+
+    /// The distribution of the wall-clock time of the rounds searched so far
+    pub fn round_durations(&self) -> DurationSketch {
+        self.stats.lock().unwrap().round_durations.clone()
+    }
+
+    /// The time spent per phase, only populated when the search ran with `profile` enabled
+    pub fn timings(&self) -> PhaseTimings {
+        self.stats.lock().unwrap().timings
+    }
+
+    /// Measure the attempts per second of the thread pool, without reporting progress
+    /// or updating the best, e.g. to compare thread counts
+    ///
+    /// # Arguments
+    /// * `params` - The search parameters, `num_rounds` rounds of `round_size` are run
+    ///
+    /// # Returns
+    /// * The attempts per second over all rounds
+    pub fn measure_rate(&self, params: SearchParams) -> f64 {
+        let params = SearchParams {
+            histogram: false,
+            profile: false,
+            sample_size: None,
+            target_score: None,
+            ..params
+        };
+        let start_time = Instant::now();
+        let attempts: u128 = self.install(|| {
+            (0..params.num_rounds)
+                .into_par_iter()
+                .map(|round| {
+                    let mut round_stats = RoundStats::default();
+                    Self::search_create2_addresses(
+                        &Self::round_params(&params, round),
+                        &mut round_stats,
+                        &self.target_reached,
+                        &self.denied,
+                        &self.deriver,
+                        &AtomicU64::new(0),
+                    );
+                    round_stats.attempts
+                })
+                .sum()
+        });
+        return attempts as f64 / start_time.elapsed().as_secs_f64();
+    }
+
+    /// The parameters of a single round of a search
+    ///
+    /// # Arguments
+    /// * `initial_params` - The parameters of the whole search
+    /// * `round` - The index of the round
+    ///
+    /// # Returns
+    /// * The parameters with `initial_salt_n` moved to the start of the round,
+    ///   and `round_size` cut to the remaining attempts in the last round
+    fn round_params(initial_params: &SearchParams, round: u128) -> SearchParams {
+        // each round spans round_size salts spaced salt_step apart
+        let round_offset = U256::from(initial_params.round_size)
+            * U256::from(initial_params.salt_step)
+            * U256::from(round);
+        let round_size = match initial_params.max_attempts {
+            // a round past the attempts scans nothing rather than underflowing
+            Some(max_attempts) => initial_params
+                .round_size
+                .min(max_attempts.saturating_sub(initial_params.round_size * round)),
+            None => initial_params.round_size,
+        };
+        SearchParams {
+            initial_salt_n: initial_params.initial_salt_n + round_offset,
+            round_size,
+            ..*initial_params
+        }
+    }
+
+    /// Warn when the attempts stand still for `stall_warning`, until `done` is set
+    fn watch_for_stall(&self, stall_warning: Duration, done: &AtomicBool) {
+        let poll = (stall_warning / 4).clamp(Duration::from_millis(10), Duration::from_secs(1));
+        let mut detector = StallDetector::new(self.live_attempts(), Instant::now());
+        while !done.load(Ordering::Relaxed) {
+            // unparked when the search is over
+            thread::park_timeout(poll);
+            let attempts = self.live_attempts();
+            if let Some(still) = detector.observe(attempts, Instant::now(), stall_warning) {
+                eprintln!(
+                    "Warning: no attempts counted for {}, stuck at {}, the search may be stalled",
+                    fmt_dms(still.as_secs() as u128),
+                    attempts
+                );
+            }
+        }
+    }
+
+    /// Emit a progress event every `interval` until `done` is set
+    fn report_progress(
+        &self,
+        interval: Duration,
+        start_time: Instant,
+        event_format: EventFormat,
+        done: &AtomicBool,
+    ) {
+        loop {
+            // unparked when the search is over
+            thread::park_timeout(interval);
+            if done.load(Ordering::Relaxed) {
+                return;
+            }
+            let attempts = self.live_attempts();
+            let elapsed_ms = start_time.elapsed().as_millis();
+            emit(
+                &Event::Progress {
+                    attempts,
+                    elapsed_ms,
+                    rate: attempts as f64 * 1000.0 / elapsed_ms.max(1) as f64,
+                },
+                event_format,
+            );
+        }
+    }
+
+    /// Read the pressure every `PRESSURE_POLL` and set the rounds allowed at once from it,
+    /// until `done` is set
+    fn throttle_on_pressure(&self, done: &AtomicBool) {
+        let (source, throttle) = self.pressure.as_ref().unwrap();
+        let num_threads = self.num_threads();
+        loop {
+            // unparked when the search is over
+            thread::park_timeout(PRESSURE_POLL);
+            if done.load(Ordering::Relaxed) {
+                return;
+            }
+            let pressure = source.read();
+            let mut throttle = throttle.lock().unwrap();
+            let before = throttle.allowed();
+            if let Some(allowed) = throttle.observe(&pressure, Instant::now()) {
+                self.thread_limit.store(allowed, Ordering::Relaxed);
+                if allowed < before {
+                    eprintln!(
+                        "Warning: the machine is under pressure ({}), searching on {} of {} \
+                         threads",
+                        pressure, allowed, num_threads
+                    );
+                } else {
+                    eprintln!(
+                        "The pressure eased ({}), searching on {} of {} threads",
+                        pressure, allowed, num_threads
+                    );
+                }
+            }
+        }
+    }
+
+    /// Wait until the ramp-up and the pressure throttle let one more round run, and count
+    /// it as running
+    ///
+    /// The rounds allowed grow linearly from one at `ramp_up_start` to one per thread
+    /// once the ramp-up is over, capped by the throttle, the thread waiting runs no other
+    /// round meanwhile.
+    fn wait_for_slot(&self, ramp_up_start: Instant) {
+        let num_threads = self.num_threads();
+        loop {
+            let ramped = match self.ramp_up {
+                Some(ramp_up) => {
+                    let progress = ramp_up_start.elapsed().as_secs_f64() / ramp_up.as_secs_f64();
+                    if progress >= 1.0 {
+                        num_threads
+                    } else {
+                        1 + ((num_threads - 1) as f64 * progress) as usize
+                    }
+                }
+                None => num_threads,
+            };
+            let allowed = ramped.min(self.thread_limit.load(Ordering::Relaxed));
+            let running = self.gated_rounds.load(Ordering::Relaxed);
+            if running < allowed
+                && self
+                    .gated_rounds
+                    .compare_exchange(running, running + 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return;
+            }
+            thread::sleep(Duration::from_millis(RAMP_UP_POLL_MS));
+        }
+    }
+
+    /// Search a round, rerunning it if it panics and skipping it once out of retries,
+    /// so a bug hit by one salt doesn't abort the search and lose the best so far
+    fn search_round_or_skip(
+        &self,
+        initial_params: &SearchParams,
+        round: u128,
+        start_time: std::time::Instant,
+    ) {
+        for attempt in 0..=self.round_retries {
+            // the panic hook has already printed the message and location
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                self.search_round(initial_params, round, start_time)
+            }));
+            if result.is_ok() {
+                return;
+            }
+            if attempt < self.round_retries {
+                eprintln!(
+                    "Warning: round {} panicked, retry {} of {}",
+                    round,
+                    attempt + 1,
+                    self.round_retries
+                );
+            }
+        }
+        eprintln!("Error: round {} panicked, skipping it", round);
+        self.failed_rounds.fetch_add(1, Ordering::Relaxed);
     }
 
     fn search_round(
@@ -71,182 +1209,1072 @@ impl Searcher {
         initial_params: &SearchParams,
         round: u128,
         start_time: std::time::Instant,
-    ) -> AddressSalt {
-        let SearchParams {
-            deployer,
-            initial_salt_n,
-            init_code_hash,
-            round_size,
-            num_rounds,
-        } = initial_params;
+    ) -> Option<AddressSalt> {
+        let params = Self::round_params(initial_params, round);
+        let parts = unskipped_parts(&params, &self.skip);
+        if parts.is_empty() {
+            // every salt of the round was searched before
+            *self.total_rounds.lock().unwrap() += 1;
+            return None;
+        }
 
-        let round_offset = U256::from(*round_size) * U256::from(round);
-        let round_salt_n = initial_salt_n + round_offset;
-        // let round_salt = bytes32(round_salt_n);
-        let params = SearchParams {
-            deployer: *deployer,
-            initial_salt_n: round_salt_n,
-            init_code_hash: init_code_hash.clone(),
-            round_size: *round_size,
-            num_rounds: *num_rounds,
+        // counted locally and merged below to keep the hot loop free of locks
+        let mut round_stats = RoundStats {
+            sample: Reservoir::new(params.sample_size.unwrap_or(0)),
+            ..RoundStats::default()
         };
+        let round_start = Instant::now();
+        let mut round_best: Option<AddressSalt> = None;
+        let mut attempts = 0;
+        for part in &parts {
+            let part_best = Self::search_create2_addresses(
+                part,
+                &mut round_stats,
+                &self.target_reached,
+                &self.denied,
+                &self.deriver,
+                &self.running_attempts,
+            );
+            attempts += round_stats.attempts;
+            // denied only if every address of the part was, it must never become the best
+            if self.denied.contains(&part_best.address) {
+                continue;
+            }
+            let better = match round_best {
+                Some(best) => params
+                    .score_mode
+                    .is_better(&part_best, &best, params.tie_break),
+                None => true,
+            };
+            if better {
+                round_best = Some(part_best);
+            }
+            // the part stopped at the target, as the round would have
+            if params.threshold_return == ThresholdReturn::First
+                && params
+                    .target_score
+                    .is_some_and(|target| params.score_mode.score(part_best.address) >= target)
+            {
+                break;
+            }
+        }
+        round_stats.attempts = attempts;
+        let merge_start = Instant::now();
+        round_stats
+            .round_durations
+            .record(merge_start - round_start);
 
-        let round_best = Self::search_create2_addresses(&params);
-        // acquire best mutex and check if there are more leading zeros
+        // acquire best mutex and check if the round found a better address
+        let score_mode = params.score_mode;
         let mut best_mutex = self.best.lock().unwrap();
         let mut total_rounds = self.total_rounds.lock().unwrap();
         *total_rounds += 1;
         let mut total_attempts = self.total_attempts.lock().unwrap();
-        *total_attempts += round_size;
+        *total_attempts += round_stats.attempts;
+        self.running_attempts
+            .fetch_sub(round_stats.reported as u64, Ordering::Relaxed);
         // this will be unlocked when the lock goes out of scope which is when the function returns
 
-        // update best
-        if best_mutex.is_none() || round_best.address < best_mutex.unwrap().address {
-            *best_mutex = Some(round_best);
-            Self::log_new_best(&best_mutex.unwrap());
-        } else if *total_rounds % 100 == 0 {
+        // once the target is met the first address to meet it stands
+        let keep_first = params.threshold_return == ThresholdReturn::First
+            && self.target_reached.load(Ordering::Relaxed);
+
+        // update best, a round whose every address was denied has none to offer
+        let improved = round_best.filter(|round_best| match *best_mutex {
+            None => true,
+            Some(best) => !keep_first && score_mode.is_better(round_best, &best, params.tie_break),
+        });
+        if let Some(round_best) = improved {
+            let previous = best_mutex.replace(round_best);
+            let event = self.logged(Event::NewBest {
+                best: BestRecord::new(&round_best, score_mode),
+            });
+            emit(&event, params.event_format);
+            if let Some(pipe) = &self.event_pipe {
+                pipe.send(&event);
+            }
+            // the first best of a search sets the depth rather than improving on it
+            let score = score_mode.score(round_best.address);
+            if previous.is_some_and(|previous| score > score_mode.score(previous.address)) {
+                let improvements = self.score_improvements.fetch_add(1, Ordering::Relaxed) + 1;
+                let best = BestRecord::new(&round_best, score_mode);
+                emit(
+                    &self.logged(Event::ScoreReached {
+                        score,
+                        improvements,
+                        best,
+                    }),
+                    params.event_format,
+                );
+            }
+        } else if let Some(best) = best_mutex.filter(|_| *total_rounds % 100 == 0) {
             // periodically log the best
-            Self::log_best(&best_mutex.unwrap());
+            let best = BestRecord::new(&best, score_mode);
+            emit(&self.logged(Event::Best { best }), params.event_format);
         }
 
-        Self::log_attempts(
-            *total_rounds,
-            *total_attempts,
-            start_time,
-            count_leading_zeroes(best_mutex.unwrap().address),
-        );
+        // under the best lock, so milestones are reported in order and once each
+        let mut reached_milestones = Vec::new();
+        if let Some(best) = *best_mutex {
+            let score = score_mode.score(best.address);
+            let mut next_milestone = self.next_milestone.lock().unwrap();
+            while *next_milestone < self.milestones.len()
+                && score >= self.milestones[*next_milestone]
+            {
+                let milestone = self.milestones[*next_milestone];
+                let record = BestRecord::new(&best, score_mode);
+                emit(
+                    &self.logged(Event::Milestone {
+                        milestone,
+                        best: record,
+                    }),
+                    params.event_format,
+                );
+                reached_milestones.push(best);
+                *next_milestone += 1;
+            }
+        }
+
+        if let (Some(target_score), Some(best)) = (params.target_score, *best_mutex) {
+            // rounds still running will notice the flag at their next check and stop,
+            // swap so only the first round to reach the target reports it
+            if score_mode.score(best.address) >= target_score
+                && !self.target_reached.swap(true, Ordering::Relaxed)
+            {
+                let best = BestRecord::new(&best, score_mode);
+                emit(
+                    &self.logged(Event::TargetReached { best }),
+                    params.event_format,
+                );
+            }
+        }
+
+        if params.histogram || params.profile {
+            round_stats.timings.merge = merge_start.elapsed();
+            let mut stats = self.stats.lock().unwrap();
+            for (total, count) in stats.histogram.iter_mut().zip(round_stats.histogram.iter()) {
+                *total += count;
+            }
+            stats.timings.add(&round_stats.timings);
+        }
+        {
+            let mut stats = self.stats.lock().unwrap();
+            stats.round_durations.merge(&round_stats.round_durations);
+            if params.sample_size.is_some() {
+                stats
+                    .sample
+                    .merge(round_stats.sample, &mut rand::thread_rng());
+            }
+        }
+
+        // should be at least 1 to avoid divide by zero
+        let elapsed_ms = start_time.elapsed().as_millis();
+        if let Some(best) = best_mutex.filter(|_| elapsed_ms > 0) {
+            // calculate the rate of attempts per second
+            let rate_ms: f64 = (*total_attempts as f64) / (elapsed_ms as f64);
+            let heartbeat = Event::Heartbeat {
+                round: *total_rounds,
+                attempts: *total_attempts,
+                elapsed_ms,
+                rate: rate_ms * 1000.0,
+                best: BestRecord::new(&best, score_mode),
+            };
+            emit(&self.logged(heartbeat), params.event_format);
+        }
+
+        // the command may take a while, don't hold up the other rounds meanwhile
+        drop(best_mutex);
+        drop(total_rounds);
+        drop(total_attempts);
+        if let Some(command) = &self.on_milestone {
+            for best in reached_milestones {
+                match run_on_success(command, &best) {
+                    Ok(status) if status.success() => {}
+                    Ok(status) => eprintln!("Error: --on-milestone command failed with {}", status),
+                    Err(e) => eprintln!("Error: failed to run the --on-milestone command: {}", e),
+                }
+            }
+        }
         return round_best;
     }
 
-    /// Search for the CREATE2 address with lowest value (i.e. most leading zeros)
+    /// Search for the best CREATE2 address according to the score mode,
+    /// by default the lowest value (i.e. most leading zeros)
     ///
     /// # Arguments
     /// * `params` - The search parameters
+    /// * `stats` - Updated with the attempts made, and the histogram and phase timings
+    ///   when enabled in `params`
+    /// * `target_reached` - Polled periodically when `params.target_score` is set, the round
+    ///   stops early once it is set since the search already has its result
+    /// * `denied` - Addresses skipped when they would be a new best, only looked up then
+    ///   so the lookup stays out of the hot path
+    /// * `deriver` - Turns each salt into its address
+    /// * `progress` - Bumped every `PROGRESS_INTERVAL` attempts, which are also added to
+    ///   `stats.reported`
     ///
     /// # Returns
-    /// * The address with the lowest value found in the search
-    fn search_create2_addresses(params: &SearchParams) -> AddressSalt {
+    /// * The best address found in the search, denied only if every address was
+    fn search_create2_addresses(
+        params: &SearchParams,
+        stats: &mut RoundStats,
+        target_reached: &AtomicBool,
+        denied: &HashSet<Address>,
+        deriver: &D,
+        progress: &AtomicU64,
+    ) -> AddressSalt {
         let SearchParams {
-            deployer,
+            factory,
             initial_salt_n,
             init_code_hash,
             round_size,
             num_rounds: _,
+            max_attempts: _,
+            salt_step,
+            histogram: count_histogram,
+            profile,
+            sample_size,
+            score_mode,
+            target_score,
+            threshold_return,
+            tie_break,
+            event_format: _,
         } = params;
+        let step = U256::from(*salt_step);
         let mut salt_n = *initial_salt_n;
         let mut salt = bytes32(salt_n);
 
         let init_code_hash_bytes: Bytes = bytes32(*init_code_hash);
 
-        let mut address: Address =
-            get_create2_address_from_hash(*deployer, &salt, &init_code_hash_bytes);
+        let mut address: Address = deriver.derive(*factory, &salt, &init_code_hash_bytes);
 
         let mut best: AddressSalt = AddressSalt {
             address: address,
             // leading_zeros: address.leading_zeros,
             salt_n,
         };
+        // kept alongside the best, the checksum modes would hash it for every comparison
+        let mut best_score = score_mode.score(address);
+        // any address that isn't denied replaces a denied first address
+        let mut best_denied = denied.contains(&address);
+        if *count_histogram {
+            stats.histogram[leading_zero_nibbles(address) as usize] += 1;
+        }
+        let sampling = sample_size.is_some();
+        let mut rng = rand::thread_rng();
+        if sampling {
+            stats.sample.offer(best, &mut rng);
+        }
+
+        stats.attempts = *round_size;
+        let check_target = target_score.is_some();
+        // with ThresholdReturn::First the round stops at its first address meeting the target
+        let stop_at = match threshold_return {
+            ThresholdReturn::First => *target_score,
+            ThresholdReturn::RoundBest => None,
+        };
+        if stop_at.is_some_and(|target| !best_denied && best_score >= target) {
+            stats.attempts = 1;
+            return best;
+        }
 
         // already checked the first address
-        for _i in 0..*round_size - 1 {
-            salt_n += U256::from(1);
+        for i in 0..*round_size - 1 {
+            // the first address plus i iterations so far
+            if (i + 1) % PROGRESS_INTERVAL == 0 {
+                progress.fetch_add(PROGRESS_INTERVAL as u64, Ordering::Relaxed);
+                stats.reported += PROGRESS_INTERVAL;
+            }
+            if check_target
+                && i % STOP_CHECK_INTERVAL == 0
+                && target_reached.load(Ordering::Relaxed)
+            {
+                // the first address plus i iterations
+                stats.attempts = i + 1;
+                break;
+            }
+            if *profile && i % PROFILE_SAMPLE_INTERVAL == 0 {
+                // same as below but with each phase timed
+                let start = Instant::now();
+                salt_n += step;
+                salt = bytes32(salt_n);
+                let incremented = Instant::now();
+                address = deriver.derive(*factory, &salt, &init_code_hash_bytes);
+                let derived = Instant::now();
+                let found = AddressSalt { address, salt_n };
+                if (best_denied
+                    || score_mode.is_better_scored(&found, &best, best_score, *tie_break))
+                    && !denied.contains(&address)
+                {
+                    best = found;
+                    best_score = score_mode.score(address);
+                    best_denied = false;
+                }
+                stats.timings.salt_increment += incremented - start;
+                stats.timings.address_derivation += derived - incremented;
+                stats.timings.comparison += derived.elapsed();
+                stats.timings.samples += 1;
+                if *count_histogram {
+                    stats.histogram[leading_zero_nibbles(address) as usize] += 1;
+                }
+                if sampling {
+                    stats
+                        .sample
+                        .offer(AddressSalt { address, salt_n }, &mut rng);
+                }
+                if stop_at.is_some_and(|target| best.address == address && best_score >= target) {
+                    // the first address plus i + 1 iterations
+                    stats.attempts = i + 2;
+                    break;
+                }
+                continue;
+            }
+
+            salt_n += step;
             salt = bytes32(salt_n);
-            address = get_create2_address_from_hash(*deployer, &salt, &init_code_hash_bytes);
+            address = deriver.derive(*factory, &salt, &init_code_hash_bytes);
+            if *count_histogram {
+                stats.histogram[leading_zero_nibbles(address) as usize] += 1;
+            }
+            if sampling {
+                stats
+                    .sample
+                    .offer(AddressSalt { address, salt_n }, &mut rng);
+            }
             // check if we have a new best
-            if address < best.address {
-                best = AddressSalt { address, salt_n };
+            let found = AddressSalt { address, salt_n };
+            if (best_denied || score_mode.is_better_scored(&found, &best, best_score, *tie_break))
+                && !denied.contains(&address)
+            {
+                best = found;
+                best_score = score_mode.score(address);
+                best_denied = false;
+                if stop_at.is_some_and(|target| best_score >= target) {
+                    // the first address plus i + 1 iterations
+                    stats.attempts = i + 2;
+                    break;
+                }
             }
         }
         return best;
     }
+}
 
-    /// Log the round/attempts/etc.
-    ///
-    /// # Arguments
-    /// * `round` - The round number
-    /// * `attempt` - The number of attempts in this round
-    /// * `now` - The time at the start of the round
-    /// * `best_zeros` - The number of leading zeros in the best address found so far
-    ///
-    /// # Returns
-    /// * None
-    ///
-    fn log_attempts(round: u128, attempt: u128, now: std::time::Instant, best_zeros: u8) {
-        // should be at least 1 to avoid divide by zero
-        let elapsed_ms = now.elapsed().as_millis();
-        if elapsed_ms == 0 {
-            return;
-        }
-
-        // calculate the rate of attempts per second
-        let rate_ms: f64 = (attempt as f64) / (elapsed_ms as f64);
-        let rate = rate_ms * 1000.0;
-        println!(
-            "Round {} @ {} attempts/sec {}",
-            round,
-            (rate as u128).to_formatted_string(&Locale::en),
-            Self::fmt_countdown(best_zeros + 1, rate, elapsed_ms)
+/// Run an operation until it succeeds, sleeping between attempts with a backoff doubled
+/// after each failure
+///
+/// # Arguments
+/// * `attempts` - The number of attempts before giving up, at least 1
+/// * `backoff` - The delay before the first retry
+/// * `what` - What the operation does, for the log, e.g. "create thread pool"
+/// * `op` - The operation
+///
+/// # Returns
+/// * The result of the first attempt to succeed, or the error of the last one
+fn retry_with_backoff<T, E: fmt::Display>(
+    attempts: u32,
+    backoff: Duration,
+    what: &str,
+    mut op: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut backoff = backoff;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= attempts => return Err(e),
+            Err(e) => {
+                eprintln!(
+                    "Failed to {} (attempt {}/{}): {}, retrying in {}ms",
+                    what,
+                    attempt,
+                    attempts,
+                    e,
+                    backoff.as_millis()
+                );
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// The parts of a round outside the salt ranges to skip
+///
+/// # Arguments
+/// * `params` - The parameters of the round
+/// * `skip` - The `[from, to)` salt ranges to pass over, sorted by `from`
+///
+/// # Returns
+/// * The parameters of each part, with its first salt and number of attempts,
+///   in salt order, the whole round when no range overlaps it
+fn unskipped_parts(params: &SearchParams, skip: &[(U256, U256)]) -> Vec<SearchParams> {
+    let start = params.initial_salt_n;
+    let step = U256::from(params.salt_step);
+    let round_size = U256::from(params.round_size);
+    // the index in the round of the first salt at or past `salt`
+    let index_of = |salt: U256| -> U256 {
+        if salt <= start {
+            return U256::zero();
+        }
+        let offset = salt - start;
+        let index = offset / step + if (offset % step).is_zero() { 0 } else { 1 };
+        index.min(round_size)
+    };
+
+    let mut parts = Vec::new();
+    let mut next = U256::zero();
+    for (from, to) in skip {
+        let (from, to) = (index_of(*from), index_of(*to));
+        if from > next {
+            parts.push((next, from));
+        }
+        next = next.max(to);
+    }
+    if next < round_size {
+        parts.push((next, round_size));
+    }
+    parts
+        .into_iter()
+        .map(|(from, to)| SearchParams {
+            initial_salt_n: start + from * step,
+            round_size: (to - from).as_u128(),
+            ..*params
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::derive::SaltDeriver;
+    use crate::score::{distance, trailing_zero_nibbles};
+    use crate::utils::create2_address;
+
+    /// A search of `num_rounds` rounds of `round_size` salts from salt 7, for leading zeros
+    fn params(round_size: u128, num_rounds: u128) -> SearchParams {
+        SearchParams {
+            factory: Address::repeat_byte(0x11),
+            initial_salt_n: U256::from(7),
+            init_code_hash: U256::from(0x1234),
+            round_size,
+            num_rounds,
+            max_attempts: None,
+            salt_step: 1,
+            histogram: false,
+            profile: false,
+            sample_size: None,
+            score_mode: ScoreMode::LeadingZeros,
+            target_score: None,
+            threshold_return: ThresholdReturn::RoundBest,
+            tie_break: TieBreak::LowestAddress,
+            event_format: EventFormat::Json,
+        }
+    }
+
+    #[test]
+    fn parallel_search_finds_the_sequential_minimum() {
+        let params = params(500, 8);
+        let sequential = (0..4000u64)
+            .map(|i| {
+                let salt_n = params.initial_salt_n + U256::from(i);
+                AddressSalt {
+                    address: create2_address(params.factory, salt_n, params.init_code_hash),
+                    salt_n,
+                }
+            })
+            .min_by_key(|found| found.address)
+            .unwrap();
+        for num_threads in [1, 2, 4, 8] {
+            let searcher = Searcher::new(num_threads, false).unwrap();
+            assert_eq!(
+                searcher.search(params),
+                Some(sequential),
+                "{} threads",
+                num_threads
+            );
+            assert_eq!(searcher.total_attempts(), 4000);
+        }
+    }
+
+    #[test]
+    fn overlapping_rounds_are_rejected() {
+        // 250 attempts end in the third of 4 rounds of 100, the last would rescan salts
+        let overlapping = SearchParams {
+            max_attempts: Some(250),
+            ..params(100, 4)
+        };
+        assert!(overlapping.check_rounds().is_err());
+        assert!(SearchParams {
+            max_attempts: Some(350),
+            ..params(100, 4)
+        }
+        .check_rounds()
+        .is_ok());
+        // a round past the attempts is empty rather than underflowing
+        assert_eq!(
+            Searcher::<Create2Deriver>::round_params(&overlapping, 3).round_size,
+            0
         );
+
+        let searcher = Searcher::new(1, false).unwrap();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            searcher.search(overlapping)
+        }));
+        assert!(result.is_err());
     }
 
-    /// Log the best address found so far
-    ///
-    /// # Arguments
-    /// * `best` - The best address found so far
-    ///
-    /// # Returns
-    /// * None
-    fn log_best(best: &AddressSalt) {
-        let msg = format!(
-            "{} zeros {:?} salt 0x{}",
-            count_leading_zeroes(best.address),
-            best.address,
-            hex::encode(bytes32(best.salt_n))
+    #[test]
+    fn search_built_from_its_three_parts_matches_the_flat_params() {
+        let target = Target {
+            factory: Address::repeat_byte(0x11),
+            init_code_hash: U256::from(0x1234),
+        };
+        let score = ScoreConfig {
+            score_mode: ScoreMode::LeadingZeros,
+            tie_break: TieBreak::LowestAddress,
+            ..ScoreConfig::default()
+        };
+        let strategy = SearchStrategy {
+            initial_salt_n: U256::from(7),
+            round_size: 500,
+            num_rounds: 8,
+            max_attempts: None,
+            salt_step: 1,
+            histogram: false,
+            profile: false,
+            sample_size: None,
+            event_format: EventFormat::Json,
+        };
+        let flat = params(500, 8);
+        assert_eq!(SearchParams::new(target, score, strategy), flat);
+        // and the flat params split back into the same parts
+        assert_eq!(
+            SearchParams::new(flat.target(), flat.score_config(), flat.strategy()),
+            flat
         );
-        // print to terminal in cyan ANSI color
-        println!("\x1b[36m{}\x1b[0m", msg);
+
+        let searcher = Searcher::new(2, false).unwrap();
+        let found = searcher.search((target, score, strategy));
+        assert_eq!(searcher.total_attempts(), 4000);
+        searcher.reset();
+        assert_eq!(found, searcher.search(flat));
+        assert!(found.is_some());
     }
 
-    /// Log a newly found best address
-    ///
-    /// # Arguments
-    /// * `best` - The best address found so far
-    ///
-    /// # Returns
-    /// * None
-    fn log_new_best(best: &AddressSalt) {
-        let msg = format!(
-            "{} zeros {:?} salt 0x{}",
-            count_leading_zeroes(best.address),
-            best.address,
-            hex::encode(bytes32(best.salt_n))
+    #[test]
+    fn closest_to_finds_the_nearest_address_of_the_range() {
+        // salts 7 through 0x206, each its own address
+        let search = |target: u64| {
+            let params = SearchParams {
+                score_mode: ScoreMode::ClosestTo(Address::from_low_u64_be(target)),
+                ..params(0x40, 8)
+            };
+            let searcher = Searcher::with_deriver(4, false, SaltDeriver).unwrap();
+            searcher.search(params).unwrap().salt_n
+        };
+        assert_eq!(search(0x150), U256::from(0x150));
+        assert_eq!(search(0), U256::from(7));
+        assert_eq!(search(0x10000), U256::from(0x206));
+
+        // the distance either way, without overflowing at the ends of the address space
+        let (low, high) = (Address::zero(), Address::repeat_byte(0xff));
+        assert_eq!(distance(low, high), (U256::one() << 160usize) - 1);
+        assert_eq!(distance(high, low), distance(low, high));
+        let target = Address::from_low_u64_be(0x150);
+        assert_eq!(
+            distance(Address::from_low_u64_be(0x140), target),
+            U256::from(0x10)
+        );
+        assert_eq!(
+            distance(Address::from_low_u64_be(0x160), target),
+            U256::from(0x10)
+        );
+        assert_eq!(
+            ScoreMode::ClosestTo(target).describe(Address::from_low_u64_be(0x140)),
+            "distance 0x10"
         );
-        // print to terminal in green ANSI color
-        println!("\x1b[32m{}\x1b[0m", msg);
     }
 
-    /// Format the countdown to the next leading zero
-    /// e.g. (5 0s T-1d 2h 3m 4s)
-    ///
-    /// # Arguments
-    /// * `zeros` - The number of leading zeros
-    /// * `rate` - The rate of attempts per second
-    /// * `elapsed_ms` - The number of milliseconds elapsed
-    ///
-    /// # Returns
-    /// * A string in the format "(X 0s T-YdZhSmSs)" where X is the number of leading zeros,
-    /// Y is the number of days, Z is the number of hours, S is the number of minutes, and S is
-    /// the number of seconds.
-    fn fmt_countdown(zeros: u8, rate: f64, elapsed_ms: u128) -> String {
-        let expected_attempts: u128 = 16_u128.pow(zeros as u32);
-        let expected_attempts_secs_at_current_rate = expected_attempts as f64 / rate;
-        // this is a statistical fallacy, but humans want to see progress
-        let expected_remaining_time_at_rate =
-            (expected_attempts_secs_at_current_rate - (elapsed_ms as f64 / 1000.0)) as u128;
-        return format!(
-            " ({} 0s T-{})",
-            zeros,
-            fmt_dms(expected_remaining_time_at_rate)
+    #[test]
+    fn estimate_attempts_generalizes_sixteen_to_the_zeros() {
+        let estimate = |score_mode: ScoreMode, target_score: Option<u32>| {
+            estimate_attempts(&ScoreConfig {
+                score_mode,
+                target_score,
+                ..ScoreConfig::default()
+            })
+        };
+        // 3 leading zeros, and a prefix of 4 of one nibble, e.g. 0x8888...
+        assert_eq!(estimate(ScoreMode::LeadingZeros, Some(3)), 16_f64.powi(3));
+        assert_eq!(
+            estimate(ScoreMode::LeadingNibble(Some(8)), Some(4)),
+            16_f64.powi(4)
+        );
+        // a prefix of whichever nibble comes first is free on its first nibble
+        assert_eq!(
+            estimate(ScoreMode::LeadingNibble(None), Some(4)),
+            16_f64.powi(3)
+        );
+        // a combined pattern, each digit 1 in 16 and each letter 1 in 32 with its case
+        let word = ScoreMode::ChecksumWord("DeaD1".parse().unwrap());
+        assert_eq!(estimate(word, Some(5)), 32_f64.powi(4) * 16.0);
+        assert_eq!(estimate(word, Some(2)), 32.0 * 32.0);
+        assert_eq!(estimate(ScoreMode::LeadingZeros, None), 1.0);
+        // the same as a search of the params would be budgeted
+        let params = SearchParams {
+            target_score: Some(6),
+            ..params(1, 1)
+        };
+        assert_eq!(estimate_attempts(&params.score_config()), 16_f64.powi(6));
+    }
+
+    #[test]
+    fn salt_list_picks_the_best_of_a_crafted_list() {
+        // the address is the low 20 bytes of the salt, so the high bytes can repeat one
+        let salt = |high: u64, low: &str| {
+            (U256::from(high) << 160usize) + U256::from_str_radix(low, 16).unwrap()
+        };
+        let salts = [
+            salt(0, "ffffffffffffffffffffffffffffffffffffffff"),
+            salt(1, "000000ffffffffffffffffffffffffffffffffff"),
+            salt(2, "00000fffffffffffffffffffffffffffffffffff"),
+            salt(3, "000000ffffffffffffffffffffffffffffffffff"),
+            salt(4, "0fffffffffffffffffffffffffffffffffffffff"),
+        ];
+        let params = params(1, 1);
+        for num_threads in [1, 4] {
+            let mut searcher = Searcher::with_deriver(num_threads, false, SaltDeriver).unwrap();
+            let best = |searcher: &Searcher<SaltDeriver>| {
+                searcher
+                    .search_list(&params, &salts)
+                    .map(|found| found.salt_n)
+            };
+            // the earliest of the two salts deriving the best address
+            assert_eq!(best(&searcher), Some(salts[1]));
+            assert_eq!(searcher.search_list(&params, &[]), None);
+
+            let mut salt_bytes = [0u8; 32];
+            salts[1].to_big_endian(&mut salt_bytes);
+            searcher.deny([Address::from_slice(&salt_bytes[12..])]);
+            assert_eq!(best(&searcher), Some(salts[2]));
+        }
+    }
+
+    #[test]
+    fn stall_detector_fires_once_on_a_frozen_counter() {
+        let limit = Duration::from_secs(10);
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        // an advancing counter never stalls, however long the run
+        let mut detector = StallDetector::new(0, start);
+        for secs in 1..=60 {
+            assert_eq!(detector.observe(secs as u128 * 1000, at(secs), limit), None);
+        }
+
+        // frozen from 60s on, the stall is reported once when it reaches the limit
+        assert_eq!(detector.observe(60_000, at(69), limit), None);
+        assert_eq!(detector.observe(60_000, at(70), limit), Some(limit));
+        assert_eq!(detector.observe(60_000, at(80), limit), None);
+
+        // moving again clears the stall, so the next one is reported too
+        assert_eq!(detector.observe(60_001, at(81), limit), None);
+        assert_eq!(detector.observe(60_001, at(90), limit), None);
+        assert_eq!(
+            detector.observe(60_001, at(95), limit),
+            Some(Duration::from_secs(14))
         );
     }
+
+    #[test]
+    fn continuing_matches_one_longer_search() {
+        let searcher = Searcher::new(2, false).unwrap();
+        let first = SearchResult {
+            params: params(100, 4),
+            best: searcher.search(params(100, 4)).unwrap(),
+        };
+        let continued = searcher.continue_from(&first, 4);
+        // only the new salts are scanned
+        assert_eq!(continued.params.initial_salt_n, U256::from(407));
+        assert_eq!(searcher.total_attempts(), 400);
+        assert!(continued.best.address <= first.best.address);
+        let longer = Searcher::new(2, false).unwrap().search(params(100, 8));
+        assert_eq!(Some(continued.best), longer);
+    }
+
+    /// A search for trailing zeros from salt 1, with `SaltDeriver` the address is the salt,
+    /// so salt 0x100 is the best of the first 0x1ff salts and 0x10 the first with a zero
+    fn trailing_params(round_size: u128, num_rounds: u128) -> SearchParams {
+        SearchParams {
+            initial_salt_n: U256::one(),
+            score_mode: ScoreMode::TrailingThenLeading,
+            ..params(round_size, num_rounds)
+        }
+    }
+
+    #[test]
+    fn rounds_merge_to_the_best_of_any_round() {
+        for num_threads in [1, 4] {
+            let searcher = Searcher::with_deriver(num_threads, false, SaltDeriver).unwrap();
+            // 0x200 in the last round ties on both counts, the lower address wins the tie
+            let found = searcher.search(trailing_params(0x80, 4)).unwrap();
+            assert_eq!(found.salt_n, U256::from(0x100), "{} threads", num_threads);
+            assert_eq!(searcher.total_attempts(), 0x200);
+        }
+    }
+
+    #[test]
+    fn sample_sorts_the_same_on_any_number_of_threads() {
+        // a sample as large as the search keeps every address, in whichever order the
+        // rounds finished in, and most tie on trailing and leading zeros
+        let params = SearchParams {
+            sample_size: Some(0x200),
+            ..trailing_params(0x20, 16)
+        };
+        let mut expected: Vec<AddressSalt> = (1..=0x200u64)
+            .map(|salt| AddressSalt {
+                address: Address::from_low_u64_be(salt),
+                salt_n: U256::from(salt),
+            })
+            .collect();
+        expected.sort_by_key(|found| {
+            (
+                std::cmp::Reverse(trailing_zero_nibbles(found.address)),
+                std::cmp::Reverse(leading_zero_nibbles(found.address)),
+                found.salt_n,
+            )
+        });
+        for num_threads in 1..=8 {
+            let searcher = Searcher::with_deriver(num_threads, false, SaltDeriver).unwrap();
+            searcher.search(params).unwrap();
+            let mut sample = searcher.sample();
+            params.score_mode.sort_best_first(&mut sample);
+            assert_eq!(sample, expected, "{} threads", num_threads);
+        }
+    }
+
+    #[test]
+    fn sample_holds_exactly_k_distinct_addresses_of_the_range() {
+        let params = SearchParams {
+            sample_size: Some(37),
+            ..params(0x40, 16)
+        };
+        let range = params.initial_salt_n..params.initial_salt_n + U256::from(0x400);
+        for num_threads in 1..=8 {
+            let searcher = Searcher::with_deriver(num_threads, false, SaltDeriver).unwrap();
+            searcher.search(params).unwrap();
+            let mut salts: Vec<U256> = searcher.sample().iter().map(|found| found.salt_n).collect();
+            assert_eq!(salts.len(), 37, "{} threads", num_threads);
+            assert!(salts.iter().all(|salt_n| range.contains(salt_n)));
+            salts.sort();
+            salts.dedup();
+            assert_eq!(salts.len(), 37, "{} threads", num_threads);
+        }
+    }
+
+    /// `SaltDeriver`, but panicking on the salts of the first round of `trailing_params(0x20, _)`
+    struct PanickingDeriver;
+
+    impl AddressDeriver for PanickingDeriver {
+        fn derive(&self, factory: Address, salt: &[u8], init_code_hash: &[u8]) -> Address {
+            if salt[31] <= 0x20 && salt[..31].iter().all(|byte| *byte == 0) {
+                panic!("deriving salt {}", salt[31]);
+            }
+            SaltDeriver.derive(factory, salt, init_code_hash)
+        }
+    }
+
+    #[test]
+    fn a_panicking_round_is_skipped_and_the_search_goes_on() {
+        for round_retries in [0, 2] {
+            let mut searcher = Searcher::with_deriver(2, false, PanickingDeriver).unwrap();
+            searcher.set_round_retries(round_retries);
+            let found = searcher.search(trailing_params(0x20, 4)).unwrap();
+            // 0x10 of the first round would have won, of the rest 0x30 ties the
+            // multiples of 0x10 on both counts and is the lowest
+            assert_eq!(found.salt_n, U256::from(0x30));
+            assert_eq!(searcher.failed_rounds(), 1);
+            assert_eq!(searcher.total_attempts(), 3 * 0x20);
+        }
+    }
+
+    #[test]
+    fn milestones_fire_in_order_and_once_each() {
+        let path =
+            std::env::temp_dir().join(format!("search-create2-milestones-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut searcher = Searcher::with_deriver(4, false, SaltDeriver).unwrap();
+        // given out of order and repeated, and 4 is never reached
+        searcher.set_milestones(
+            vec![3, 1, 2, 2, 4],
+            Some(format!("echo {{salt}} >> {}", path.display())),
+        );
+        // salts 0x10, 0x100 and 0x1000 are the first with 1, 2 and 3 trailing zeros
+        searcher.search(trailing_params(0x100, 16)).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let scores: Vec<u32> = contents
+            .lines()
+            .map(|salt| {
+                let salt_n = U256::from_str_radix(salt.trim_start_matches("0x"), 16).unwrap();
+                ScoreMode::TrailingThenLeading.score(Address::from_slice(&bytes32(salt_n)[12..]))
+            })
+            .collect();
+        // one line per milestone, each with a best reaching it
+        assert_eq!(scores.len(), 3, "{}", contents);
+        for (milestone, score) in [1, 2, 3].into_iter().zip(&scores) {
+            assert!(*score >= milestone, "{}", contents);
+        }
+        assert!(
+            scores.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{}",
+            contents
+        );
+    }
+
+    #[test]
+    fn score_reached_counts_each_score_once() {
+        // rounds of 0x10, the first best is 0x10 and only 0x100 and 0x1000 improve on its
+        // score, the round bests in between tie on it and lose to the lower address
+        let searcher = Searcher::with_deriver(1, false, SaltDeriver).unwrap();
+        let found = searcher.search(trailing_params(0x10, 0x100)).unwrap();
+        assert_eq!(found.salt_n, U256::from(0x1000));
+        assert_eq!(searcher.score_improvements(), 2);
+
+        // rounds finishing out of order can skip a score, but never count one twice
+        let searcher = Searcher::with_deriver(4, false, SaltDeriver).unwrap();
+        for _ in 0..4 {
+            searcher.reset();
+            searcher.search(trailing_params(0x10, 0x100)).unwrap();
+            assert!(searcher.score_improvements() <= 2);
+        }
+    }
+
+    #[test]
+    fn ramp_up_keeps_the_totals_exact() {
+        // the last of 32 rounds cut short, so a lost or repeated round would show
+        let params = SearchParams {
+            max_attempts: Some(0x80 * 31 + 5),
+            ..trailing_params(0x80, 32)
+        };
+        let unramped = Searcher::with_deriver(4, false, SaltDeriver)
+            .unwrap()
+            .search(params);
+        for num_threads in [1, 4] {
+            let mut searcher = Searcher::with_deriver(num_threads, false, SaltDeriver).unwrap();
+            searcher.set_ramp_up(Duration::from_millis(200));
+            let start = Instant::now();
+            assert_eq!(searcher.search(params), unramped, "{} threads", num_threads);
+            assert_eq!(searcher.total_attempts(), 0x80 * 31 + 5);
+            assert_eq!(*searcher.total_rounds.lock().unwrap(), 32);
+            // a search after the ramp-up is as exact
+            if start.elapsed() < Duration::from_millis(200) {
+                thread::sleep(Duration::from_millis(200) - start.elapsed());
+            }
+            searcher.reset();
+            assert_eq!(searcher.search(params), unramped);
+            assert_eq!(searcher.total_attempts(), 0x80 * 31 + 5);
+        }
+    }
+
+    /// `SaltDeriver`, but holding the round at salt `gate` until the test has looked
+    struct GatedDeriver {
+        gate: u64,
+        reached: Arc<std::sync::Barrier>,
+        resume: Arc<std::sync::Barrier>,
+    }
+
+    impl AddressDeriver for GatedDeriver {
+        fn derive(&self, factory: Address, salt: &[u8], init_code_hash: &[u8]) -> Address {
+            if salt[24..] == self.gate.to_be_bytes() {
+                self.reached.wait();
+                self.resume.wait();
+            }
+            SaltDeriver.derive(factory, salt, init_code_hash)
+        }
+    }
+
+    #[test]
+    fn live_attempts_advance_within_a_round() {
+        let reached = Arc::new(std::sync::Barrier::new(2));
+        let resume = Arc::new(std::sync::Barrier::new(2));
+        let interval = PROGRESS_INTERVAL as u64;
+        // the first salt is 1, so this is the salt after 3 intervals of attempts
+        let deriver = GatedDeriver {
+            gate: 1 + 3 * interval,
+            reached: reached.clone(),
+            resume: resume.clone(),
+        };
+        let searcher = Searcher::with_deriver(1, false, deriver).unwrap();
+        let params = trailing_params(16 * PROGRESS_INTERVAL, 1);
+        thread::scope(|scope| {
+            let search = scope.spawn(|| searcher.search(params));
+            reached.wait();
+            // the one round is still running, its attempts aren't in the total yet
+            assert_eq!(searcher.total_attempts(), 0);
+            assert_eq!(searcher.live_attempts(), 3 * PROGRESS_INTERVAL);
+            resume.wait();
+            search.join().unwrap().unwrap();
+        });
+        assert_eq!(searcher.total_attempts(), 16 * PROGRESS_INTERVAL);
+        assert_eq!(searcher.live_attempts(), 16 * PROGRESS_INTERVAL);
+    }
+
+    #[test]
+    fn first_threshold_returns_the_first_address_meeting_the_target() {
+        let searcher = Searcher::with_deriver(1, false, SaltDeriver).unwrap();
+        let found = searcher
+            .search(SearchParams {
+                target_score: Some(1),
+                threshold_return: ThresholdReturn::First,
+                ..trailing_params(0x1ff, 1)
+            })
+            .unwrap();
+        assert_eq!(found.salt_n, U256::from(0x10));
+        assert!(searcher.target_reached());
+    }
+
+    #[test]
+    fn round_best_threshold_finishes_the_round() {
+        let searcher = Searcher::with_deriver(1, false, SaltDeriver).unwrap();
+        let found = searcher
+            .search(SearchParams {
+                target_score: Some(1),
+                ..trailing_params(0x1ff, 1)
+            })
+            .unwrap();
+        assert_eq!(found.salt_n, U256::from(0x100));
+        assert_eq!(searcher.total_attempts(), 0x1ff);
+    }
+
+    #[test]
+    fn rounds_after_the_target_are_skipped() {
+        // one thread runs the rounds in order, the second reaches the target
+        let searcher = Searcher::with_deriver(1, false, SaltDeriver).unwrap();
+        let found = searcher
+            .search(SearchParams {
+                target_score: Some(2),
+                ..trailing_params(0x80, 4)
+            })
+            .unwrap();
+        assert_eq!(found.salt_n, U256::from(0x100));
+        assert!(searcher.target_reached());
+        assert_eq!(searcher.total_attempts(), 0x100);
+    }
+
+    #[test]
+    fn search_of_only_skipped_salts_finds_nothing() {
+        let mut searcher = Searcher::with_deriver(2, false, SaltDeriver).unwrap();
+        searcher.skip([(U256::from(7), U256::from(27))]);
+        assert_eq!(searcher.search(params(10, 2)), None);
+        // a seeded best is returned instead
+        let best = AddressSalt {
+            address: Address::from_low_u64_be(1),
+            salt_n: U256::one(),
+        };
+        searcher.reset();
+        searcher.seed_best(best);
+        assert_eq!(searcher.search(params(10, 2)), Some(best));
+    }
+
+    #[test]
+    fn denied_addresses_never_win() {
+        // the salt is the address, so the best is the lowest salt which isn't denied
+        let mut searcher = Searcher::with_deriver(2, false, SaltDeriver).unwrap();
+        searcher.deny((7..17).map(Address::from_low_u64_be));
+        let found = searcher.search(params(10, 2)).unwrap();
+        assert_eq!(found.address, Address::from_low_u64_be(17));
+    }
+
+    #[test]
+    fn search_stops_at_exactly_max_attempts() {
+        let params = SearchParams {
+            max_attempts: Some(25),
+            ..params(10, 3)
+        };
+        assert_eq!(Searcher::round_params(&params, 2).round_size, 5);
+        // the first 24 salts are denied, so the best is the 25th, the last one scanned
+        let mut searcher = Searcher::with_deriver(2, false, SaltDeriver).unwrap();
+        searcher.deny((7..31).map(Address::from_low_u64_be));
+        let found = searcher.search(params).unwrap();
+        assert_eq!(found.salt_n, U256::from(31));
+        assert_eq!(searcher.total_attempts(), 25);
+    }
+
+    #[test]
+    fn searcher_rejects_zero_threads() {
+        assert!(matches!(
+            Searcher::new(0, false),
+            Err(SearchError::InvalidThreadCount(0))
+        ));
+    }
+
+    #[test]
+    fn searcher_runs_on_the_threads_asked_for() {
+        assert_eq!(Searcher::new(3, false).unwrap().num_threads(), 3);
+    }
+
+    /// `SaltDeriver`, recording the most threads of the pool it was called on
+    #[derive(Default)]
+    struct PoolDeriver {
+        pool_threads: AtomicUsize,
+    }
+
+    impl AddressDeriver for PoolDeriver {
+        fn derive(&self, factory: Address, salt: &[u8], init_code_hash: &[u8]) -> Address {
+            self.pool_threads
+                .fetch_max(rayon::current_num_threads(), Ordering::Relaxed);
+            SaltDeriver.derive(factory, salt, init_code_hash)
+        }
+    }
+
+    #[test]
+    fn global_and_dedicated_pools_search_the_same() {
+        let dedicated = Searcher::with_deriver(3, false, PoolDeriver::default()).unwrap();
+        let global = Searcher::with_global_pool(PoolDeriver::default());
+        assert_eq!(global.num_threads(), rayon::current_num_threads());
+
+        let params = params(500, 8);
+        let found = dedicated.search(params);
+        assert!(found.is_some());
+        assert_eq!(global.search(params), found);
+        assert_eq!(dedicated.total_attempts(), 4000);
+        assert_eq!(global.total_attempts(), 4000);
+        // each ran its rounds on its own pool
+        let pool_threads = |searcher: &Searcher<PoolDeriver>| {
+            searcher.deriver.pool_threads.load(Ordering::Relaxed)
+        };
+        assert_eq!(pool_threads(&dedicated), 3);
+        assert_eq!(pool_threads(&global), rayon::current_num_threads());
+    }
+
+    #[test]
+    fn retry_with_backoff_retries_until_success() {
+        let mut calls = 0;
+        let result: Result<u32, String> =
+            retry_with_backoff(3, Duration::from_millis(1), "test", || {
+                calls += 1;
+                if calls < 3 {
+                    return Err(format!("failure {}", calls));
+                }
+                Ok(calls)
+            });
+        assert_eq!(result, Ok(3));
+    }
+
+    #[test]
+    fn retry_with_backoff_returns_the_last_error() {
+        let mut calls = 0;
+        let result: Result<(), String> =
+            retry_with_backoff(3, Duration::from_millis(1), "test", || {
+                calls += 1;
+                Err(format!("failure {}", calls))
+            });
+        assert_eq!(result, Err("failure 3".to_string()));
+        assert_eq!(calls, 3);
+    }
 }
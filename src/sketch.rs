@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+/// Sub-buckets per power of two, the relative error of a quantile is at most 1 / this
+const SUB_BUCKETS: u64 = 16;
+
+/// log2 of `SUB_BUCKETS`
+const SUB_BUCKET_BITS: u32 = 4;
+
+/// An approximate distribution of durations in constant memory, to report quantiles
+/// of round times over runs too long to keep every one
+///
+/// Durations are counted in log-linear buckets of microseconds: exact below 16µs, then
+/// 16 buckets per power of two, so a quantile is within 1/16 of the true value.
+/// The minimum and maximum are kept exactly.
+#[derive(Clone, Default)]
+pub struct DurationSketch {
+    /// Count per bucket, grown as longer durations are recorded
+    buckets: Vec<u64>,
+    count: u64,
+    min: Option<Duration>,
+    max: Duration,
+}
+
+impl DurationSketch {
+    /// Record one duration
+    ///
+    /// # Arguments
+    /// * `duration` - The duration to count
+    pub fn record(&mut self, duration: Duration) {
+        let index = bucket_index(duration.as_micros().min(u64::MAX as u128) as u64);
+        if index >= self.buckets.len() {
+            self.buckets.resize(index + 1, 0);
+        }
+        self.buckets[index] += 1;
+        self.count += 1;
+        self.min = Some(self.min.map_or(duration, |min| min.min(duration)));
+        self.max = self.max.max(duration);
+    }
+
+    /// Combine with the sketch of another set of durations, as if one sketch had
+    /// recorded both
+    ///
+    /// # Arguments
+    /// * `other` - The sketch to merge in
+    pub fn merge(&mut self, other: &DurationSketch) {
+        if other.buckets.len() > self.buckets.len() {
+            self.buckets.resize(other.buckets.len(), 0);
+        }
+        for (total, count) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *total += count;
+        }
+        self.count += other.count;
+        self.min = match (self.min, other.min) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max = self.max.max(other.max);
+    }
+
+    /// The number of durations recorded
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The shortest duration recorded, exactly
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    /// The longest duration recorded, exactly
+    pub fn max(&self) -> Option<Duration> {
+        self.min.map(|_| self.max)
+    }
+
+    /// The approximate duration below which a fraction `q` of the durations fall
+    ///
+    /// # Arguments
+    /// * `q` - The quantile, 0.5 for the median, 0.99 for the 99th percentile
+    ///
+    /// # Returns
+    /// * The midpoint of the bucket holding the quantile, clamped to the exact
+    ///   minimum and maximum, or `None` if nothing was recorded
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let (min, max) = (self.min?, self.max);
+        // the rank of the quantile, 1-based
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let (low, width) = bucket_bounds(index);
+                let micros = Duration::from_micros(low + width / 2);
+                return Some(micros.clamp(min, max));
+            }
+        }
+        Some(max)
+    }
+}
+
+/// The bucket counting a duration in microseconds
+fn bucket_index(micros: u64) -> usize {
+    if micros < SUB_BUCKETS {
+        return micros as usize;
+    }
+    let exponent = 63 - micros.leading_zeros();
+    let mantissa = (micros >> (exponent - SUB_BUCKET_BITS)) & (SUB_BUCKETS - 1);
+    (SUB_BUCKETS + (exponent - SUB_BUCKET_BITS) as u64 * SUB_BUCKETS + mantissa) as usize
+}
+
+/// The first duration in microseconds counted by a bucket, and how many it spans
+fn bucket_bounds(index: usize) -> (u64, u64) {
+    let index = index as u64;
+    if index < SUB_BUCKETS {
+        return (index, 1);
+    }
+    let shift = (index - SUB_BUCKETS) / SUB_BUCKETS;
+    let mantissa = (index - SUB_BUCKETS) % SUB_BUCKETS;
+    ((SUB_BUCKETS + mantissa) << shift, 1 << shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Durations spread log-uniformly from 1µs to 1s, like rounds of very uneven cost
+    fn synthetic_durations(n: usize) -> Vec<Duration> {
+        let mut rng = StdRng::seed_from_u64(154);
+        (0..n)
+            .map(|_| Duration::from_micros(10f64.powf(rng.gen::<f64>() * 6.0) as u64))
+            .collect()
+    }
+
+    #[test]
+    fn quantiles_are_within_a_sixteenth() {
+        let durations = synthetic_durations(10_000);
+        let mut sketch = DurationSketch::default();
+        for duration in &durations {
+            sketch.record(*duration);
+        }
+        let mut sorted = durations.clone();
+        sorted.sort();
+        assert_eq!(sketch.count(), 10_000);
+        assert_eq!(sketch.min(), sorted.first().copied());
+        assert_eq!(sketch.max(), sorted.last().copied());
+        for q in [0.01, 0.1, 0.5, 0.9, 0.99] {
+            let exact = sorted[(q * sorted.len() as f64).ceil() as usize - 1].as_secs_f64();
+            let approx = sketch.quantile(q).unwrap().as_secs_f64();
+            assert!(
+                (approx - exact).abs() <= exact / SUB_BUCKETS as f64,
+                "quantile {}: {}s approximates {}s",
+                q,
+                approx,
+                exact
+            );
+        }
+    }
+
+    #[test]
+    fn merged_sketches_match_one_sketch() {
+        let durations = synthetic_durations(1_000);
+        let mut whole = DurationSketch::default();
+        let (mut first, mut second) = (DurationSketch::default(), DurationSketch::default());
+        for (i, duration) in durations.iter().enumerate() {
+            whole.record(*duration);
+            match i % 2 {
+                0 => first.record(*duration),
+                _ => second.record(*duration),
+            }
+        }
+        first.merge(&second);
+        assert_eq!(first.count(), whole.count());
+        assert_eq!((first.min(), first.max()), (whole.min(), whole.max()));
+        for q in [0.0, 0.5, 0.99, 1.0] {
+            assert_eq!(first.quantile(q), whole.quantile(q));
+        }
+    }
+
+    #[test]
+    fn empty_sketch_has_no_quantiles() {
+        let sketch = DurationSketch::default();
+        assert_eq!(sketch.quantile(0.5), None);
+        assert_eq!(sketch.max(), None);
+    }
+}
@@ -0,0 +1,104 @@
+use std::{fmt, str::FromStr};
+
+use ethers_core::types::Address;
+
+/// Well-known proxy contracts whose init code can be assembled from their parameters,
+/// so the init code hash doesn't have to be computed from hand-crafted bytecode
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Template {
+    /// EIP-1167 minimal proxy (a "clone") delegating to an implementation
+    Eip1167,
+    /// EIP-7511 minimal proxy, the EIP-1167 proxy rewritten with PUSH0
+    Eip7511,
+}
+
+impl Template {
+    /// The names accepted by `from_str`
+    pub const NAMES: [&'static str; 2] = ["eip1167", "eip7511"];
+
+    /// Assemble the creation bytecode of the proxy
+    ///
+    /// # Arguments
+    /// * `implementation` - The contract the proxy delegates calls to
+    ///
+    /// # Returns
+    /// * The init code, i.e. the creation bytecode with the implementation embedded
+    pub fn init_code(&self, implementation: Address) -> Vec<u8> {
+        // creation code and the runtime code around the PUSH20 of the implementation
+        let (prefix, suffix): (&str, &str) = match self {
+            Template::Eip1167 => (
+                "3d602d80600a3d3981f3363d3d373d3d3d363d73",
+                "5af43d82803e903d91602b57fd5bf3",
+            ),
+            Template::Eip7511 => (
+                "602c8060095f395ff3365f5f375f5f365f73",
+                "5af43d5f5f3e6029573d5ffd5b3d5ff3",
+            ),
+        };
+        [
+            hex::decode(prefix).unwrap().as_slice(),
+            implementation.as_bytes(),
+            hex::decode(suffix).unwrap().as_slice(),
+        ]
+        .concat()
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "eip1167" => Ok(Template::Eip1167),
+            "eip7511" => Ok(Template::Eip7511),
+            _ => Err(format!(
+                "unknown template {}, expected one of {}",
+                s,
+                Template::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Template::Eip1167 => write!(f, "eip1167"),
+            Template::Eip7511 => write!(f, "eip7511"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ethers_core::utils::keccak256;
+
+    /// The implementation the EIPs use in their examples
+    const IMPLEMENTATION: &str = "0xbebebebebebebebebebebebebebebebebebebebe";
+
+    #[test]
+    fn templates_match_the_reference_bytecode() {
+        let implementation: Address = IMPLEMENTATION.parse().unwrap();
+        let cases = [
+            (
+                Template::Eip1167,
+                "3d602d80600a3d3981f3363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe\
+                 5af43d82803e903d91602b57fd5bf3",
+                "60a146579875aca032c9566ded6fc12bea362d66b7f2aa0f440db7bd8a90a4fe",
+            ),
+            (
+                Template::Eip7511,
+                "602c8060095f395ff3365f5f375f5f365f73bebebebebebebebebebebebebebebebebebebebe\
+                 5af43d5f5f3e6029573d5ffd5b3d5ff3",
+                "974a2d18c297668cdbac3cb3047a72e8becd45310451eb5ac26c8754659e46b5",
+            ),
+        ];
+        for (template, init_code, hash) in cases {
+            let assembled = template.init_code(implementation);
+            assert_eq!(hex::encode(&assembled), init_code, "{}", template);
+            assert_eq!(hex::encode(keccak256(&assembled)), hash, "{}", template);
+        }
+    }
+}
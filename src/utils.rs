@@ -1,4 +1,15 @@
-use ethers_core::types::{Address, Bytes, U256};
+use std::{fs, io, num::NonZeroUsize, ops::Range, path::Path, time::Duration};
+
+use ethers_core::{
+    types::{Address, Bytes, U256},
+    utils::{get_create2_address_from_hash, to_checksum},
+};
+
+use rayon::prelude::*;
+
+use crate::error::SearchError;
+use crate::hash::HashAlgo;
+use crate::score::ScoreMode;
 
 /// Convert a U256 to a 32-byte array
 ///
@@ -14,13 +25,442 @@ pub fn bytes32(n: U256) -> Bytes {
     Bytes::from(bytes)
 }
 
+/// Stands in for a salt in the logs of a search run with `--mask-salts`
+pub const MASKED_SALT: &str = "0x<masked>";
+
+/// Format a salt as 0x-prefixed hex, or as `MASKED_SALT` to keep it out of the logs
+///
+/// # Arguments
+/// * `salt_n` - The salt as a number
+/// * `mask` - Whether to hide it
+pub fn fmt_salt(salt_n: U256, mask: bool) -> String {
+    if mask {
+        return MASKED_SALT.to_string();
+    }
+    format!("0x{}", hex::encode(bytes32(salt_n)))
+}
+
+/// Number of hex characters (nibbles) in an address
+pub const ADDRESS_NIBBLES: usize = 40;
+
+/// Number of bytes in an address
+pub const ADDRESS_BYTES: usize = 20;
+
+/// Number of threads used when the number of available cores can't be detected
+pub const FALLBACK_NUM_THREADS: usize = 4;
+
+/// The number of threads to use when none is configured, one per available core
+///
+/// # Returns
+/// The available parallelism, or `FALLBACK_NUM_THREADS` if it can't be detected
+///
+pub fn default_num_threads() -> usize {
+    num_threads_or_fallback(std::thread::available_parallelism())
+}
+
+/// Pick the detected number of threads or fall back, split out of
+/// `default_num_threads` so the fallback doesn't depend on the platform
+///
+/// # Arguments
+/// * `detected` - The result of `std::thread::available_parallelism`
+///
+/// # Returns
+/// The detected parallelism, or `FALLBACK_NUM_THREADS` if detection failed
+///
+pub fn num_threads_or_fallback(detected: io::Result<NonZeroUsize>) -> usize {
+    match detected {
+        Ok(n) => n.get(),
+        Err(e) => {
+            eprintln!(
+                "Could not detect the number of cores ({}), using {} threads",
+                e, FALLBACK_NUM_THREADS
+            );
+            FALLBACK_NUM_THREADS
+        }
+    }
+}
+
+/// The EIP-2470 singleton factory, deployed at the same address on every chain
+/// it takes any salt, so it is searched in full-salt mode
+pub const EIP2470_FACTORY: &str = "0xce0042B868300000d44A59004Da54A005ffdcf9f";
+
+/// The ImmutableCreate2Factory, deployed at the same address on most chains
+pub const IMMUTABLE_CREATE2_FACTORY: &str = "0x0000000000FFe8B47B3e2130213B802212439497";
+
+/// Look up a factory known by name
+///
+/// # Arguments
+/// * `name` - The name given in place of a factory address, e.g. `eip2470`
+///
+/// # Returns
+/// The canonical address of the factory, or `None` if the name isn't known
+///
+pub fn known_factory(name: &str) -> Option<Address> {
+    match name {
+        "eip2470" => Some(EIP2470_FACTORY.parse().unwrap()),
+        _ => None,
+    }
+}
+
+/// Strip the surrounding whitespace and a 0x or 0X prefix from a hex input,
+/// the single place hex inputs are normalized
+pub fn strip_hex_prefix(input: &str) -> &str {
+    let input = input.trim();
+    input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input)
+}
+
+/// Decode a hex argument into bytes
+///
+/// # Arguments
+/// * `name` - The name of the argument, used in the error message
+/// * `input` - The hex string in any case, with or without a 0x or 0X prefix
+///
+/// # Returns
+/// The decoded bytes, or an error naming the argument if it isn't valid hex
+/// or has an odd number of digits
+///
+pub fn parse_hex(name: &str, input: &str) -> Result<Vec<u8>, SearchError> {
+    hex::decode(strip_hex_prefix(input))
+        .map_err(|e| SearchError::InvalidHex(format!("{} {}: {}", name, input, e)))
+}
+
+/// Parse a hex number of up to 32 bytes, such as a salt or an init code hash
+///
+/// # Arguments
+/// * `name` - The name of the argument, used in the error message
+/// * `input` - The hex number in any case, with or without a 0x or 0X prefix,
+///   leading zeros may be left out so any number of digits is accepted
+///
+/// # Returns
+/// The number, or an error naming the argument if it isn't valid hex or is too large
+///
+pub fn parse_hex_u256(name: &str, input: &str) -> Result<U256, SearchError> {
+    let digits = strip_hex_prefix(input);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(SearchError::InvalidHex(format!(
+            "{} {}: expected hex digits",
+            name, input
+        )));
+    }
+    U256::from_str_radix(digits, 16)
+        .map_err(|e| SearchError::InvalidHex(format!("{} {}: {}", name, input, e)))
+}
+
+/// Parse a hex address, checksummed or not
+///
+/// # Arguments
+/// * `name` - The name of the argument, used in the error message
+/// * `input` - The address in any case, with or without a 0x or 0X prefix
+///
+/// # Returns
+/// The address, or an error naming the argument if it isn't 20 bytes of hex
+///
+pub fn parse_address(name: &str, input: &str) -> Result<Address, SearchError> {
+    let bytes = parse_hex(name, input)?;
+    if bytes.len() != ADDRESS_BYTES {
+        return Err(SearchError::InvalidHex(format!(
+            "{} {}: expected {} bytes, got {}",
+            name,
+            input,
+            ADDRESS_BYTES,
+            bytes.len()
+        )));
+    }
+    Ok(Address::from_slice(&bytes))
+}
+
+/// Compute the init code hash of a contract from its creation bytecode
+/// and ABI-encoded constructor arguments
+///
+/// # Arguments
+/// * `bytecode` - The creation bytecode
+/// * `constructor_args` - The ABI-encoded constructor arguments, empty if there are none
+/// * `hash_algo` - The hash of the factory, keccak256 for a standard CREATE2
+///
+/// # Returns
+/// The hash of the bytecode followed by the constructor arguments
+///
+pub fn init_code_hash(bytecode: &[u8], constructor_args: &[u8], hash_algo: HashAlgo) -> U256 {
+    let init_code = [bytecode, constructor_args].concat();
+    U256::from_big_endian(&hash_algo.digest(&init_code))
+}
+
+/// The largest init code file read, in bytes of hex, well past the 49,152 bytes EIP-3860
+/// allows init code, so a larger file is a mistake, e.g. a binary or a whole build directory
+pub const MAX_INIT_CODE_FILE_BYTES: u64 = 1_000_000;
+
+/// Read creation bytecode written as hex in a file, e.g. the bytecode of a build artifact
+///
+/// The size is checked before reading, so a huge file picked by mistake is never decoded.
+///
+/// # Arguments
+/// * `path` - The path of the file, the hex may have a 0x prefix and surrounding whitespace
+///
+/// # Returns
+/// The bytecode, or an error if the file is over `MAX_INIT_CODE_FILE_BYTES`, is binary,
+/// isn't hex or is empty
+///
+pub fn read_init_code_file(path: &Path) -> Result<Vec<u8>, SearchError> {
+    let size = fs::metadata(path).map_err(SearchError::Io)?.len();
+    if size > MAX_INIT_CODE_FILE_BYTES {
+        return Err(SearchError::InvalidArgument(format!(
+            "init code file {} is {}, over the {} limit, init code is far smaller",
+            path.display(),
+            fmt_bytes(size as f64),
+            fmt_bytes(MAX_INIT_CODE_FILE_BYTES as f64)
+        )));
+    }
+    let contents = fs::read(path).map_err(SearchError::Io)?;
+    let contents = String::from_utf8(contents).map_err(|_| {
+        SearchError::InvalidArgument(format!(
+            "init code file {} is binary, expected the bytecode written as hex",
+            path.display()
+        ))
+    })?;
+    // not parse_hex, whose error would quote the whole file
+    let init_code = hex::decode(strip_hex_prefix(&contents)).map_err(|e| {
+        SearchError::InvalidHex(format!("init code file {}: {}", path.display(), e))
+    })?;
+    if init_code.is_empty() {
+        return Err(SearchError::EmptyBytecode);
+    }
+    Ok(init_code)
+}
+
+/// Read a list of addresses, one per line
+///
+/// # Arguments
+/// * `path` - The path of the file, blank lines and lines starting with `#` are skipped
+///
+/// # Returns
+/// The addresses, or an error naming the line of the first invalid address
+///
+pub fn read_address_list(path: &Path) -> Result<Vec<Address>, SearchError> {
+    let contents = fs::read_to_string(path).map_err(SearchError::Io)?;
+    contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| {
+            parse_address("address", line).map_err(|e| {
+                SearchError::InvalidArgument(format!("{}:{}: {}", path.display(), line_number, e))
+            })
+        })
+        .collect()
+}
+
+/// Read a list of salts, one hex number per line
+///
+/// # Arguments
+/// * `path` - The path of the file, blank lines and lines starting with `#` are skipped
+///
+/// # Returns
+/// The salts, or an error naming the line of the first invalid salt
+///
+pub fn read_salt_list(path: &Path) -> Result<Vec<U256>, SearchError> {
+    read_hex_list(path, "salt")
+}
+
+/// Read a list of candidate init code hashes, one per line, as `read_salt_list`
+pub fn read_hash_list(path: &Path) -> Result<Vec<U256>, SearchError> {
+    read_hex_list(path, "init code hash")
+}
+
+/// Read a list of `salt,address` pairs, one per line, e.g. results mined earlier
+///
+/// # Arguments
+/// * `path` - The path of the file, blank lines and lines starting with `#` are skipped
+///
+/// # Returns
+/// The salts with their expected addresses, or an error naming the line of the first invalid pair
+///
+pub fn read_salt_address_list(path: &Path) -> Result<Vec<(U256, Address)>, SearchError> {
+    let contents = fs::read_to_string(path).map_err(SearchError::Io)?;
+    contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| {
+            let pair = match line.split_once(',') {
+                Some((salt, address)) => parse_hex_u256("salt", salt.trim())
+                    .and_then(|salt_n| Ok((salt_n, parse_address("address", address.trim())?))),
+                None => Err(SearchError::InvalidArgument(
+                    "expected salt,address".to_string(),
+                )),
+            };
+            pair.map_err(|e| {
+                SearchError::InvalidArgument(format!("{}:{}: {}", path.display(), line_number, e))
+            })
+        })
+        .collect()
+}
+
+fn read_hex_list(path: &Path, name: &str) -> Result<Vec<U256>, SearchError> {
+    let contents = fs::read_to_string(path).map_err(SearchError::Io)?;
+    contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| {
+            parse_hex_u256(name, line).map_err(|e| {
+                SearchError::InvalidHex(format!("{}:{}: {}", path.display(), line_number, e))
+            })
+        })
+        .collect()
+}
+
+/// Find which of a list of init code hashes a deployed address was created with,
+/// knowing its factory and salt
+///
+/// keccak256 can't be inverted, so the hash is only found if it is among the candidates.
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address
+/// * `salt_n` - The salt as a number
+/// * `address` - The deployed address
+/// * `candidates` - The init code hashes to try
+///
+/// # Returns
+/// * The first candidate deriving `address`, or `None` if none does
+pub fn recover_init_code_hash(
+    factory: Address,
+    salt_n: U256,
+    address: Address,
+    candidates: &[U256],
+) -> Option<U256> {
+    candidates
+        .par_iter()
+        .find_first(|init_code_hash| create2_address(factory, salt_n, **init_code_hash) == address)
+        .copied()
+}
+
+/// Init code hashes that can't belong to a real deployment, with the likely mistake
+const SUSPICIOUS_INIT_CODE_HASHES: [(&str, &str); 2] = [
+    (
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+        "it is the keccak256 of empty bytes, was the bytecode empty?",
+    ),
+    (
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "it is zero, was the hash left unset?",
+    ),
+];
+
+/// Check an init code hash against known-bad values, a best-effort
+/// guard since the intended contract is unknown
+///
+/// # Arguments
+/// * `init_code_hash` - The init code hash as a number
+///
+/// # Returns
+/// Why the hash is suspicious, or `None` if it isn't a known-bad value
+///
+pub fn suspicious_init_code_hash(init_code_hash: U256) -> Option<&'static str> {
+    let hash = hex::encode(bytes32(init_code_hash));
+    SUSPICIOUS_INIT_CODE_HASHES
+        .iter()
+        .find(|(known, _)| *known == hash)
+        .map(|(_, reason)| *reason)
+}
+
+/// The length of the bytes CREATE2 hashes, 1 + 20 + 32 + 32
+pub const CREATE2_PREIMAGE_LEN: usize = 85;
+
+/// The fields of a CREATE2 preimage in order, each with its byte range
+pub const CREATE2_PREIMAGE_LAYOUT: [(&str, Range<usize>); 4] = [
+    ("0xff", 0..1),
+    ("factory", 1..21),
+    ("salt", 21..53),
+    ("init code hash", 53..85),
+];
+
+/// Assemble the bytes CREATE2 hashes, whose keccak256 ends in the address
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address
+/// * `salt` - The 32-byte salt given to CREATE2
+/// * `init_code_hash` - The 32-byte init code hash
+///
+/// # Returns
+/// * 0xff ++ factory ++ salt ++ init code hash, laid out as `CREATE2_PREIMAGE_LAYOUT`
+pub fn create2_preimage(
+    factory: Address,
+    salt: &[u8],
+    init_code_hash: &[u8],
+) -> [u8; CREATE2_PREIMAGE_LEN] {
+    let mut preimage = [0u8; CREATE2_PREIMAGE_LEN];
+    preimage[0] = 0xff;
+    preimage[1..21].copy_from_slice(factory.as_bytes());
+    preimage[21..53].copy_from_slice(salt);
+    preimage[53..85].copy_from_slice(init_code_hash);
+    return preimage;
+}
+
+/// Compute the CREATE2 address for a single salt
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address
+/// * `salt_n` - The salt as a number
+/// * `init_code_hash` - The init code hash as a number
+///
+/// # Returns
+/// The address the factory would deploy to
+///
+pub fn create2_address(factory: Address, salt_n: U256, init_code_hash: U256) -> Address {
+    get_create2_address_from_hash(factory, bytes32(salt_n), bytes32(init_code_hash))
+}
+
+/// Compute the CREATE2 addresses for many salts at once, in parallel
+/// e.g. to precompute counterfactual addresses without running a search
+///
+/// # Arguments
+/// * `factory` - The CREATE2 factory address
+/// * `init_code_hash` - The init code hash as a number
+/// * `salts` - The salts as numbers
+///
+/// # Returns
+/// The addresses, in the same order as `salts`
+///
+pub fn compute_batch(factory: Address, init_code_hash: U256, salts: &[U256]) -> Vec<Address> {
+    salts
+        .par_iter()
+        .map(|salt_n| create2_address(factory, *salt_n, init_code_hash))
+        .collect()
+}
+
+/// Format an address and salt the way foundry's `cast create2` prints them,
+/// so the output of both tools can be diffed
+///
+/// # Arguments
+/// * `address` - The derived address
+/// * `salt_n` - The salt as a number
+///
+/// # Returns
+/// The two lines "Address: <checksummed address>" and "Salt: 0x<salt> (<decimal salt>)"
+pub fn fmt_cast_create2(address: Address, salt_n: U256) -> String {
+    format!(
+        "Address: {}\nSalt: 0x{} ({})",
+        to_checksum(&address, None),
+        hex::encode(bytes32(salt_n)),
+        salt_n
+    )
+}
+
 /// Count the number of leading zeroes in an address
+/// zeroes are hex characters, i.e. nibbles, the unit the 16^zeros estimates assume
 ///
 /// # Arguments
 /// * `address` - The address to count the leading zeroes in
 ///
 /// # Returns
-/// The number of leading zeroes
+/// The number of leading zero nibbles, 0 through 40 where 40 is the zero address,
+/// always equal to `leading_zero_nibbles`
 ///
 pub fn count_leading_zeroes(address: Address) -> u8 {
     let mut leading_zeros = 0;
@@ -34,6 +474,42 @@ pub fn count_leading_zeroes(address: Address) -> u8 {
     leading_zeros
 }
 
+/// Interpret the 20 address bytes as a big-endian integer
+///
+/// # Arguments
+/// * `address` - The address to convert
+///
+/// # Returns
+/// The numeric value of the address
+///
+pub fn address_to_u256(address: Address) -> U256 {
+    U256::from_big_endian(address.as_bytes())
+}
+
+/// Count the number of leading zero nibbles in an address
+/// directly on its bytes, cheap enough to call for every address in the hot loop
+///
+/// # Arguments
+/// * `address` - The address to count the leading zero nibbles in
+///
+/// # Returns
+/// The number of leading zero nibbles, the same as `count_leading_zeroes`
+///
+pub fn leading_zero_nibbles(address: Address) -> u8 {
+    let mut nibbles = 0;
+    for byte in address.as_bytes() {
+        if *byte == 0 {
+            nibbles += 2;
+        } else {
+            if *byte < 0x10 {
+                nibbles += 1;
+            }
+            break;
+        }
+    }
+    nibbles
+}
+
 /// Format a number of seconds into days, hours, minutes, seconds
 ///
 /// # Arguments
@@ -50,3 +526,416 @@ pub fn fmt_dms(seconds: u128) -> String {
     let seconds = seconds % 60;
     format!("{}d{}h{}m{}s", days, hours, minutes, seconds)
 }
+
+/// Format a number of bytes with a decimal unit, e.g. "1.5 GB"
+///
+/// # Arguments
+/// * `bytes` - The number of bytes to format
+///
+/// # Returns
+/// A string with one decimal and the largest unit keeping the number at least 1
+pub fn fmt_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 7] = ["B", "KB", "MB", "GB", "TB", "PB", "EB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        return format!("{} {}", value, UNITS[unit]);
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// The core-hours needed to make a number of attempts at a measured rate
+///
+/// Used to extrapolate the cost of one more leading zero, which takes 16 times
+/// the attempts of the last, from the rate the run achieved.
+///
+/// # Arguments
+/// * `attempts` - The number of attempts to make
+/// * `rate` - The measured rate across all threads, in attempts per second
+/// * `num_threads` - The number of threads the rate was measured with
+///
+/// # Returns
+/// * The wall-clock hours to make the attempts times the number of threads
+pub fn core_hours(attempts: f64, rate: f64, num_threads: usize) -> f64 {
+    attempts / rate * num_threads as f64 / 3600.0
+}
+
+/// The expected time to score one and two more than the current best
+///
+/// Each extra leading zero takes 16 times the attempts, so one more takes roughly 16 times
+/// as long as reaching the current count did, and two more 256 times. Other score modes
+/// grow by their own `ScoreMode::expected_attempts`, e.g. far less per zero byte.
+///
+/// # Arguments
+/// * `score_mode` - How the best is scored
+/// * `current_score` - The score of the best address so far
+/// * `rate` - The measured rate in attempts per second
+/// * `elapsed` - The time searched so far, the estimate falls back to scaling this by the
+///   growth in expected attempts when no rate has been measured yet
+///
+/// # Returns
+/// * The expected seconds for one more and for two more
+pub fn marginal_time_for_extra_score(
+    score_mode: ScoreMode,
+    current_score: u8,
+    rate: f64,
+    elapsed: Duration,
+) -> [u128; 2] {
+    let current = score_mode.expected_attempts(current_score);
+    [1, 2].map(|extra| {
+        let attempts = score_mode.expected_attempts(current_score.saturating_add(extra));
+        if rate > 0.0 && rate.is_finite() {
+            (attempts / rate) as u128
+        } else {
+            (elapsed.as_secs_f64() * attempts / current) as u128
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file in the temp directory, unique to the test
+    fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("search-create2-{}-{}", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn init_code_file_is_decoded() {
+        let path = temp_file("init-code", b"0x6080604052\n");
+        let init_code = read_init_code_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(init_code.unwrap(), vec![0x60, 0x80, 0x60, 0x40, 0x52]);
+    }
+
+    #[test]
+    fn oversized_init_code_file_is_rejected() {
+        let path = temp_file(
+            "oversized-init-code",
+            &vec![b'0'; MAX_INIT_CODE_FILE_BYTES as usize + 2],
+        );
+        let init_code = read_init_code_file(&path);
+        fs::remove_file(&path).unwrap();
+        match init_code {
+            Err(SearchError::InvalidArgument(e)) => assert!(e.contains("over the 1.0 MB limit")),
+            other => panic!("expected the file to be rejected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn binary_init_code_file_is_rejected() {
+        let path = temp_file("binary-init-code", &[0x60, 0x80, 0xff, 0xfe, 0x00]);
+        let init_code = read_init_code_file(&path);
+        fs::remove_file(&path).unwrap();
+        match init_code {
+            Err(SearchError::InvalidArgument(e)) => {
+                assert!(e.ends_with("is binary, expected the bytecode written as hex"))
+            }
+            other => panic!("expected the file to be rejected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn compute_batch_matches_single_addresses() {
+        let factory = Address::repeat_byte(0x42);
+        let init_code_hash = U256::from(0xdead_beef_u64);
+        let salts = [
+            U256::zero(),
+            U256::one(),
+            U256::from(255),
+            U256::from_big_endian(&[0xab; 32]),
+            U256::MAX,
+        ];
+        let addresses = compute_batch(factory, init_code_hash, &salts);
+        assert_eq!(addresses.len(), salts.len());
+        for (salt_n, address) in salts.iter().zip(addresses) {
+            let expected =
+                get_create2_address_from_hash(factory, bytes32(*salt_n), bytes32(init_code_hash));
+            assert_eq!(address, expected);
+        }
+    }
+
+    #[test]
+    fn create2_preimage_hashes_to_the_address() {
+        use ethers_core::utils::keccak256;
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(150);
+        for _ in 0..256 {
+            let factory = Address::from(rng.gen::<[u8; 20]>());
+            let salt: [u8; 32] = rng.gen();
+            let init_code_hash: [u8; 32] = rng.gen();
+            let hash = keccak256(create2_preimage(factory, &salt, &init_code_hash));
+            assert_eq!(
+                Address::from_slice(&hash[12..]),
+                get_create2_address_from_hash(factory, salt, init_code_hash),
+                "factory {:?}, salt 0x{}, init code hash 0x{}",
+                factory,
+                hex::encode(salt),
+                hex::encode(init_code_hash)
+            );
+        }
+    }
+
+    #[test]
+    fn marginal_time_follows_the_score_mode() {
+        let elapsed = Duration::from_secs(10);
+        assert_eq!(
+            marginal_time_for_extra_score(ScoreMode::LeadingZeros, 2, 16.0, elapsed),
+            [256, 4096]
+        );
+        // without a rate the time so far is scaled by the growth in attempts
+        assert_eq!(
+            marginal_time_for_extra_score(ScoreMode::LeadingZeros, 2, 0.0, elapsed),
+            [160, 2560]
+        );
+        // a zero byte anywhere in 20 is far more common than a leading zero nibble
+        let [one, _] = marginal_time_for_extra_score(ScoreMode::TotalZeroBytes, 0, 1.0, elapsed);
+        assert!(one < 16);
+    }
+
+    #[test]
+    fn fmt_cast_create2_matches_cast() {
+        // example 5 of EIP-1014, as `cast create2` prints it
+        let factory: Address = "0x00000000000000000000000000000000deadbeef"
+            .parse()
+            .unwrap();
+        let init_code_hash =
+            U256::from_big_endian(&ethers_core::utils::keccak256([0xde, 0xad, 0xbe, 0xef]));
+        let salt_n = U256::from(0xcafe_babe_u64);
+        assert_eq!(
+            fmt_cast_create2(create2_address(factory, salt_n, init_code_hash), salt_n),
+            "Address: 0x60f3f640a8508fC6a86d45DF051962668E1e8AC7\n\
+             Salt: 0x00000000000000000000000000000000000000000000000000000000cafebabe \
+             (3405691582)"
+        );
+    }
+
+    #[test]
+    fn address_to_u256_keeps_the_value_and_order() {
+        assert_eq!(address_to_u256(Address::zero()), U256::zero());
+        assert_eq!(
+            address_to_u256(Address::from_low_u64_be(0x1234)),
+            U256::from(0x1234)
+        );
+        let max = address_to_u256(Address::repeat_byte(0xff));
+        assert_eq!(max, (U256::one() << 160) - 1);
+        // the 20 bytes round trip through the low bytes of the number
+        let address: Address = "0x0000000000FFe8B47B3e2130213B802212439497"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Address::from_slice(&bytes32(address_to_u256(address))[12..]),
+            address
+        );
+        // numeric order is the byte order the search compares addresses in
+        let addresses = [
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(0x100),
+            address,
+            Address::repeat_byte(0x01),
+        ];
+        for pair in addresses.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(address_to_u256(pair[0]) < address_to_u256(pair[1]));
+        }
+    }
+
+    #[test]
+    fn failed_core_detection_falls_back() {
+        let detected = NonZeroUsize::new(6).unwrap();
+        assert_eq!(num_threads_or_fallback(Ok(detected)), 6);
+        let failed = io::Error::new(io::ErrorKind::Unsupported, "no cgroup");
+        assert_eq!(num_threads_or_fallback(Err(failed)), FALLBACK_NUM_THREADS);
+    }
+
+    #[test]
+    fn suspicious_init_code_hashes_are_flagged() {
+        let empty = U256::from_big_endian(&ethers_core::utils::keccak256(b""));
+        assert!(suspicious_init_code_hash(empty)
+            .unwrap()
+            .contains("empty bytes"));
+        assert!(suspicious_init_code_hash(U256::zero())
+            .unwrap()
+            .contains("zero"));
+        let real = U256::from_big_endian(&ethers_core::utils::keccak256([0xde, 0xad]));
+        assert_eq!(suspicious_init_code_hash(real), None);
+    }
+
+    #[test]
+    fn count_leading_zeroes_counts_nibbles() {
+        assert_eq!(count_leading_zeroes(Address::zero()), 40);
+        // a nonzero byte with a zero high nibble adds half a byte
+        let cases = [
+            ([0x10, 0xff], 0),
+            ([0x0f, 0xff], 1),
+            ([0x00, 0x10], 2),
+            ([0x00, 0x01], 3),
+        ];
+        for (prefix, zeros) in cases {
+            let mut bytes = [0xff; 20];
+            bytes[..2].copy_from_slice(&prefix);
+            assert_eq!(count_leading_zeroes(Address::from(bytes)), zeros);
+        }
+        assert_eq!(count_leading_zeroes(Address::from_low_u64_be(1)), 39);
+    }
+
+    #[test]
+    fn count_leading_zeroes_matches_leading_zero_nibbles() {
+        for position in 0..20 {
+            for byte in [0x01, 0x0f, 0x10, 0xff] {
+                let mut bytes = [0; 20];
+                bytes[position] = byte;
+                let address = Address::from(bytes);
+                assert_eq!(
+                    count_leading_zeroes(address),
+                    leading_zero_nibbles(address),
+                    "{:?}",
+                    address
+                );
+            }
+        }
+        assert_eq!(leading_zero_nibbles(Address::zero()), 40);
+    }
+
+    #[test]
+    fn hex_inputs_take_either_prefix_and_case() {
+        assert_eq!(strip_hex_prefix(" 0XaB "), "aB");
+        assert_eq!(strip_hex_prefix("0xaB"), "aB");
+        assert_eq!(strip_hex_prefix("aB"), "aB");
+
+        for input in ["0XdEaDbEeF", "0xDEADbeef", "deadBEEF"] {
+            assert_eq!(parse_hex("x", input).unwrap(), vec![0xde, 0xad, 0xbe, 0xef]);
+            assert_eq!(
+                parse_hex_u256("x", input).unwrap(),
+                U256::from(0xdead_beef_u64)
+            );
+        }
+        // a number may leave out a leading zero, bytes may not
+        assert!(matches!(
+            parse_hex("x", "0X123"),
+            Err(SearchError::InvalidHex(_))
+        ));
+        assert_eq!(parse_hex_u256("x", "0X123").unwrap(), U256::from(0x123));
+        assert!(parse_hex_u256("x", "0X").is_err());
+
+        let expected: Address = "0x60f3f640a8508fc6a86d45df051962668e1e8ac7"
+            .parse()
+            .unwrap();
+        for input in [
+            "0X60F3F640A8508FC6A86D45DF051962668E1E8AC7",
+            "0x60f3f640a8508fC6a86d45DF051962668E1e8AC7",
+            "60f3f640a8508fc6a86d45df051962668e1e8ac7",
+        ] {
+            assert_eq!(parse_address("x", input).unwrap(), expected);
+        }
+        // one digit short, odd, and one byte short
+        assert!(parse_address("x", "0X60f3f640a8508fC6a86d45DF051962668E1e8AC").is_err());
+        assert!(parse_address("x", "0x60f3f640a8508fC6a86d45DF051962668E1e8A").is_err());
+    }
+
+    #[test]
+    fn core_hours_scale_with_attempts_and_threads() {
+        // an hour at the rate on 4 threads is 4 core-hours
+        assert_eq!(core_hours(3_600_000_000.0, 1_000_000.0, 4), 4.0);
+        // 16^7 attempts for a 7th zero at a million a second, 268.435456s on 8 threads
+        let seventh = core_hours(16_f64.powi(7), 1_000_000.0, 8);
+        assert!((seventh - 268.435456 * 8.0 / 3600.0).abs() < 1e-12);
+        // each more zero costs 16 times the last
+        let eighth = core_hours(ScoreMode::LeadingZeros.expected_attempts(8), 1_000_000.0, 8);
+        assert!((eighth / seventh - 16.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn recover_hash_finds_the_hash_among_the_candidates() {
+        // example 5 of EIP-1014
+        let factory: Address = "0x00000000000000000000000000000000deadbeef"
+            .parse()
+            .unwrap();
+        let salt_n = U256::from(0xcafe_babe_u64);
+        let address: Address = "0x60f3f640a8508fC6a86d45DF051962668E1e8AC7"
+            .parse()
+            .unwrap();
+        let path = temp_file(
+            "hash-list",
+            b"# keccak256 of empty bytes\n\
+              0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470\n\
+              \n",
+        );
+        let listed = read_hash_list(&path);
+        fs::remove_file(&path).unwrap();
+        let mut candidates = listed.unwrap();
+        // the hash of 0xdeadbeef among those of its neighbours
+        for init_code in [
+            &[0xde_u8][..],
+            &[0xde, 0xad],
+            &[0xde, 0xad, 0xbe],
+            &[0xde, 0xad, 0xbe, 0xef],
+            &[0xde, 0xad, 0xbe, 0xef, 0x00],
+        ] {
+            candidates.push(U256::from_big_endian(&ethers_core::utils::keccak256(
+                init_code,
+            )));
+        }
+        assert_eq!(candidates.len(), 6);
+
+        let recovered = recover_init_code_hash(factory, salt_n, address, &candidates);
+        assert_eq!(recovered, Some(candidates[4]));
+        // the right hash with another salt or factory derives another address
+        assert_eq!(
+            recover_init_code_hash(factory, salt_n + 1, address, &candidates),
+            None
+        );
+        assert_eq!(
+            recover_init_code_hash(Address::zero(), salt_n, address, &candidates),
+            None
+        );
+        candidates.remove(4);
+        assert_eq!(
+            recover_init_code_hash(factory, salt_n, address, &candidates),
+            None
+        );
+    }
+
+    #[test]
+    fn preimage_is_laid_out_as_labelled() {
+        // example 5 of EIP-1014
+        let factory: Address = "0x00000000000000000000000000000000deadbeef"
+            .parse()
+            .unwrap();
+        let salt = bytes32(U256::from(0xcafe_babe_u64));
+        let init_code_hash = ethers_core::utils::keccak256([0xde, 0xad, 0xbe, 0xef]);
+        let preimage = create2_preimage(factory, &salt, &init_code_hash);
+        assert_eq!(preimage.len(), 85);
+
+        let expected = [
+            "ff",
+            "00000000000000000000000000000000deadbeef",
+            "00000000000000000000000000000000000000000000000000000000cafebabe",
+            "d4fd4e189132273036449fc9e11198c739161b4c0116a9a2dccdfa1c492006f1",
+        ];
+        let mut end = 0;
+        for ((label, range), segment) in CREATE2_PREIMAGE_LAYOUT.into_iter().zip(expected) {
+            // the segments follow each other without a gap
+            assert_eq!(range.start, end, "{}", label);
+            end = range.end;
+            assert_eq!(hex::encode(&preimage[range]), segment, "{}", label);
+        }
+        assert_eq!(end, preimage.len());
+        let address: Address = "0x60f3f640a8508fC6a86d45DF051962668E1e8AC7"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Address::from_slice(&ethers_core::utils::keccak256(preimage)[12..]),
+            address
+        );
+    }
+}